@@ -18,6 +18,19 @@ vec3 F_Schlick(float VdotH, vec3 F0, vec3 F90)
     return F0 + (F90 - F0) * pow(1.0 - VdotH, 5.0);
 }
 
+// Charlie sheen distribution, Estevez and Kulla 2017
+float D_Charlie(float sheenRoughness, float NdotH)
+{
+    float invAlpha = 1.0 / (sheenRoughness * sheenRoughness);
+    float sin2h = max(1.0 - NdotH * NdotH, 0.0078125);
+    return (2.0 + invAlpha) * pow(sin2h, invAlpha * 0.5) / (2.0 * PI);
+}
+
+float V_Neubelt(float NdotL, float NdotV)
+{
+    return clamp(1.0 / (4.0 * (NdotL + NdotV - NdotL * NdotV)), 0.0, 1.0);
+}
+
 float ShlickGGX(float NdotV, float roughness)
 {
     float r = roughness + 1;
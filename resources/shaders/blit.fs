@@ -0,0 +1,11 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+
+void main()
+{
+    FragColor = vec4(texture(source, TexCoord).rgb, 1.0);
+}
@@ -0,0 +1,60 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+uniform vec2 sourceTexelSize;
+// the first level weighs the samples by their brightness so single bright pixels
+// do not turn into flickering blobs
+uniform int firstLevel;
+
+// 13 tap filter from Jorge Jimenez's "Next Generation Post Processing in Call of Duty:
+// Advanced Warfare", five overlapping 2x2 boxes
+vec3 sampleOffset(float x, float y)
+{
+    return texture(source, TexCoord + vec2(x, y) * sourceTexelSize).rgb;
+}
+
+float karisWeight(vec3 color)
+{
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return 1.0 / (1.0 + luma);
+}
+
+vec3 weighted(vec3 a, vec3 b, vec3 c, vec3 d)
+{
+    if (firstLevel == 0) {
+        return (a + b + c + d) * 0.25;
+    }
+    float wa = karisWeight(a);
+    float wb = karisWeight(b);
+    float wc = karisWeight(c);
+    float wd = karisWeight(d);
+    return (a * wa + b * wb + c * wc + d * wd) / (wa + wb + wc + wd);
+}
+
+void main()
+{
+    vec3 a = sampleOffset(-2.0, 2.0);
+    vec3 b = sampleOffset(0.0, 2.0);
+    vec3 c = sampleOffset(2.0, 2.0);
+    vec3 d = sampleOffset(-2.0, 0.0);
+    vec3 e = sampleOffset(0.0, 0.0);
+    vec3 f = sampleOffset(2.0, 0.0);
+    vec3 g = sampleOffset(-2.0, -2.0);
+    vec3 h = sampleOffset(0.0, -2.0);
+    vec3 i = sampleOffset(2.0, -2.0);
+    vec3 j = sampleOffset(-1.0, 1.0);
+    vec3 k = sampleOffset(1.0, 1.0);
+    vec3 l = sampleOffset(-1.0, -1.0);
+    vec3 m = sampleOffset(1.0, -1.0);
+
+    vec3 color = weighted(j, k, l, m) * 0.5
+        + weighted(a, b, d, e) * 0.125
+        + weighted(b, c, e, f) * 0.125
+        + weighted(d, e, g, h) * 0.125
+        + weighted(e, f, h, i) * 0.125;
+
+    FragColor = vec4(max(color, vec3(0.0001)), 1.0);
+}
@@ -0,0 +1,27 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+// in uv units so every level spreads over the same part of the screen
+uniform float filterRadius;
+
+// 3x3 tent filter, added on top of the next larger level
+void main()
+{
+    float x = filterRadius;
+    float y = filterRadius;
+
+    vec3 color = texture(source, TexCoord).rgb * 4.0;
+    color += (texture(source, TexCoord + vec2(-x, 0.0)).rgb
+        + texture(source, TexCoord + vec2(x, 0.0)).rgb
+        + texture(source, TexCoord + vec2(0.0, -y)).rgb
+        + texture(source, TexCoord + vec2(0.0, y)).rgb) * 2.0;
+    color += texture(source, TexCoord + vec2(-x, -y)).rgb
+        + texture(source, TexCoord + vec2(x, -y)).rgb
+        + texture(source, TexCoord + vec2(-x, y)).rgb
+        + texture(source, TexCoord + vec2(x, y)).rgb;
+
+    FragColor = vec4(color / 16.0, 1.0);
+}
@@ -8,12 +8,31 @@ struct Light {
     vec4 color;
 };
 
+#define ALPHA_MODE_OPAQUE 0
+#define ALPHA_MODE_MASK 1
+#define ALPHA_MODE_BLEND 2
+
 struct Material {
     vec3 color;
+    float alpha;
     float roughness;
     float metallic;
     float ao;
     float ior;
+    vec3 emissive;
+    float normalScale;
+    float occlusionStrength;
+    int alphaMode;
+    float alphaCutoff;
+    float emissiveStrength;
+    float clearcoatFactor;
+    float clearcoatRoughness;
+    float clearcoatNormalScale;
+    vec3 sheenColor;
+    float sheenRoughness;
+    float transmissionFactor;
+    float specularFactor;
+    vec3 specularColor;
 };
 
 
@@ -31,6 +50,37 @@ uniform sampler2D u_emissiveMap;
 uniform sampler2D u_brdfMap;
 uniform samplerCube u_irradianceMap;
 uniform samplerCube u_prefilterMap;
+uniform sampler2D u_occlusionMap;
+uniform sampler2D u_clearcoatMap;
+uniform sampler2D u_clearcoatRoughnessMap;
+uniform sampler2D u_clearcoatNormalMap;
+uniform sampler2D u_sheenColorMap;
+uniform sampler2D u_sheenRoughnessMap;
+uniform sampler2D u_transmissionMap;
+uniform sampler2D u_specularMap;
+uniform sampler2D u_specularColorMap;
+
+// KHR_texture_transform and the uv set of every map, indexed by texture slot
+struct TextureMap {
+    int texCoord;
+    mat3 transform;
+};
+
+uniform TextureMap u_textureMaps[16];
+
+#define ALBEDO_SLOT 0
+#define NORMAL_SLOT 1
+#define METALLIC_SLOT 2
+#define EMISSIVE_SLOT 3
+#define OCCLUSION_SLOT 7
+#define CLEARCOAT_SLOT 8
+#define CLEARCOAT_ROUGHNESS_SLOT 9
+#define CLEARCOAT_NORMAL_SLOT 10
+#define SHEEN_COLOR_SLOT 11
+#define SHEEN_ROUGHNESS_SLOT 12
+#define TRANSMISSION_SLOT 13
+#define SPECULAR_SLOT 14
+#define SPECULAR_COLOR_SLOT 15
 
 struct PBRInfo {
     vec3 baseColor;
@@ -44,6 +94,8 @@ struct PBRInfo {
 in vec3 vertex_normal;
 in vec3 vertex_position;
 in vec2 vertex_tex_coord;
+in vec2 vertex_tex_coord1;
+in vec4 vertex_tangent;
 
 out vec4 FragColor;
 
@@ -52,47 +104,104 @@ vec3 getSurfaceRelectance(inout PBRInfo surface, float NdotL, float NdotH, float
     return vec3(1.0);
 }
 
-vec3 getNormal(vec3 view)
+vec2 mapUV(int slot)
 {
-    vec3 tangentNormal = texture2D(u_normalMap, vertex_tex_coord).rgb  * 2.0 - 1.0;
-
-    //tangentNormal = tangentNormal * 255./127. - 128./127.;
-    vec3 q1 = dFdx(view);
-    vec3 q2 = dFdy(view);
-    vec2 st1 = dFdx(vertex_tex_coord);
-    vec2 st2 = dFdy(vertex_tex_coord);
+    TextureMap map = u_textureMaps[slot];
+    vec2 uv = map.texCoord == 1 ? vertex_tex_coord1 : vertex_tex_coord;
+    return (map.transform * vec3(uv, 1.0)).xy;
+}
 
+vec3 getNormal(sampler2D normalMap, vec2 uv, float normalScale, vec3 view)
+{
+    vec3 tangentNormal = texture2D(normalMap, uv).rgb  * 2.0 - 1.0;
+    tangentNormal.xy *= normalScale;
     vec3 N = normalize(vertex_normal);
-    vec3 T = normalize(q1 * st2.t - q2 * st1.t);
-    vec3 B = normalize(cross(N,T));
 
-    mat3 TBN = mat3(T, B, N);
-    return normalize(TBN * tangentNormal);
+    // only double sided materials are drawn without back face culling
+    if (!gl_FrontFacing)
+    {
+        N = -N;
+    }
 
-    //return getPerturbNormal(vNormal, vPosition, TexCoord);
+    vec3 T;
+    vec3 B;
+    if (dot(vertex_tangent.xyz, vertex_tangent.xyz) > 0.000001)
+    {
+        // vertex tangent frame, re-orthogonalized after interpolation
+        T = normalize(vertex_tangent.xyz - N * dot(N, vertex_tangent.xyz));
+        B = cross(N, T) * vertex_tangent.w;
+    }
+    else
+    {
+        // shapes without tangents fall back to screen space derivatives
+        vec3 q1 = dFdx(view);
+        vec3 q2 = dFdy(view);
+        vec2 st1 = dFdx(uv);
+        vec2 st2 = dFdy(uv);
+
+        T = normalize(q1 * st2.t - q2 * st1.t);
+        B = normalize(cross(N,T));
+    }
 
+    mat3 TBN = mat3(T, B, N);
+    return normalize(TBN * tangentNormal);
 }
 
 void main() {
 
+    vec4 albedoSample = texture(u_albedoMap, mapUV(ALBEDO_SLOT));
+    float alpha = albedoSample.a * material.alpha;
+    if (material.alphaMode == ALPHA_MODE_MASK)
+    {
+        if (alpha < material.alphaCutoff)
+        {
+            discard;
+        }
+        alpha = 1.0;
+    }
+    else if (material.alphaMode == ALPHA_MODE_OPAQUE)
+    {
+        alpha = 1.0;
+    }
+
     PBRInfo surface;
-    surface.baseColor = texture(u_albedoMap, vertex_tex_coord).rgb * material.color;
+    surface.baseColor = albedoSample.rgb * material.color;
     surface.roughness = material.roughness;
     surface.metallic = material.metallic;
 
-    vec4 materialRoughnessSample = texture(u_metallicMap, vertex_tex_coord);
+    vec4 materialRoughnessSample = texture(u_metallicMap, mapUV(METALLIC_SLOT));
     surface.roughness *= materialRoughnessSample.g;
     surface.metallic *= materialRoughnessSample.b;
-    vec3 F0 = vec3(0.04);
 
-    surface.f0 =  mix(F0, surface.baseColor, surface.metallic);
+    // KHR_materials_ior and KHR_materials_specular only change the dielectric reflectance
+    float specularWeight = material.specularFactor * texture(u_specularMap, mapUV(SPECULAR_SLOT)).a;
+    vec3 specularTint = material.specularColor * texture(u_specularColorMap, mapUV(SPECULAR_COLOR_SLOT)).rgb;
+    float dielectricF0 = pow((material.ior - 1.0) / (material.ior + 1.0), 2.0);
+    vec3 F0 = min(vec3(dielectricF0) * specularTint, vec3(1.0)) * specularWeight;
+
+    surface.f0 = mix(F0, surface.baseColor, surface.metallic);
+    surface.f90 = mix(vec3(specularWeight), vec3(1.0), surface.metallic);
+
+    float clearcoat = material.clearcoatFactor * texture(u_clearcoatMap, mapUV(CLEARCOAT_SLOT)).r;
+    float clearcoatRoughness = material.clearcoatRoughness * texture(u_clearcoatRoughnessMap, mapUV(CLEARCOAT_ROUGHNESS_SLOT)).g;
+    clearcoatRoughness = max(clearcoatRoughness, 0.01);
+
+    vec3 sheenColor = material.sheenColor * texture(u_sheenColorMap, mapUV(SHEEN_COLOR_SLOT)).rgb;
+    float sheenRoughness = material.sheenRoughness * texture(u_sheenRoughnessMap, mapUV(SHEEN_ROUGHNESS_SLOT)).a;
+    sheenRoughness = max(sheenRoughness, 0.07);
+    // energy the sheen layer takes away from the base, a constant estimate of its albedo
+    float sheenScaling = 1.0 - max(sheenColor.r, max(sheenColor.g, sheenColor.b)) * 0.157;
+
+    float transmission = material.transmissionFactor * texture(u_transmissionMap, mapUV(TRANSMISSION_SLOT)).r;
 
     vec3 lo = vec3(0.0);
 
     vec3 V = normalize(camera_position - vertex_position);
-    vec3 N = getNormal(-V);
+    vec3 N = getNormal(u_normalMap, mapUV(NORMAL_SLOT), material.normalScale, -V);
+    vec3 clearcoatN = getNormal(u_clearcoatNormalMap, mapUV(CLEARCOAT_NORMAL_SLOT), material.clearcoatNormalScale, -V);
     vec3 reflection = -reflect(V, N);
     float NdotV = max(abs(dot(N, V)), 0.001);
+    float clearcoatNdotV = max(abs(dot(clearcoatN, V)), 0.001);
 
     for (int index = 0; index < 4; index++)
     {
@@ -101,9 +210,7 @@ void main() {
         vec3 H = normalize(V + L);
 
         float NdotL = max(dot(N, L), 0.0);
-        float LdotN = clamp(dot(N, L), 0.0, 1.0);
         float NdotH = max(dot(N, H), 0.01);
-        float LdotH = max(dot(L, H), 0.0);
         float VdotH = max(dot(V, H), 0.0);
 
         // light properties
@@ -113,14 +220,27 @@ void main() {
         vec3 radiance = light.color.rgb * attenuation * light.position.w;
 
         float D = NDF(NdotH, surface.roughness);
-        float G = G_SchlicksmithGGX(NdotL, NdotH, surface.roughness);
-        vec3  F = F_Schlick2(NdotH, surface.f0);
+        float G = G_SchlicksmithGGX(NdotL, NdotV, surface.roughness);
+        vec3  F = F_Schlick(VdotH, surface.f0, surface.f90);
 
         vec3 Fr =  (D * G * F) / (4.0 * NdotL * NdotV + 0.00001);
 
-        vec3 Fd = (1.0 - F);
+        // transmitted light leaves through the back, so it no longer scatters diffusely
+        vec3 Fd = (1.0 - F) * (1.0 - transmission) * (1.0 - surface.metallic);
         Fd = Fd * surface.baseColor / PI;
-        lo += (Fr + Fd) * radiance * NdotL;
+
+        vec3 sheen = sheenColor * D_Charlie(sheenRoughness, NdotH) * V_Neubelt(NdotL, NdotV);
+        vec3 base = ((Fr + Fd) * sheenScaling + sheen) * NdotL;
+
+        // clearcoat is a second dielectric layer over the base
+        float clearcoatNdotL = max(dot(clearcoatN, L), 0.0);
+        float clearcoatNdotH = max(dot(clearcoatN, H), 0.01);
+        vec3 Fc = F_Schlick(VdotH, vec3(0.04), vec3(1.0)) * clearcoat;
+        float Dc = NDF(clearcoatNdotH, clearcoatRoughness);
+        float Gc = G_SchlicksmithGGX(clearcoatNdotL, clearcoatNdotV, clearcoatRoughness);
+        vec3 clearcoatSpecular = (Dc * Gc * Fc) / (4.0 * clearcoatNdotL * clearcoatNdotV + 0.00001);
+
+        lo += (base * (1.0 - Fc) + clearcoatSpecular * clearcoatNdotL) * radiance;
 
         vec3 specularLightColor = textureLod(u_prefilterMap, reflection, surface.roughness * 4.0).rgb;
         vec2 brdfColor = texture(u_brdfMap, vec2(NdotV, surface.roughness)).rg;
@@ -129,15 +249,24 @@ void main() {
         //lo += specularColor;
     }
 
-     vec3 irradianceColor = texture(u_irradianceMap, N).rgb;
-     lo += irradianceColor * surface.baseColor;
+    // occlusion only applies to the indirect lighting
+    float occlusion = texture(u_occlusionMap, mapUV(OCCLUSION_SLOT)).r;
+    occlusion = mix(1.0, occlusion, material.occlusionStrength) * material.ao;
+
+    // without a copy of the scene behind the surface the environment is seen through it
+    vec3 refraction = refract(-V, N, 1.0 / material.ior);
+    vec3 transmittedColor = textureLod(u_prefilterMap, refraction, surface.roughness * 4.0).rgb * surface.baseColor;
+
+    vec3 irradianceColor = texture(u_irradianceMap, N).rgb;
+    vec3 diffuseColor = mix(irradianceColor * surface.baseColor, transmittedColor, transmission);
+    lo += diffuseColor * sheenScaling * occlusion;
 
 
     vec3 ambient = vec3(0.08) * surface.baseColor;
 
-    vec3 emissive = texture(u_emissiveMap, vertex_tex_coord).rgb * 1.0;
+    vec3 emissive = texture(u_emissiveMap, mapUV(EMISSIVE_SLOT)).rgb * material.emissive * material.emissiveStrength;
     lo += emissive;
 
     //lo += ambient;
-    FragColor = vec4(lo, material.ao);
+    FragColor = vec4(lo, alpha);
 }
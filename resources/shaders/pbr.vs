@@ -3,6 +3,8 @@
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec3 aNormal;
 layout (location = 2) in vec2 aTexCoord;
+layout (location = 3) in vec4 aTangent;
+layout (location = 6) in vec2 aTexCoord1;
 
 uniform mat4 model;
 uniform mat4 projection;
@@ -11,11 +13,15 @@ uniform mat4 view;
 out vec3 vertex_normal;
 out vec3 vertex_position;
 out vec2 vertex_tex_coord;
+out vec2 vertex_tex_coord1;
+out vec4 vertex_tangent;
 
 void main() {
     mat3 model_mat3 = mat3(model);
     vertex_position = vec3(model * vec4(aPos, 1.0));
     vertex_normal = normalize(model_mat3 * aNormal);
     vertex_tex_coord = aTexCoord;
+    vertex_tex_coord1 = aTexCoord1;
+    vertex_tangent = vec4(model_mat3 * aTangent.xyz, aTangent.w);
     gl_Position = projection * view * model * vec4(aPos, 1.0f);
 }
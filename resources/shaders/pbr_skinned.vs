@@ -0,0 +1,47 @@
+#version 330 core
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoord;
+layout (location = 3) in vec4 aTangent;
+layout (location = 4) in uvec4 aJoints;
+layout (location = 5) in vec4 aWeights;
+layout (location = 6) in vec2 aTexCoord1;
+
+uniform mat4 model;
+uniform mat4 projection;
+uniform mat4 view;
+// the joint palette of the skin, one column per texel
+uniform samplerBuffer u_jointMatrices;
+
+out vec3 vertex_normal;
+out vec3 vertex_position;
+out vec2 vertex_tex_coord;
+out vec2 vertex_tex_coord1;
+out vec4 vertex_tangent;
+
+mat4 jointMatrix(uint joint) {
+    int column = int(joint) * 4;
+    return mat4(
+        texelFetch(u_jointMatrices, column),
+        texelFetch(u_jointMatrices, column + 1),
+        texelFetch(u_jointMatrices, column + 2),
+        texelFetch(u_jointMatrices, column + 3));
+}
+
+void main() {
+    mat4 skin_matrix =
+        aWeights.x * jointMatrix(aJoints.x) +
+        aWeights.y * jointMatrix(aJoints.y) +
+        aWeights.z * jointMatrix(aJoints.z) +
+        aWeights.w * jointMatrix(aJoints.w);
+
+    mat4 skinned_model = model * skin_matrix;
+    mat3 model_mat3 = mat3(skinned_model);
+    vertex_position = vec3(skinned_model * vec4(aPos, 1.0));
+    vertex_normal = normalize(model_mat3 * aNormal);
+    vertex_tex_coord = aTexCoord;
+    vertex_tex_coord1 = aTexCoord1;
+    vertex_tangent = vec4(model_mat3 * aTangent.xyz, aTangent.w);
+    gl_Position = projection * view * skinned_model * vec4(aPos, 1.0f);
+}
@@ -0,0 +1,23 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+uniform float time;
+
+// how far red and blue split at the corners, in uv units
+uniform float strength; // 0.0 0.02 0.004
+
+void main()
+{
+    // lenses separate the channels more the further they are from the center
+    vec2 offset = (TexCoord - 0.5) * 2.0 * strength;
+
+    float red = texture(source, TexCoord + offset).r;
+    float green = texture(source, TexCoord).g;
+    float blue = texture(source, TexCoord - offset).b;
+
+    FragColor = vec4(red, green, blue, 1.0);
+}
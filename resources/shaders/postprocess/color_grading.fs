@@ -0,0 +1,32 @@
+#version 330 core
+
+// post processing effects get the output of the previous pass in source, already
+// tone mapped and sRGB encoded, and the size of a pixel and the time in seconds.
+// uniform floats with a "// min max default" comment show up as sliders in the ui.
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+uniform float time;
+
+// the .cube table, red along x
+uniform sampler3D lut;
+uniform float lutSize;
+uniform vec3 lutDomainMin;
+uniform vec3 lutDomainMax;
+
+uniform float strength; // 0.0 1.0 1.0
+
+void main()
+{
+    vec3 color = texture(source, TexCoord).rgb;
+
+    // sample the centers of the first and last texels at the ends of the domain
+    vec3 position = clamp((color - lutDomainMin) / (lutDomainMax - lutDomainMin), 0.0, 1.0);
+    vec3 coordinate = position * ((lutSize - 1.0) / lutSize) + 0.5 / lutSize;
+    vec3 graded = texture(lut, coordinate).rgb;
+
+    FragColor = vec4(mix(color, graded, strength), 1.0);
+}
@@ -0,0 +1,34 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+uniform float time;
+
+uniform float intensity; // 0.0 0.3 0.05
+// grain size in pixels
+uniform float size; // 1.0 4.0 1.5
+
+float hash(vec3 p)
+{
+    p = fract(p * vec3(0.1031, 0.1030, 0.0973));
+    p += dot(p, p.yzx + 33.33);
+    return fract((p.x + p.y) * p.z);
+}
+
+void main()
+{
+    vec3 color = texture(source, TexCoord).rgb;
+
+    // a new pattern every frame, 24 times a second like film
+    vec2 cell = floor(gl_FragCoord.xy / size);
+    float noise = hash(vec3(cell, floor(time * 24.0))) - 0.5;
+
+    // grain shows most in the mid tones
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float response = 1.0 - abs(luma * 2.0 - 1.0);
+
+    FragColor = vec4(max(color + noise * intensity * response, 0.0), 1.0);
+}
@@ -0,0 +1,26 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+uniform float time;
+
+uniform float intensity; // 0.0 1.0 0.4
+// distance from the center where the darkening starts, 0.5 is the middle of an edge
+uniform float radius; // 0.0 1.0 0.45
+uniform float softness; // 0.01 1.0 0.4
+
+void main()
+{
+    vec3 color = texture(source, TexCoord).rgb;
+
+    // round on screen whatever the aspect ratio
+    vec2 offset = TexCoord - 0.5;
+    offset.x *= texelSize.y / texelSize.x;
+    float fromCenter = length(offset);
+    float darkening = smoothstep(radius, radius + softness, fromCenter) * intensity;
+
+    FragColor = vec4(color * (1.0 - darkening), 1.0);
+}
@@ -0,0 +1,121 @@
+#version 330 core
+
+out vec4 FragColor;
+in vec2 TexCoord;
+
+// linear radiance of the scene pass
+uniform sampler2D hdrTexture;
+// 2^EV, applied before the curve
+uniform float exposure;
+// matches render::tonemap::Operator
+uniform int toneMapOperator;
+
+#define REINHARD 0
+#define ACES 1
+#define AGX 2
+#define UNCHARTED2 3
+
+vec3 reinhard(vec3 color)
+{
+    return color / (1.0 + color);
+}
+
+// Stephen Hill's fit of the ACES RRT and ODT, sRGB primaries in and out
+const mat3 ACES_INPUT = mat3(
+    0.59719, 0.07600, 0.02840,
+    0.35458, 0.90834, 0.13383,
+    0.04823, 0.01566, 0.83777);
+
+const mat3 ACES_OUTPUT = mat3(
+    1.60475, -0.10208, -0.00327,
+    -0.53108, 1.10813, -0.07276,
+    -0.07367, -0.00605, 1.07602);
+
+vec3 acesFilmic(vec3 color)
+{
+    color = ACES_INPUT * color;
+    vec3 a = color * (color + 0.0245786) - 0.000090537;
+    vec3 b = color * (0.983729 * color + 0.4329510) + 0.238081;
+    return clamp(ACES_OUTPUT * (a / b), 0.0, 1.0);
+}
+
+// Benjamin Wrensch's fit of the AgX base look
+const mat3 AGX_INSET = mat3(
+    0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+    0.0784335999999992, 0.878468636469772, 0.0784336,
+    0.0792237451477643, 0.0791661274605434, 0.879142973793104);
+
+const mat3 AGX_OUTSET = mat3(
+    1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+    -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+    -0.0990297440797205, -0.0989611768448433, 1.15107367264116);
+
+const float AGX_MIN_EV = -12.47393;
+const float AGX_MAX_EV = 4.026069;
+
+vec3 agxContrast(vec3 x)
+{
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2
+        - 40.14 * x4 * x
+        + 31.96 * x4
+        - 6.868 * x2 * x
+        + 0.4298 * x2
+        + 0.1191 * x
+        - 0.00232;
+}
+
+vec3 agx(vec3 color)
+{
+    color = AGX_INSET * max(color, vec3(1e-10));
+    color = clamp(log2(color), AGX_MIN_EV, AGX_MAX_EV);
+    color = (color - AGX_MIN_EV) / (AGX_MAX_EV - AGX_MIN_EV);
+    color = AGX_OUTSET * agxContrast(color);
+    // the curve ends in display encoding, decode it so every operator returns linear
+    return pow(clamp(color, 0.0, 1.0), vec3(2.2));
+}
+
+// John Hable's filmic curve
+vec3 hable(vec3 x)
+{
+    const float A = 0.15;
+    const float B = 0.50;
+    const float C = 0.10;
+    const float D = 0.20;
+    const float E = 0.02;
+    const float F = 0.30;
+    return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+}
+
+vec3 uncharted2(vec3 color)
+{
+    const float exposureBias = 2.0;
+    const float whitePoint = 11.2;
+    return hable(color * exposureBias) / hable(vec3(whitePoint));
+}
+
+vec3 linearToSrgb(vec3 color)
+{
+    color = clamp(color, 0.0, 1.0);
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(color, vec3(0.0031308)));
+}
+
+void main()
+{
+    vec3 color = texture(hdrTexture, TexCoord).rgb * exposure;
+
+    if (toneMapOperator == ACES) {
+        color = acesFilmic(color);
+    } else if (toneMapOperator == AGX) {
+        color = agx(color);
+    } else if (toneMapOperator == UNCHARTED2) {
+        color = uncharted2(color);
+    } else {
+        color = reinhard(color);
+    }
+
+    FragColor = vec4(linearToSrgb(color), 1.0);
+}
@@ -8,6 +8,8 @@
 
 use core::f32;
 use std::convert::From;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use egui;
 use gl;
@@ -16,19 +18,27 @@ use glfw::Context;
 
 use crate::{
     clock, file_watcher,
-    render::{self, ModelCache},
-    ui,
+    render::{
+        self,
+        framebuffer::{DepthFormat, FrameBuffer},
+        ModelCache,
+    },
+    scene, ui,
 };
 
 pub struct Entity {
     pub transform: iml::Transform,
     pub model: render::model::ModelPointer,
+    // kept so the scene can be saved back
+    pub source: scene::ModelSource,
+    pub material: Option<scene::MaterialOverride>,
 }
 
 struct RenderArgs<'e> {
     entities: &'e Vec<Entity>,
     view_matrix: &'e iml::Mat4,
     projection_matrix: &'e iml::Mat4,
+    joint_palette: &'e render::buffer::TextureBuffer,
 }
 
 struct FPSCamera {
@@ -42,18 +52,28 @@ struct FPSCamera {
 }
 
 impl FPSCamera {
-    fn new() -> FPSCamera {
+    fn new(desc: &scene::CameraDesc) -> FPSCamera {
+        let [x, y, z] = desc.position;
         FPSCamera {
-            position: iml::Point3::new(-2.0, 5.0, -2.0),
-            orientation: iml::Quat::from(iml::Vec3::new(40.0, 50.0, 0.0).to_radians()),
-            fov: 90.0,
-            yaw: 40.0,
-            pitch: 50.0,
+            position: iml::Point3::new(x, y, z),
+            orientation: iml::Quat::from(iml::Vec3::new(desc.pitch, desc.yaw, 0.0).to_radians()),
+            fov: desc.fov,
+            yaw: desc.yaw,
+            pitch: desc.pitch,
             last_cursor: iml::Vec2::new(0.0, 0.0),
             repeat: false,
         }
     }
 
+    fn desc(&self) -> scene::CameraDesc {
+        scene::CameraDesc {
+            position: [self.position.x, self.position.y, self.position.z],
+            yaw: self.yaw,
+            pitch: self.pitch,
+            fov: self.fov,
+        }
+    }
+
     fn projection_matrix(&self, width: f32, height: f32, near: f32, far: f32) -> iml::Mat4 {
         let apect_ratio = width / height;
         iml::shared::perspective(self.fov.to_radians(), apect_ratio, near, far)
@@ -173,14 +193,57 @@ fn glfw_key_to_egui_key(key: glfw::Key) -> Option<egui::Key> {
     }
 }
 
+// decoding is mostly image work, a couple of threads keep a scene of models busy
+const LOADER_THREADS: usize = 2;
+// bytes of buffer and texture data created on the GPU per frame
+const UPLOAD_BUDGET: usize = 16 * 1024 * 1024;
+// size of the lights array in pbr.fs
+const MAX_LIGHTS: usize = 4;
+// texture unit of the skinning palette, after the material and environment maps
+const JOINT_PALETTE_SLOT: u32 = 16;
+// size of the u_textureMaps array in pbr.fs, one per texture slot
+const TEXTURE_MAP_COUNT: usize = 16;
+
 type WindowEvents = std::sync::mpsc::Receiver<(f64, glfw::WindowEvent)>;
 pub struct App {
     window: glfw::Window,
     events: WindowEvents,
     glfw: glfw::Glfw,
     model_cache: render::ModelCache,
-    texture_cache: render::texture::TextureCache,
+    texture_cache: Arc<render::texture::TextureCache>,
     debug_ui: ui::Ui,
+    scene: scene::Scene,
+    save_path: PathBuf,
+    run_options: RunOptions,
+}
+
+// how the viewer runs, set from the command line
+pub struct RunOptions {
+    pub vsync: bool,
+    // exit after this many frames, counted once every asset is loaded
+    pub frames: Option<u32>,
+    // png or exr written from the last frame before exiting, {frame} in the name
+    // writes every counted frame instead
+    pub screenshot: Option<PathBuf>,
+    // renders into an offscreen target behind an invisible window
+    pub headless: bool,
+    // .cube table for the color grading effect
+    pub lut: Option<PathBuf>,
+    // fragment shaders run after the built in post processing effects
+    pub effects: Vec<PathBuf>,
+}
+
+// software rasterizers like llvmpipe may stop at 4.5, nothing needs more than that
+const CONTEXT_VERSIONS: [(u32, u32); 2] = [(4, 6), (4, 5)];
+// errors are only printed, a context version that is not supported fails the window
+// creation and the next version is tried
+const GLFW_ERRORS: Option<glfw::ErrorCallback<()>> = Some(glfw::Callback {
+    f: print_glfw_error,
+    data: (),
+});
+
+fn print_glfw_error(_: glfw::Error, description: String, _: &()) {
+    eprintln!("glfw: {}", description);
 }
 
 #[repr(C)]
@@ -193,6 +256,24 @@ impl Light {
     pub fn new(position: iml::Vec4, color: iml::Vec4) -> Light {
         Light { position, color }
     }
+
+    // the intensity lives in the w of the position, the shader ignores the color w
+    fn from_desc(desc: &scene::LightDesc) -> Light {
+        let [x, y, z] = desc.position;
+        let [red, green, blue] = desc.color;
+        Light::new(
+            iml::Vec4::new(x, y, z, desc.intensity),
+            iml::Vec4::new(red, green, blue, desc.intensity),
+        )
+    }
+
+    fn desc(&self) -> scene::LightDesc {
+        scene::LightDesc {
+            position: [self.position.x, self.position.y, self.position.z],
+            color: [self.color.x, self.color.y, self.color.z],
+            intensity: self.position.w,
+        }
+    }
 }
 
 struct LightManager {
@@ -209,21 +290,53 @@ impl LightManager {
     }
 
     fn add(&mut self, light: Light) {
+        if self.lights.len() == MAX_LIGHTS {
+            println!(
+                "only {} lights are supported, skipping the rest",
+                MAX_LIGHTS
+            );
+            return;
+        }
         self.lights.push(light);
     }
+
+    // the shader always reads MAX_LIGHTS lights, the missing ones have no intensity
+    fn update_buffer_data(&mut self) {
+        let data = &mut self.light_buffer.data;
+        data.clear();
+        data.extend_from_slice(to_byte_slice(&self.lights));
+        data.resize(MAX_LIGHTS * std::mem::size_of::<Light>(), 0);
+    }
 }
 
 impl App {
-    pub fn init(width: u32, height: u32) -> App {
-        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-        glfw.window_hint(glfw::WindowHint::ContextVersion(4, 6));
+    pub fn init(
+        scene: scene::Scene,
+        save_path: PathBuf,
+        run_options: RunOptions,
+    ) -> Result<App, String> {
+        let scene::WindowDesc { width, height } = scene.window;
+        let mut glfw = glfw::init(GLFW_ERRORS)
+            .map_err(|error| format!("failed to initialize glfw: {}", error))?;
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
         glfw.window_hint(glfw::WindowHint::OpenGlProfile(
             glfw::OpenGlProfileHint::Core,
         ));
-
-        let (mut window, events) = glfw
-            .create_window(width, height, "PBR Demo", glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window");
+        glfw.window_hint(glfw::WindowHint::Visible(!run_options.headless));
+
+        let (mut window, events) = CONTEXT_VERSIONS
+            .iter()
+            .find_map(|(major, minor)| {
+                glfw.window_hint(glfw::WindowHint::ContextVersion(*major, *minor));
+                glfw.create_window(width, height, "PBR Demo", glfw::WindowMode::Windowed)
+            })
+            .ok_or_else(|| {
+                let (major, minor) = CONTEXT_VERSIONS[CONTEXT_VERSIONS.len() - 1];
+                format!(
+                    "failed to create a window with an OpenGL {}.{} context",
+                    major, minor
+                )
+            })?;
 
         gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
 
@@ -233,15 +346,18 @@ impl App {
             gl::BindVertexArray(vao);
         }
         let model_cache = render::ModelCache::new();
-        let texture_cache = render::texture::TextureCache::new();
-        App {
+        let texture_cache = Arc::new(render::texture::TextureCache::new());
+        Ok(App {
             window,
             events,
             glfw,
             model_cache,
             texture_cache,
             debug_ui: ui::Ui::new(),
-        }
+            scene,
+            save_path,
+            run_options,
+        })
     }
 
     pub fn run(self) {
@@ -252,6 +368,9 @@ impl App {
             mut model_cache,
             texture_cache,
             mut debug_ui,
+            scene,
+            save_path,
+            run_options,
         } = self;
 
         window.set_cursor_pos_polling(true);
@@ -266,11 +385,22 @@ impl App {
             gl::Enable(gl::LINE_SMOOTH);
         }
 
-        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-        let skybox = render::skybox::Skybox::new(
-            "resources/images/IBL/TropicalBeach/TropicalBeach.hdr",
-            &mut model_cache,
-        );
+        if run_options.vsync {
+            glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+        } else {
+            glfw.set_swap_interval(glfw::SwapInterval::None);
+        }
+        let mut asset_loader =
+            render::loader::AssetLoader::new(LOADER_THREADS, Arc::clone(&texture_cache));
+        let mut upload_queue = render::loader::UploadQueue::new(UPLOAD_BUDGET);
+
+        // rendered with black environment maps until the hdr is decoded
+        let mut skybox = render::skybox::Skybox::placeholder(&mut model_cache);
+        let mut environment_pending = false;
+        if let Some(environment) = &scene.environment {
+            asset_loader.load_environment(environment.clone());
+            environment_pending = true;
+        }
         let skybox_pipeline = render::shader::Pipeline::new(
             "resources/shaders/skybox.vs",
             "resources/shaders/skybox.fs",
@@ -281,17 +411,21 @@ impl App {
             file_watcher::FileWatcher::new(String::from("resources/shaders/pbr.fs"));
         let fragment_shader_file: &'static str = "resources/shaders/pbr.fs";
         let vertex_shader_file: &'static str = "resources/shaders/pbr.vs";
-        let mut pipeline =
-            render::shader::Pipeline::new(vertex_shader_file, fragment_shader_file).unwrap();
+        let skinned_vertex_shader_file: &'static str = "resources/shaders/pbr_skinned.vs";
+        let mut pipeline = MaterialPipeline::new(vertex_shader_file, fragment_shader_file).unwrap();
+        let mut skinned_pipeline =
+            MaterialPipeline::new(skinned_vertex_shader_file, fragment_shader_file).unwrap();
 
         let mut clock = clock::Clock::new();
-        let mut camera = FPSCamera::new();
+        let mut camera = FPSCamera::new(&scene.camera);
         window.make_current();
         window.set_key_polling(true);
 
         let mut floor = Entity {
             transform: iml::Transform::default(),
             model: ModelCache::get_shape(render::model::Shape::Cube),
+            source: scene::ModelSource::Shape(render::model::Shape::Cube),
+            material: None,
         };
 
         floor.transform.scale = iml::Vec3::new(100.0, 0.5, 100.0);
@@ -320,112 +454,210 @@ impl App {
         //     }
         // }
 
-        if let Ok(gltf_model) =
-            render::model::load_gltf_model(String::from("resources/glTF-models/DamagedHelmet.glb"))
-        {
+        // entities show a cube until their model is loaded and uploaded
+        let mut pending_entities: Vec<(render::loader::LoadId, usize)> = Vec::new();
+        for entity in &scene.entities {
+            let model = match &entity.model {
+                scene::ModelSource::Shape(shape) => {
+                    let model = ModelCache::get_shape(*shape);
+                    if let Some(material) = &entity.material {
+                        material.apply(&mut model.borrow_mut());
+                    }
+                    model
+                }
+                scene::ModelSource::Path(path) => {
+                    let load_id = asset_loader
+                        .load_model(path.clone(), render::model::LoadOptions::default());
+                    pending_entities.push((load_id, entities.len()));
+                    ModelCache::get_shape(render::model::Shape::Cube)
+                }
+            };
+
             entities.push(Entity {
-                transform: iml::Transform::new(iml::Point3::new(0.0, 0.0, 0.0)),
-                model: gltf_model,
+                transform: entity.transform.transform(),
+                model,
+                source: entity.model.clone(),
+                material: entity.material,
             });
-        } else {
-            println!("failed to load model");
         }
 
         let mut light_manager = LightManager::new();
-        light_manager.add(Light::new(
-            iml::Vec4::new(0.0, 11.0, 0.0, 300.0),
-            iml::Vec4::new(1.0, 1.0, 1.0, 300.0),
-        ));
-        light_manager.add(Light::new(
-            iml::Vec4::new(0.0, 11.0, 15.0, 300.0),
-            iml::Vec4::new(1.0, 1.0, 1.0, 300.0),
-        ));
-        light_manager.add(Light::new(
-            iml::Vec4::new(15.0, 11.0, 0.0, 300.0),
-            iml::Vec4::new(1.0, 1.0, 1.0, 300.0),
-        ));
-        light_manager.add(Light::new(
-            iml::Vec4::new(15.0, 11.0, 15.0, 300.0),
-            iml::Vec4::new(1.0, 1.0, 1.0, 300.0),
-        ));
+        for light in &scene.lights {
+            light_manager.add(Light::from_desc(light));
+        }
 
         let mut light_unifrom_buffer = 0;
-        if light_manager.lights.len() > 0 {
-            light_manager
-                .light_buffer
-                .data
-                .extend_from_slice(to_byte_slice(&light_manager.lights));
+        light_manager.update_buffer_data();
 
-            let light_buffer_size =
-                light_manager.light_buffer.data.len() * std::mem::size_of::<u8>();
-            unsafe {
-                gl::GenBuffers(1, &mut light_unifrom_buffer);
-                gl::BindBuffer(gl::UNIFORM_BUFFER, light_unifrom_buffer);
-                gl::BufferData(
-                    gl::UNIFORM_BUFFER,
-                    light_buffer_size as isize,
-                    light_manager.light_buffer.data.as_ptr().cast(),
-                    gl::STATIC_DRAW,
-                );
+        let light_buffer_size = light_manager.light_buffer.data.len() * std::mem::size_of::<u8>();
+        unsafe {
+            gl::GenBuffers(1, &mut light_unifrom_buffer);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, light_unifrom_buffer);
+            gl::BufferData(
+                gl::UNIFORM_BUFFER,
+                light_buffer_size as isize,
+                light_manager.light_buffer.data.as_ptr().cast(),
+                gl::STATIC_DRAW,
+            );
 
-                gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
 
-                gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, light_unifrom_buffer);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, light_unifrom_buffer);
+        }
+        // skinned draws upload the palette of their skin into it
+        let joint_palette = render::buffer::TextureBuffer::new();
+
+        // the scene is lit in linear space into a float target, the tone mapping pass
+        // writes the display encoded result to the window or the headless target
+        let (width, height) = window.get_size();
+        let mut scene_target = FrameBuffer::new(width as u32, height as u32);
+        scene_target.add_color_texture(0, gl::RGBA16F);
+        scene_target.add_depth_renderbuffer(DepthFormat::Depth24Stencil8);
+        if let Err(error) = scene_target.check() {
+            println!("failed to create scene target: {}", error);
+            return;
+        }
+        let mut offscreen = if run_options.headless {
+            let mut framebuffer = FrameBuffer::new(width as u32, height as u32);
+            framebuffer.add_color_renderbuffer(0, gl::RGBA8);
+            if let Err(error) = framebuffer.check() {
+                println!("failed to create offscreen target: {}", error);
+                return;
+            }
+            Some(framebuffer)
+        } else {
+            None
+        };
+        let output_framebuffer = offscreen
+            .as_ref()
+            .map_or(render::capture::DEFAULT_FRAMEBUFFER, FrameBuffer::id);
+        let mut tone_map = render::tonemap::ToneMapSettings::default();
+        let mut auto_exposure = render::exposure::AutoExposure::new();
+        let mut post_process = match render::postprocess::Chain::new() {
+            Ok(chain) => chain,
+            Err(error) => {
+                println!("failed to create post processing: {}", error);
+                return;
+            }
+        };
+        if let Some(path) = &run_options.lut {
+            if let Err(error) = post_process.set_lut(path) {
+                println!("failed to load lut: {}", error);
+            }
+        }
+        for path in &run_options.effects {
+            if let Err(error) = post_process.add_effect(path) {
+                println!("failed to load effect: {}", error);
             }
         }
+        let mut capture_key_down = false;
 
+        let mut loaded_frames: u32 = 0;
+        // seconds since the first frame, animates effects like film grain
+        let mut time = 0.0;
         while !window.should_close() {
             let delta_time = clock.delta_time();
+            time += delta_time;
 
             file_wather.update(|| {
-                let new_pipeline =
-                    render::shader::Pipeline::new(vertex_shader_file, fragment_shader_file);
+                let new_pipeline = MaterialPipeline::new(vertex_shader_file, fragment_shader_file);
 
                 match new_pipeline {
                     Ok(pipeline_) => pipeline = pipeline_,
                     Err(error) => println!("failed to compile pipeline: {}", error),
                 }
+
+                let new_skinned_pipeline =
+                    MaterialPipeline::new(skinned_vertex_shader_file, fragment_shader_file);
+
+                match new_skinned_pipeline {
+                    Ok(pipeline_) => skinned_pipeline = pipeline_,
+                    Err(error) => println!("failed to compile skinned pipeline: {}", error),
+                }
             });
+
+            for loaded in asset_loader.poll() {
+                match loaded {
+                    render::loader::Loaded::Model(load_id, Ok(model)) => {
+                        for warning in &model.borrow().warnings {
+                            println!("warning: {}", warning);
+                        }
+                        upload_queue.push_model(load_id, model)
+                    }
+                    render::loader::Loaded::Model(load_id, Err(error)) => {
+                        println!("failed to load model: {}", error);
+                        remove_pending_entity(&mut entities, &mut pending_entities, load_id);
+                    }
+                    // rendering the environment maps has to happen here, it takes a frame
+                    render::loader::Loaded::Environment(Ok((pixels, width, height))) => {
+                        environment_pending = false;
+                        skybox = render::skybox::Skybox::from_hdr(
+                            &pixels,
+                            width,
+                            height,
+                            &mut model_cache,
+                        );
+                    }
+                    render::loader::Loaded::Environment(Err(error)) => {
+                        environment_pending = false;
+                        println!("failed to load environment: {}", error)
+                    }
+                }
+            }
+
+            for (load_id, model) in upload_queue.process() {
+                if let Some(position) = pending_entities
+                    .iter()
+                    .position(|(pending, _)| *pending == load_id)
+                {
+                    let (_, index) = pending_entities.remove(position);
+                    if let Some(material) = &entities[index].material {
+                        material.apply(&mut model.borrow_mut());
+                    }
+                    entities[index].model = model;
+                }
+            }
+
+            let window_size = window.get_size();
+            let window_width = window_size.0;
+            let window_height = window_size.1;
+
+            scene_target.resize(window_width as u32, window_height as u32);
+            if let Some(framebuffer) = &mut offscreen {
+                framebuffer.resize(window_width as u32, window_height as u32);
+            }
+            scene_target.bind();
             unsafe {
                 gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
                 gl::ClearColor(0.0, 0.0, 0.0, 1.0);
             }
             let raw_input = App::process_events(&mut glfw, &mut window, &events);
-            if light_manager.lights.len() > 0 {
-                light_manager.light_buffer.data.clear();
-                light_manager
-                    .light_buffer
-                    .data
-                    .extend_from_slice(to_byte_slice(&light_manager.lights));
-
-                let light_buffer_size =
-                    light_manager.light_buffer.data.len() * std::mem::size_of::<u8>();
-                unsafe {
-                    gl::BindBuffer(gl::UNIFORM_BUFFER, light_unifrom_buffer);
-                    gl::BufferData(
-                        gl::UNIFORM_BUFFER,
-                        light_buffer_size as isize,
-                        light_manager.light_buffer.data.as_ptr().cast(),
-                        gl::STATIC_DRAW,
-                    );
+            light_manager.update_buffer_data();
 
-                    gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            let light_buffer_size =
+                light_manager.light_buffer.data.len() * std::mem::size_of::<u8>();
+            unsafe {
+                gl::BindBuffer(gl::UNIFORM_BUFFER, light_unifrom_buffer);
+                gl::BufferData(
+                    gl::UNIFORM_BUFFER,
+                    light_buffer_size as isize,
+                    light_manager.light_buffer.data.as_ptr().cast(),
+                    gl::STATIC_DRAW,
+                );
 
-                    gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, light_unifrom_buffer);
-                }
+                gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+
+                gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, light_unifrom_buffer);
             }
             camera.update(&mut window, 4.0, delta_time);
-            let window_size = window.get_size();
-            let window_width = window_size.0;
-            let window_height = window_size.1;
+            for entity in &entities {
+                entity.model.borrow_mut().update_animation(delta_time);
+            }
 
             let view = camera.view_matrix();
             let projection =
                 camera.projection_matrix(window_width as f32, window_height as f32, 0.3, 700.0);
 
-            unsafe {
-                gl::Viewport(0, 0, window_width as i32, window_height as i32);
-            }
             render_skybox(
                 model_cache.shape(&render::model::Shape::Cube),
                 projection,
@@ -438,18 +670,120 @@ impl App {
                 entities: &entities,
                 view_matrix: &view,
                 projection_matrix: &projection,
+                joint_palette: &joint_palette,
             };
             render_model(
                 &mut render_args,
                 &pipeline,
+                &skinned_pipeline,
                 &texture_cache,
                 &camera,
                 &skybox,
             );
+            post_process.render_hdr(&scene_target, &model_cache);
+
+            if let Some(scene_color) = scene_target.color_texture(0) {
+                // with auto exposure the manual exposure is a compensation on top
+                let mut frame_tone_map = tone_map;
+                if auto_exposure.settings.enabled {
+                    frame_tone_map.exposure += auto_exposure.update(scene_color, delta_time);
+                }
+                post_process.render_display(
+                    scene_color,
+                    &frame_tone_map,
+                    offscreen.as_ref(),
+                    time,
+                    &model_cache,
+                );
+            }
 
-            debug_ui.update(raw_input, &mut light_manager.lights);
-            debug_ui.render(window_width as f32, window_height as f32);
+            // scripted runs only count frames that show the whole scene
+            let loading =
+                environment_pending || !pending_entities.is_empty() || !upload_queue.is_empty();
+            if !loading {
+                loaded_frames += 1;
+            }
+            let finished = run_options
+                .frames
+                .map_or(false, |frames| loaded_frames >= frames);
+            if let Some(path) = &run_options.screenshot {
+                let pattern = path.to_string_lossy();
+                let sequence = pattern.contains("{frame}");
+                if (sequence && !loading) || finished {
+                    let path =
+                        PathBuf::from(pattern.replace("{frame}", &loaded_frames.to_string()));
+                    // exr keeps the linear radiance from before tone mapping
+                    let (framebuffer, encoding) = if is_exr(&path) {
+                        (scene_target.id(), render::capture::ColorEncoding::Linear)
+                    } else {
+                        (output_framebuffer, render::capture::ColorEncoding::Srgb)
+                    };
+                    match render::capture::save_color(
+                        &path,
+                        framebuffer,
+                        window_width as u32,
+                        window_height as u32,
+                        encoding,
+                    ) {
+                        Ok(()) => println!("saved screenshot to {}", path.display()),
+                        Err(error) => println!("failed to save screenshot: {}", error),
+                    }
+                }
+            }
+
+            if !run_options.headless {
+                // F12 saves the frame and its depth without the ui
+                let capture_pressed = window.get_key(glfw::Key::F12) == glfw::Action::Press;
+                if capture_pressed && !capture_key_down {
+                    capture_frame(
+                        output_framebuffer,
+                        scene_target.id(),
+                        window_width as u32,
+                        window_height as u32,
+                    );
+                }
+                capture_key_down = capture_pressed;
+
+                let ui_actions = debug_ui.update(
+                    raw_input,
+                    &mut light_manager.lights,
+                    &mut tone_map,
+                    &mut auto_exposure,
+                    &mut post_process,
+                    &texture_cache.stats(),
+                );
+                if ui_actions.export_scene {
+                    export_scene(&entities, Path::new("scene.glb"));
+                }
+                if ui_actions.save_scene {
+                    let current_scene = scene::Scene {
+                        window: scene::WindowDesc {
+                            width: window_width as u32,
+                            height: window_height as u32,
+                        },
+                        camera: camera.desc(),
+                        environment: scene.environment.clone(),
+                        lights: light_manager.lights.iter().map(Light::desc).collect(),
+                        entities: entities
+                            .iter()
+                            .map(|entity| scene::EntityDesc {
+                                model: entity.source.clone(),
+                                transform: scene::TransformDesc::from_transform(&entity.transform),
+                                material: entity.material,
+                            })
+                            .collect(),
+                    };
+                    match current_scene.save(&save_path) {
+                        Ok(()) => println!("saved scene to {}", save_path.display()),
+                        Err(error) => println!("failed to save scene: {}", error),
+                    }
+                }
+                debug_ui.render(window_width as f32, window_height as f32);
+            }
             window.swap_buffers();
+            if finished {
+                window.set_should_close(true);
+            }
         }
     }
 
@@ -578,7 +912,7 @@ fn render_skybox(
 
     unsafe {
         gl::ActiveTexture(gl::TEXTURE0);
-        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id());
 
         let start_index = sub_mesh.start_index * std::mem::size_of::<u32>();
         gl::DrawElements(
@@ -592,92 +926,366 @@ fn render_skybox(
     }
 }
 
+// the pbr pipeline with the locations of u_textureMaps, looked up once per compile
+struct MaterialPipeline {
+    pipeline: render::shader::Pipeline,
+    texture_maps: [TextureMapLocation; TEXTURE_MAP_COUNT],
+}
+
+#[derive(Copy, Clone, Default)]
+struct TextureMapLocation {
+    tex_coord: i32,
+    transform: i32,
+}
+
+impl MaterialPipeline {
+    fn new(vertex_shader: &str, fragment_shader: &str) -> Result<MaterialPipeline, String> {
+        let pipeline = render::shader::Pipeline::new(vertex_shader, fragment_shader)?;
+        let mut texture_maps = [TextureMapLocation::default(); TEXTURE_MAP_COUNT];
+        for (slot, location) in texture_maps.iter_mut().enumerate() {
+            location.tex_coord =
+                pipeline.uniform_location(&format!("u_textureMaps[{}].texCoord\0", slot));
+            location.transform =
+                pipeline.uniform_location(&format!("u_textureMaps[{}].transform\0", slot));
+        }
+        Ok(MaterialPipeline {
+            pipeline,
+            texture_maps,
+        })
+    }
+}
+
+struct ModelPass<'p> {
+    render_args: &'p RenderArgs<'p>,
+    pipeline: &'p MaterialPipeline,
+    skinned_pipeline: &'p MaterialPipeline,
+    texture_cache: &'p render::texture::TextureCache,
+    camera: &'p FPSCamera,
+    skybox: &'p render::skybox::Skybox,
+}
+
 fn render_model(
     render_args: &RenderArgs,
-    pipeline: &render::shader::Pipeline,
+    pipeline: &MaterialPipeline,
+    skinned_pipeline: &MaterialPipeline,
     texture_cache: &render::texture::TextureCache,
     camera: &FPSCamera,
     skybox: &render::skybox::Skybox,
 ) {
-    for entity in render_args.entities {
+    let pass = ModelPass {
+        render_args,
+        pipeline,
+        skinned_pipeline,
+        texture_cache,
+        camera,
+        skybox,
+    };
+
+    let camera_position = unsafe { *(camera.position.as_ptr() as *const [f32; 3]) };
+
+    // opaque and masked sub meshes are drawn right away, blended ones are
+    // collected as (distance, entity, mesh, sub mesh) and drawn back to front
+    let mut transparent: Vec<(f32, usize, usize, usize)> = Vec::new();
+    for (entity_index, entity) in render_args.entities.iter().enumerate() {
         let mut model = entity.model.borrow_mut();
-        let model_matrix = &entity.transform.matrix();
+        let entity_matrix = entity.transform.matrix();
         render::Backend::set_vertex_buffer(&mut model.vertex_buffer);
         render::Backend::set_attributes(&model.attributes);
         render::Backend::set_index_buffer(&mut model.index_buffer);
-        for mesh in &model.meshes {
-            for sub_mesh in &mesh.sub_meshes {
-                unsafe {
-                    gl::UseProgram(pipeline.id);
-
-                    let material = &model.materials[sub_mesh.material_index];
-                    let camera_position = &camera.position;
-                    pipeline.set_uniform_mat4("model\0", &model_matrix);
-                    pipeline.set_uniform_mat4("projection\0", &render_args.projection_matrix);
-                    pipeline.set_uniform_mat4("view\0", &render_args.view_matrix);
-
-                    pipeline.set_uniform_vec3("material.color\0", &material.color);
-                    pipeline.set_uniform_1f("material.roughness\0", material.roughness);
-                    pipeline.set_uniform_1f("material.metallic\0", material.metallic);
-                    pipeline.set_uniform_1f("material.ao\0", material.ao);
-                    pipeline.set_uniform_1f("material.specular\0", material.roughness);
-                    pipeline.set_uniform_point3("camera_position\0", &camera_position);
-                    pipeline.set_uniform_1i("u_albedoMap\0", 0);
-                    pipeline.set_uniform_1i("u_normalMap\0", 1);
-                    pipeline.set_uniform_1i("u_metallicMap\0", 2);
-                    pipeline.set_uniform_1i("u_emissiveMap\0", 3);
-                    pipeline.set_uniform_1i("u_brdfMap\0", 4);
-                    pipeline.set_uniform_1i("u_irradianceMap\0", 5);
-                    pipeline.set_uniform_1i("u_prefilterMap\0", 6);
-
-                    enable_texture(
-                        gl::TEXTURE_2D,
-                        0,
-                        match material.albedo_map.as_ref() {
-                            None => texture_cache.white_texture.id,
-                            Some(texture) => texture.id,
-                        },
-                    );
-                    enable_texture(
-                        gl::TEXTURE_2D,
-                        1,
-                        match material.normal_map.as_ref() {
-                            None => texture_cache.blue_texture.id,
-                            Some(texture) => texture.id,
-                        },
-                    );
+        for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+            for (sub_mesh_index, sub_mesh) in mesh.sub_meshes.iter().enumerate() {
+                if model.materials[sub_mesh.material_index].is_transparent() {
+                    let model_matrix = mesh_model_matrix(entity_matrix, mesh);
+                    let center = transform_point(&model_matrix, sub_mesh.center);
+                    let offset = [
+                        center[0] - camera_position[0],
+                        center[1] - camera_position[1],
+                        center[2] - camera_position[2],
+                    ];
+                    let distance =
+                        offset[0] * offset[0] + offset[1] * offset[1] + offset[2] * offset[2];
+                    transparent.push((distance, entity_index, mesh_index, sub_mesh_index));
+                    continue;
+                }
 
-                    enable_texture(
-                        gl::TEXTURE_2D,
-                        2,
-                        match material.specular_map.as_ref() {
-                            None => texture_cache.gray_texture.id,
-                            Some(texture) => texture.id,
-                        },
-                    );
+                draw_sub_mesh(&pass, &model, mesh, sub_mesh, entity_matrix);
+            }
+        }
+    }
 
-                    enable_texture(
-                        gl::TEXTURE_2D,
-                        3,
-                        match material.emissive_map.as_ref() {
-                            None => texture_cache.black_texture.id,
-                            Some(texture) => texture.id,
-                        },
-                    );
+    transparent.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
 
-                    enable_texture(gl::TEXTURE_2D, 4, skybox.brdf.id);
-                    enable_texture(gl::TEXTURE_CUBE_MAP, 5, skybox.irradiance.id);
-                    enable_texture(gl::TEXTURE_CUBE_MAP, 6, skybox.prefilter.id);
+    // blended surfaces are depth tested but do not occlude each other
+    unsafe {
+        gl::DepthMask(gl::FALSE as u8);
+    }
+    for (_, entity_index, mesh_index, sub_mesh_index) in transparent {
+        let entity = &render_args.entities[entity_index];
+        let mut model = entity.model.borrow_mut();
+        render::Backend::set_vertex_buffer(&mut model.vertex_buffer);
+        render::Backend::set_attributes(&model.attributes);
+        render::Backend::set_index_buffer(&mut model.index_buffer);
 
-                    let start_index = sub_mesh.start_index * std::mem::size_of::<u32>();
-                    gl::DrawElements(
-                        gl::TRIANGLES,
-                        sub_mesh.num_indices as i32,
-                        gl::UNSIGNED_INT,
-                        start_index as *const _,
-                    );
+        let mesh = &model.meshes[mesh_index];
+        let sub_mesh = &mesh.sub_meshes[sub_mesh_index];
+        draw_sub_mesh(&pass, &model, mesh, sub_mesh, entity.transform.matrix());
+    }
+
+    unsafe {
+        gl::DepthMask(gl::TRUE as u8);
+        gl::Disable(gl::CULL_FACE);
+    }
+}
+
+// screenshots go to the working directory, named by the time they were taken. the
+// depth comes from the scene target, the output framebuffer has none.
+fn capture_frame(color_framebuffer: u32, depth_framebuffer: u32, width: u32, height: u32) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let color_path = render::capture::timestamped_name("screenshot", now, "png");
+    let depth_path = render::capture::timestamped_name("screenshot-depth", now, "exr");
+    let saved = render::capture::save_color(
+        Path::new(&color_path),
+        color_framebuffer,
+        width,
+        height,
+        render::capture::ColorEncoding::Srgb,
+    )
+    .and_then(|()| {
+        render::capture::save_depth(Path::new(&depth_path), depth_framebuffer, width, height)
+    });
+
+    match saved {
+        Ok(()) => println!("saved {} and {}", color_path, depth_path),
+        Err(error) => println!("failed to save screenshot: {}", error),
+    }
+}
+
+fn is_exr(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("exr"))
+}
+
+fn export_scene(entities: &[Entity], path: &Path) {
+    let models: Vec<_> = entities
+        .iter()
+        .map(|entity| entity.model.borrow())
+        .collect();
+    let instances: Vec<render::export::Instance> = entities
+        .iter()
+        .zip(&models)
+        .map(|(entity, model)| render::export::Instance {
+            model,
+            matrix: entity.transform.matrix(),
+        })
+        .collect();
+
+    match render::export::export_glb(path, &instances) {
+        Ok(()) => println!("exported scene to {}", path.display()),
+        Err(error) => println!("failed to export scene: {}", error),
+    }
+}
+
+fn mesh_model_matrix(entity_matrix: iml::Mat4, mesh: &render::model::Mesh) -> iml::Mat4 {
+    match mesh.skin {
+        Some(_) => entity_matrix,
+        None => entity_matrix * mesh.matrix,
+    }
+}
+
+// iml matrices are column major like OpenGL
+fn transform_point(matrix: &iml::Mat4, point: [f32; 3]) -> [f32; 3] {
+    let m = unsafe { std::slice::from_raw_parts(matrix.as_ptr(), 16) };
+    [
+        m[0] * point[0] + m[4] * point[1] + m[8] * point[2] + m[12],
+        m[1] * point[0] + m[5] * point[1] + m[9] * point[2] + m[13],
+        m[2] * point[0] + m[6] * point[1] + m[10] * point[2] + m[14],
+    ]
+}
+
+fn draw_sub_mesh(
+    pass: &ModelPass,
+    model: &render::Model,
+    mesh: &render::model::Mesh,
+    sub_mesh: &render::SubMesh,
+    entity_matrix: iml::Mat4,
+) {
+    let material_pipeline = match mesh.skin {
+        Some(_) => pass.skinned_pipeline,
+        None => pass.pipeline,
+    };
+    let pipeline = &material_pipeline.pipeline;
+    let model_matrix = mesh_model_matrix(entity_matrix, mesh);
+    let material = &model.materials[sub_mesh.material_index];
+    let texture_cache = pass.texture_cache;
+
+    unsafe {
+        gl::UseProgram(pipeline.id);
+
+        if let Some(joint_matrices) = mesh.skin.and_then(|skin| model.joint_matrices.get(skin)) {
+            let joint_palette = pass.render_args.joint_palette;
+            joint_palette.update(to_byte_slice(joint_matrices));
+            enable_texture(gl::TEXTURE_BUFFER, JOINT_PALETTE_SLOT, joint_palette.id());
+            pipeline.set_uniform_1i("u_jointMatrices\0", JOINT_PALETTE_SLOT as i32);
+        }
+
+        if material.double_sided {
+            gl::Disable(gl::CULL_FACE);
+        } else {
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+        }
+
+        let alpha_mode = match material.alpha_mode {
+            render::material::AlphaMode::Opaque => 0,
+            render::material::AlphaMode::Mask => 1,
+            render::material::AlphaMode::Blend => 2,
+        };
+
+        pipeline.set_uniform_mat4("model\0", &model_matrix);
+        pipeline.set_uniform_mat4("projection\0", &pass.render_args.projection_matrix);
+        pipeline.set_uniform_mat4("view\0", &pass.render_args.view_matrix);
+
+        pipeline.set_uniform_vec3("material.color\0", &material.color);
+        pipeline.set_uniform_1f("material.alpha\0", material.alpha);
+        pipeline.set_uniform_1f("material.roughness\0", material.roughness);
+        pipeline.set_uniform_1f("material.metallic\0", material.metallic);
+        pipeline.set_uniform_1f("material.ao\0", material.ao);
+        pipeline.set_uniform_1f("material.specular\0", material.roughness);
+        pipeline.set_uniform_vec3("material.emissive\0", &material.emissive);
+        pipeline.set_uniform_1f("material.normalScale\0", material.normal_scale);
+        pipeline.set_uniform_1f("material.occlusionStrength\0", material.occlusion_strength);
+        pipeline.set_uniform_1i("material.alphaMode\0", alpha_mode);
+        pipeline.set_uniform_1f("material.alphaCutoff\0", material.alpha_cutoff);
+        pipeline.set_uniform_1f("material.ior\0", material.ior);
+        pipeline.set_uniform_1f("material.emissiveStrength\0", material.emissive_strength);
+        pipeline.set_uniform_1f("material.clearcoatFactor\0", material.clearcoat.factor);
+        pipeline.set_uniform_1f(
+            "material.clearcoatRoughness\0",
+            material.clearcoat.roughness,
+        );
+        pipeline.set_uniform_1f(
+            "material.clearcoatNormalScale\0",
+            material.clearcoat.normal_scale,
+        );
+        pipeline.set_uniform_vec3("material.sheenColor\0", &material.sheen.color);
+        pipeline.set_uniform_1f("material.sheenRoughness\0", material.sheen.roughness);
+        pipeline.set_uniform_1f(
+            "material.transmissionFactor\0",
+            material.transmission.factor,
+        );
+        pipeline.set_uniform_1f("material.specularFactor\0", material.specular.factor);
+        pipeline.set_uniform_vec3("material.specularColor\0", &material.specular.color);
+        pipeline.set_uniform_point3("camera_position\0", &pass.camera.position);
+
+        enable_texture(gl::TEXTURE_2D, 4, pass.skybox.brdf.id());
+        enable_texture(gl::TEXTURE_CUBE_MAP, 5, pass.skybox.irradiance.id());
+        enable_texture(gl::TEXTURE_CUBE_MAP, 6, pass.skybox.prefilter.id());
+        pipeline.set_uniform_1i("u_brdfMap\0", 4);
+        pipeline.set_uniform_1i("u_irradianceMap\0", 5);
+        pipeline.set_uniform_1i("u_prefilterMap\0", 6);
+
+        // the factors carry the value when a map is missing, so most fall back to white
+        let white = &texture_cache.white_texture;
+        let blue = &texture_cache.blue_texture;
+        let material_maps = [
+            ("u_albedoMap\0", 0, &material.albedo_map, white),
+            ("u_normalMap\0", 1, &material.normal_map, blue),
+            (
+                "u_metallicMap\0",
+                2,
+                &material.metallic_roughness_map,
+                white,
+            ),
+            ("u_emissiveMap\0", 3, &material.emissive_map, white),
+            ("u_occlusionMap\0", 7, &material.occlusion_map, white),
+            ("u_clearcoatMap\0", 8, &material.clearcoat.map, white),
+            (
+                "u_clearcoatRoughnessMap\0",
+                9,
+                &material.clearcoat.roughness_map,
+                white,
+            ),
+            (
+                "u_clearcoatNormalMap\0",
+                10,
+                &material.clearcoat.normal_map,
+                blue,
+            ),
+            ("u_sheenColorMap\0", 11, &material.sheen.color_map, white),
+            (
+                "u_sheenRoughnessMap\0",
+                12,
+                &material.sheen.roughness_map,
+                white,
+            ),
+            ("u_transmissionMap\0", 13, &material.transmission.map, white),
+            ("u_specularMap\0", 14, &material.specular.map, white),
+            (
+                "u_specularColorMap\0",
+                15,
+                &material.specular.color_map,
+                white,
+            ),
+        ];
+
+        for (name, slot, map, fallback) in material_maps {
+            // textures still in the upload queue use the fallback for now
+            let (texture_id, tex_coord, transform) = match map {
+                Some(map) if map.texture.is_uploaded() => {
+                    (map.texture.id(), map.tex_coord, map.transform)
                 }
-            }
+                _ => (
+                    fallback.id(),
+                    0,
+                    render::material::TextureTransform::default(),
+                ),
+            };
+            enable_texture(gl::TEXTURE_2D, slot, texture_id);
+            pipeline.set_uniform_1i(name, slot as i32);
+
+            // u_textureMaps is indexed by the texture slot
+            let location = &material_pipeline.texture_maps[slot as usize];
+            gl::Uniform1i(location.tex_coord, tex_coord as i32);
+            gl::UniformMatrix3fv(
+                location.transform,
+                1,
+                0,
+                transform.matrix().as_ptr() as *const _,
+            );
+        }
+
+        let start_index = sub_mesh.start_index * std::mem::size_of::<u32>();
+        gl::DrawElements(
+            gl::TRIANGLES,
+            sub_mesh.num_indices as i32,
+            gl::UNSIGNED_INT,
+            start_index as *const _,
+        );
+    }
+}
+
+// drops the placeholder of a model that failed to load
+fn remove_pending_entity(
+    entities: &mut Vec<Entity>,
+    pending_entities: &mut Vec<(render::loader::LoadId, usize)>,
+    load_id: render::loader::LoadId,
+) {
+    let position = match pending_entities
+        .iter()
+        .position(|(pending, _)| *pending == load_id)
+    {
+        Some(position) => position,
+        None => return,
+    };
+
+    let (_, index) = pending_entities.remove(position);
+    entities.remove(index);
+    for (_, pending_index) in pending_entities.iter_mut() {
+        if *pending_index > index {
+            *pending_index -= 1;
         }
     }
 }
@@ -0,0 +1,522 @@
+// cli.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::scene;
+
+pub const USAGE: &str = "\
+usage: physical_based_renderer [options] [scene.json] [models...]
+
+  scene.json             scene to open, resources/scenes/default.json by default.
+                         Save scene writes back to it, or to a new scene.json when
+                         it is not given or models replace its entities
+  models                 glTF, GLB, OBJ or PLY files shown instead of the scene entities
+
+options:
+  --env <file>           equirectangular .hdr environment, or an sIBL .ibl set whose
+                         reflection or environment .hdr is used
+  --size <width>x<height>
+  --vsync <on|off>       on by default
+  --camera <x,y,z[,yaw,pitch[,fov]]>
+                         camera position, angles in degrees
+  --screenshot <file>    save a PNG or EXR of the last frame and exit, {frame} in the
+                         name saves every frame instead. EXR files keep the linear
+                         color from before tone mapping
+  --frames <n>           render n frames once everything is loaded, then exit
+  --headless             render offscreen behind an invisible window, needs
+                         --screenshot. without a GPU run it under xvfb-run with
+                         LIBGL_ALWAYS_SOFTWARE=1
+  --lut <file.cube>      3D color table, enables the color grading effect
+  --effect <file>        post processing fragment shader (.fs, .frag or .glsl) run
+                         after the built in effects, can be given more than once
+  -h, --help";
+
+const DEFAULT_SCENE: &str = "resources/scenes/default.json";
+// where Save scene writes when the opened scene must not be overwritten
+const SAVED_SCENE: &str = "scene.json";
+
+pub struct Options {
+    pub scene: Option<PathBuf>,
+    pub models: Vec<PathBuf>,
+    pub environment: Option<PathBuf>,
+    pub size: Option<(u32, u32)>,
+    pub vsync: bool,
+    pub camera: Option<scene::CameraDesc>,
+    pub screenshot: Option<PathBuf>,
+    pub frames: Option<u32>,
+    pub headless: bool,
+    pub lut: Option<PathBuf>,
+    pub effects: Vec<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            models: Vec::new(),
+            environment: None,
+            size: None,
+            vsync: true,
+            camera: None,
+            screenshot: None,
+            frames: None,
+            headless: false,
+            lut: None,
+            effects: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Help,
+    UnknownOption(String),
+    MissingValue(&'static str),
+    InvalidValue {
+        option: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    // an option that only works together with another one
+    Requires(&'static str, &'static str),
+    MissingFile(PathBuf),
+    UnsupportedFile(PathBuf),
+    // an .ibl set without a usable .hdr
+    Ibl(PathBuf, String),
+    SecondScene(PathBuf),
+    Scene(PathBuf, scene::SceneError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            CliError::MissingValue(option) => write!(f, "{} needs a value", option),
+            CliError::InvalidValue {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{}' for {}, expected {}",
+                value, option, expected
+            ),
+            CliError::Requires(option, required) => write!(f, "{} needs {}", option, required),
+            CliError::MissingFile(path) => write!(f, "'{}' does not exist", path.display()),
+            CliError::UnsupportedFile(path) => {
+                write!(f, "'{}' is not a supported file", path.display())
+            }
+            CliError::Ibl(path, error) => write!(f, "{}: {}", path.display(), error),
+            CliError::SecondScene(path) => {
+                write!(
+                    f,
+                    "only one scene can be opened, got '{}' too",
+                    path.display()
+                )
+            }
+            CliError::Scene(path, error) => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+// args does not include the program name
+pub fn parse<I>(args: I) -> Result<Options, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |option: &'static str| args.next().ok_or(CliError::MissingValue(option));
+        match arg.as_str() {
+            "-h" | "--help" => return Err(CliError::Help),
+            "--env" => {
+                let path = existing_file(value("--env")?)?;
+                options.environment = match extension(&path).as_str() {
+                    "hdr" => Some(path),
+                    "ibl" => Some(ibl_environment(&path)?),
+                    _ => return Err(CliError::UnsupportedFile(path)),
+                };
+            }
+            "--size" => options.size = Some(parse_size(&value("--size")?)?),
+            "--vsync" => {
+                options.vsync = match value("--vsync")?.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    other => {
+                        return Err(CliError::InvalidValue {
+                            option: "--vsync",
+                            value: other.to_string(),
+                            expected: "on or off",
+                        })
+                    }
+                }
+            }
+            "--camera" => options.camera = Some(parse_camera(&value("--camera")?)?),
+            "--screenshot" => {
+                let path = PathBuf::from(value("--screenshot")?);
+                if !matches!(extension(&path).as_str(), "png" | "exr") {
+                    return Err(CliError::UnsupportedFile(path));
+                }
+                options.screenshot = Some(path);
+            }
+            "--frames" => {
+                let frames = value("--frames")?;
+                options.frames = match frames.parse() {
+                    Ok(frames) if frames > 0 => Some(frames),
+                    _ => {
+                        return Err(CliError::InvalidValue {
+                            option: "--frames",
+                            value: frames,
+                            expected: "a positive number",
+                        })
+                    }
+                };
+            }
+            "--headless" => options.headless = true,
+            "--lut" => {
+                let path = existing_file(value("--lut")?)?;
+                if extension(&path) != "cube" {
+                    return Err(CliError::UnsupportedFile(path));
+                }
+                options.lut = Some(path);
+            }
+            "--effect" => {
+                let path = existing_file(value("--effect")?)?;
+                if !matches!(extension(&path).as_str(), "fs" | "frag" | "glsl") {
+                    return Err(CliError::UnsupportedFile(path));
+                }
+                options.effects.push(path);
+            }
+            _ if arg.starts_with('-') => return Err(CliError::UnknownOption(arg)),
+            _ => {
+                let path = existing_file(arg)?;
+                match extension(&path).as_str() {
+                    "json" if options.scene.is_some() => return Err(CliError::SecondScene(path)),
+                    "json" => options.scene = Some(path),
+                    "gltf" | "glb" | "obj" | "ply" => options.models.push(path),
+                    _ => return Err(CliError::UnsupportedFile(path)),
+                }
+            }
+        }
+    }
+
+    if options.headless && options.screenshot.is_none() {
+        return Err(CliError::Requires("--headless", "--screenshot"));
+    }
+    Ok(options)
+}
+
+impl Options {
+    // where the scene is read from
+    pub fn scene_path(&self) -> PathBuf {
+        self.scene
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SCENE))
+    }
+
+    // where Save scene writes, the default scene and a scene whose entities were
+    // replaced by models are left alone
+    pub fn save_path(&self) -> PathBuf {
+        match &self.scene {
+            Some(path) if self.models.is_empty() => path.clone(),
+            _ => PathBuf::from(SAVED_SCENE),
+        }
+    }
+
+    // the scene with the command line applied on top, a missing default scene is
+    // not an error
+    pub fn load_scene(&self) -> Result<scene::Scene, CliError> {
+        let path = self.scene_path();
+        let mut scene = match scene::Scene::load(&path) {
+            Ok(scene) => scene,
+            Err(error) if self.scene.is_some() => return Err(CliError::Scene(path, error)),
+            Err(error) => {
+                println!("failed to load {}: {}", path.display(), error);
+                scene::Scene::default()
+            }
+        };
+
+        if !self.models.is_empty() {
+            scene.entities = self
+                .models
+                .iter()
+                .map(|path| scene::EntityDesc {
+                    model: scene::ModelSource::Path(path.clone()),
+                    transform: scene::TransformDesc::default(),
+                    material: None,
+                })
+                .collect();
+        }
+        if let Some(environment) = &self.environment {
+            scene.environment = Some(environment.clone());
+        }
+        if let Some((width, height)) = self.size {
+            scene.window = scene::WindowDesc { width, height };
+        }
+        if let Some(camera) = self.camera {
+            scene.camera = camera;
+        }
+        Ok(scene)
+    }
+}
+
+fn existing_file(path: String) -> Result<PathBuf, CliError> {
+    let path = PathBuf::from(path);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(CliError::MissingFile(path))
+    }
+}
+
+// the equirectangular map of an sIBL set, the reflection map is sharper than the
+// environment one. the jpg background is not used since environments are HDR.
+fn ibl_environment(path: &Path) -> Result<PathBuf, CliError> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| CliError::Ibl(path.to_path_buf(), error.to_string()))?;
+    let file = match parse_ibl(&source) {
+        Some(file) => file,
+        None => {
+            return Err(CliError::Ibl(
+                path.to_path_buf(),
+                String::from("no REFfile or EVfile"),
+            ))
+        }
+    };
+
+    let environment = path.parent().unwrap_or_else(|| Path::new("")).join(file);
+    if extension(&environment) != "hdr" {
+        return Err(CliError::UnsupportedFile(environment));
+    }
+    if !environment.is_file() {
+        return Err(CliError::MissingFile(environment));
+    }
+    Ok(environment)
+}
+
+// key = "value" lines, the section names do not matter since the keys are unique
+fn parse_ibl(source: &str) -> Option<String> {
+    let value = |key: &str| {
+        source.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            if name.trim() != key {
+                return None;
+            }
+            let value = value.trim().trim_matches('"');
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    };
+    value("REFfile").or_else(|| value("EVfile"))
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), CliError> {
+    let size = value
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(CliError::InvalidValue {
+            option: "--size",
+            value: value.to_string(),
+            expected: "<width>x<height>",
+        }),
+    }
+}
+
+fn parse_camera(value: &str) -> Result<scene::CameraDesc, CliError> {
+    let invalid = || CliError::InvalidValue {
+        option: "--camera",
+        value: value.to_string(),
+        expected: "x,y,z[,yaw,pitch[,fov]]",
+    };
+
+    let numbers = value
+        .split(',')
+        .map(|number| number.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| invalid())?;
+
+    let mut camera = scene::CameraDesc::default();
+    match numbers.as_slice() {
+        [x, y, z, rest @ ..] if matches!(rest.len(), 0 | 2 | 3) => {
+            camera.position = [*x, *y, *z];
+            if let [yaw, pitch, ..] = rest {
+                camera.yaw = *yaw;
+                camera.pitch = *pitch;
+            }
+            if let [_, _, fov] = rest {
+                camera.fov = *fov;
+            }
+            Ok(camera)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IBL: &str = "\
+[Header]
+Name = \"Rooftops\"
+
+[Background]
+BGfile = \"rooftops_8k.jpg\"
+
+[Enviroment]
+EVfile = \"rooftops_env.hdr\"
+
+[Reflection]
+REFfile = \"rooftops_3k.hdr\"
+";
+
+    fn parse_args(args: &[&str]) -> Result<Options, CliError> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn is_invalid_value(result: Result<Options, CliError>, expected_option: &str) -> bool {
+        matches!(result, Err(CliError::InvalidValue { option, .. }) if option == expected_option)
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let options = parse_args(&[
+            DEFAULT_SCENE,
+            "--size",
+            "800x600",
+            "--vsync",
+            "off",
+            "--camera",
+            "1,2,3,90,-10,60",
+            "--screenshot",
+            "frame_{frame}.png",
+            "--frames",
+            "4",
+            "--headless",
+        ])
+        .unwrap();
+        assert_eq!(options.scene, Some(PathBuf::from(DEFAULT_SCENE)));
+        assert_eq!(options.size, Some((800, 600)));
+        assert!(!options.vsync);
+        let camera = options.camera.unwrap();
+        assert_eq!(camera.position, [1.0, 2.0, 3.0]);
+        assert_eq!([camera.yaw, camera.pitch, camera.fov], [90.0, -10.0, 60.0]);
+        assert_eq!(options.screenshot, Some(PathBuf::from("frame_{frame}.png")));
+        assert_eq!(options.frames, Some(4));
+        assert!(options.headless);
+    }
+
+    #[test]
+    fn unknown_options_fail() {
+        match parse_args(&["--fullscreen"]) {
+            Err(CliError::UnknownOption(option)) => assert_eq!(option, "--fullscreen"),
+            _ => panic!("expected --fullscreen to be unknown"),
+        }
+        assert!(matches!(
+            parse_args(&["--size"]),
+            Err(CliError::MissingValue("--size"))
+        ));
+    }
+
+    #[test]
+    fn files_have_to_exist_and_be_supported() {
+        match parse_args(&["missing.glb"]) {
+            Err(CliError::MissingFile(path)) => assert_eq!(path, PathBuf::from("missing.glb")),
+            _ => panic!("expected missing.glb to be missing"),
+        }
+        assert!(matches!(
+            parse_args(&["--lut", "missing.cube"]),
+            Err(CliError::MissingFile(_))
+        ));
+        match parse_args(&["src/main.rs"]) {
+            Err(CliError::UnsupportedFile(path)) => assert_eq!(path, PathBuf::from("src/main.rs")),
+            _ => panic!("expected src/main.rs to be unsupported"),
+        }
+        assert!(matches!(
+            parse_args(&["--effect", "src/main.rs"]),
+            Err(CliError::UnsupportedFile(_))
+        ));
+        assert!(matches!(
+            parse_args(&["--screenshot", "frame.jpg"]),
+            Err(CliError::UnsupportedFile(_))
+        ));
+    }
+
+    #[test]
+    fn only_one_scene_is_opened() {
+        match parse_args(&[DEFAULT_SCENE, DEFAULT_SCENE]) {
+            Err(CliError::SecondScene(path)) => assert_eq!(path, PathBuf::from(DEFAULT_SCENE)),
+            _ => panic!("expected a second scene to fail"),
+        }
+    }
+
+    #[test]
+    fn invalid_values_fail() {
+        for size in ["800", "800x", "0x600", "800x-600", "wide"] {
+            assert!(is_invalid_value(parse_args(&["--size", size]), "--size"));
+        }
+        for camera in ["1,2", "1,2,3,4", "1,2,3,4,5,6,7", "1,y,3"] {
+            assert!(is_invalid_value(
+                parse_args(&["--camera", camera]),
+                "--camera"
+            ));
+        }
+        assert!(is_invalid_value(parse_args(&["--vsync", "yes"]), "--vsync"));
+        assert!(is_invalid_value(parse_args(&["--frames", "0"]), "--frames"));
+    }
+
+    #[test]
+    fn headless_needs_a_screenshot() {
+        assert!(matches!(
+            parse_args(&["--headless"]),
+            Err(CliError::Requires("--headless", "--screenshot"))
+        ));
+        assert!(parse_args(&["--headless", "--screenshot", "frame.exr"]).is_ok());
+    }
+
+    #[test]
+    fn ibl_sets_prefer_the_reflection_map() {
+        assert_eq!(parse_ibl(IBL), Some(String::from("rooftops_3k.hdr")));
+
+        let without_reflection = IBL.replace("REFfile = \"rooftops_3k.hdr\"", "REFfile = \"\"");
+        assert_eq!(
+            parse_ibl(&without_reflection),
+            Some(String::from("rooftops_env.hdr"))
+        );
+        assert_eq!(parse_ibl("[Header]\nBGfile = \"background.jpg\"\n"), None);
+    }
+
+    #[test]
+    fn only_an_opened_scene_is_saved_back() {
+        let mut options = Options::default();
+        assert_eq!(options.scene_path(), PathBuf::from(DEFAULT_SCENE));
+        assert_eq!(options.save_path(), PathBuf::from(SAVED_SCENE));
+
+        options.scene = Some(PathBuf::from("room.json"));
+        assert_eq!(options.save_path(), PathBuf::from("room.json"));
+
+        // the models replaced the entities of the scene
+        options.models.push(PathBuf::from("helmet.glb"));
+        assert_eq!(options.save_path(), PathBuf::from(SAVED_SCENE));
+    }
+}
@@ -16,13 +16,50 @@ extern crate iml;
 extern crate rspirv;
 
 mod app;
+mod cli;
 mod clock;
 mod file_watcher;
 mod render;
+mod scene;
 mod ui;
 
 fn main() {
-    let application = app::App::init(1080, 1080);
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(cli::CliError::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(error) => exit_with_error(error),
+    };
+    let scene = match options.load_scene() {
+        Ok(scene) => scene,
+        Err(error) => exit_with_error(error),
+    };
+
+    let run_options = app::RunOptions {
+        vsync: options.vsync,
+        // a screenshot alone is taken of the first frame with everything loaded
+        frames: options
+            .frames
+            .or_else(|| options.screenshot.as_ref().map(|_| 1)),
+        screenshot: options.screenshot.clone(),
+        headless: options.headless,
+        lut: options.lut.clone(),
+        effects: options.effects.clone(),
+    };
+    let application = match app::App::init(scene, options.save_path(), run_options) {
+        Ok(application) => application,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    };
 
     application.run();
 }
+
+fn exit_with_error(error: cli::CliError) -> ! {
+    eprintln!("error: {}\n\n{}", error, cli::USAGE);
+    std::process::exit(2);
+}
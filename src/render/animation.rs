@@ -0,0 +1,578 @@
+// animation.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+// column major, same layout as glTF
+pub type Matrix4 = [[f32; 4]; 4];
+
+pub const IDENTITY: Matrix4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    Step,
+    CubicSpline,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Property {
+    Translation,
+    Rotation,
+    Scale,
+    // morph target weights, the sampler width is the number of targets
+    Weights,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub translation: [f32; 3],
+    // quaternion stored as x, y, z, w
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self {
+            translation: [0.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl Pose {
+    pub fn matrix(&self) -> Matrix4 {
+        from_translation_rotation_scale(self.translation, self.rotation, self.scale)
+    }
+}
+
+pub struct Sampler {
+    pub interpolation: Interpolation,
+    pub inputs: Vec<f32>,
+    pub outputs: Vec<f32>,
+    // number of floats per output value, 3 for translation, 4 for rotation
+    pub width: usize,
+}
+
+impl Sampler {
+    fn value(&self, key: usize) -> &[f32] {
+        let start = match self.interpolation {
+            // cubic spline keys are stored as in-tangent, value, out-tangent
+            Interpolation::CubicSpline => (key * 3 + 1) * self.width,
+            _ => key * self.width,
+        };
+        &self.outputs[start..start + self.width]
+    }
+
+    fn in_tangent(&self, key: usize) -> &[f32] {
+        let start = key * 3 * self.width;
+        &self.outputs[start..start + self.width]
+    }
+
+    fn out_tangent(&self, key: usize) -> &[f32] {
+        let start = (key * 3 + 2) * self.width;
+        &self.outputs[start..start + self.width]
+    }
+
+    pub fn sample(&self, time: f32, spherical: bool, output: &mut [f32]) {
+        let count = self.inputs.len();
+        if count == 0 {
+            return;
+        }
+
+        if count == 1 || time <= self.inputs[0] {
+            output.copy_from_slice(self.value(0));
+            return;
+        }
+
+        if time >= self.inputs[count - 1] {
+            output.copy_from_slice(self.value(count - 1));
+            return;
+        }
+
+        let next = self.inputs.partition_point(|&input| input <= time);
+        let previous = next - 1;
+        let delta = self.inputs[next] - self.inputs[previous];
+        let t = (time - self.inputs[previous]) / delta;
+
+        match self.interpolation {
+            Interpolation::Step => output.copy_from_slice(self.value(previous)),
+            Interpolation::Linear => {
+                let start = self.value(previous);
+                let end = self.value(next);
+                if spherical {
+                    let result = slerp(
+                        [start[0], start[1], start[2], start[3]],
+                        [end[0], end[1], end[2], end[3]],
+                        t,
+                    );
+                    output.copy_from_slice(&result);
+                } else {
+                    for index in 0..self.width {
+                        output[index] = start[index] + (end[index] - start[index]) * t;
+                    }
+                }
+            }
+            Interpolation::CubicSpline => {
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let start = self.value(previous);
+                let start_tangent = self.out_tangent(previous);
+                let end = self.value(next);
+                let end_tangent = self.in_tangent(next);
+
+                for index in 0..self.width {
+                    output[index] = (2.0 * t3 - 3.0 * t2 + 1.0) * start[index]
+                        + (t3 - 2.0 * t2 + t) * delta * start_tangent[index]
+                        + (-2.0 * t3 + 3.0 * t2) * end[index]
+                        + (t3 - t2) * delta * end_tangent[index];
+                }
+
+                if spherical {
+                    normalize(output);
+                }
+            }
+        }
+    }
+}
+
+pub struct Channel {
+    pub node: usize,
+    pub property: Property,
+    pub sampler: Sampler,
+}
+
+impl Channel {
+    pub fn sample(&self, time: f32, output: &mut [f32]) {
+        self.sampler
+            .sample(time, self.property == Property::Rotation, output);
+    }
+}
+
+pub struct Clip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl Clip {
+    // writes the animated values over the poses and morph weights, both indexed by node.
+    // nodes without a channel keep their value
+    pub fn evaluate(&self, time: f32, poses: &mut [Pose], weights: &mut [Vec<f32>]) {
+        for channel in &self.channels {
+            if channel.property == Property::Weights {
+                if let Some(weights) = weights.get_mut(channel.node) {
+                    weights.resize(channel.sampler.width, 0.0);
+                    channel.sample(time, weights);
+                }
+                continue;
+            }
+
+            let pose = match poses.get_mut(channel.node) {
+                Some(pose) => pose,
+                None => continue,
+            };
+
+            match channel.property {
+                Property::Translation => channel.sample(time, &mut pose.translation),
+                Property::Rotation => channel.sample(time, &mut pose.rotation),
+                Property::Scale => channel.sample(time, &mut pose.scale),
+                Property::Weights => {}
+            }
+        }
+    }
+}
+
+pub struct Skin {
+    // node indices
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Matrix4>,
+}
+
+impl Skin {
+    pub fn joint_matrices(&self, global_matrices: &[Matrix4]) -> Vec<Matrix4> {
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(index, joint)| {
+                let inverse_bind_matrix = self
+                    .inverse_bind_matrices
+                    .get(index)
+                    .unwrap_or(&IDENTITY);
+                multiply(&global_matrices[*joint], inverse_bind_matrix)
+            })
+            .collect()
+    }
+}
+
+pub struct Animator {
+    pub clip: Option<usize>,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    pub playing: bool,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            clip: None,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            playing: true,
+        }
+    }
+}
+
+impl Animator {
+    pub fn advance(&mut self, delta_time: f32, duration: f32) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += delta_time * self.speed;
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+    }
+}
+
+// parents are indexed by node, roots have None
+pub fn global_matrices(parents: &[Option<usize>], poses: &[Pose]) -> Vec<Matrix4> {
+    let mut matrices: Vec<Option<Matrix4>> = vec![None; poses.len()];
+    for node in 0..poses.len() {
+        resolve_global_matrix(node, parents, poses, &mut matrices);
+    }
+
+    matrices
+        .into_iter()
+        .map(|matrix| matrix.unwrap_or(IDENTITY))
+        .collect()
+}
+
+fn resolve_global_matrix(
+    node: usize,
+    parents: &[Option<usize>],
+    poses: &[Pose],
+    matrices: &mut Vec<Option<Matrix4>>,
+) -> Matrix4 {
+    if let Some(matrix) = matrices[node] {
+        return matrix;
+    }
+
+    let local = poses[node].matrix();
+    let global = match parents[node] {
+        Some(parent) => multiply(
+            &resolve_global_matrix(parent, parents, poses, matrices),
+            &local,
+        ),
+        None => local,
+    };
+
+    matrices[node] = Some(global);
+    global
+}
+
+pub fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut result = [[0.0; 4]; 4];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column][row] = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    result
+}
+
+pub fn from_translation_rotation_scale(
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+) -> Matrix4 {
+    let [x, y, z, w] = rotation;
+    let (x2, y2, z2) = (x + x, y + y, z + z);
+    let (xx, xy, xz) = (x * x2, x * y2, x * z2);
+    let (yy, yz, zz) = (y * y2, y * z2, z * z2);
+    let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+
+    [
+        [
+            (1.0 - (yy + zz)) * scale[0],
+            (xy + wz) * scale[0],
+            (xz - wy) * scale[0],
+            0.0,
+        ],
+        [
+            (xy - wz) * scale[1],
+            (1.0 - (xx + zz)) * scale[1],
+            (yz + wx) * scale[1],
+            0.0,
+        ],
+        [
+            (xz + wy) * scale[2],
+            (yz - wx) * scale[2],
+            (1.0 - (xx + yy)) * scale[2],
+            0.0,
+        ],
+        [translation[0], translation[1], translation[2], 1.0],
+    ]
+}
+
+pub fn slerp(start: [f32; 4], end: [f32; 4], t: f32) -> [f32; 4] {
+    let mut end = end;
+    let mut cos_theta: f32 = (0..4).map(|index| start[index] * end[index]).sum();
+
+    // take the shortest path
+    if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        end = [-end[0], -end[1], -end[2], -end[3]];
+    }
+
+    let (start_weight, end_weight) = if cos_theta > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        (
+            ((1.0 - t) * theta).sin() / sin_theta,
+            (t * theta).sin() / sin_theta,
+        )
+    };
+
+    let mut result = [0.0; 4];
+    for index in 0..4 {
+        result[index] = start[index] * start_weight + end[index] * end_weight;
+    }
+    normalize(&mut result);
+    result
+}
+
+fn normalize(values: &mut [f32]) {
+    let length = values.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length > 0.0 {
+        values.iter_mut().for_each(|value| *value /= length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    fn scalar_sampler(interpolation: Interpolation, inputs: &[f32], outputs: &[f32]) -> Sampler {
+        Sampler {
+            interpolation,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+            width: 1,
+        }
+    }
+
+    fn sample(sampler: &Sampler, time: f32) -> f32 {
+        let mut output = [0.0];
+        sampler.sample(time, false, &mut output);
+        output[0]
+    }
+
+    fn transform_point(matrix: &Matrix4, point: [f32; 3]) -> [f32; 3] {
+        let mut result = [0.0; 3];
+        for (row, value) in result.iter_mut().enumerate() {
+            *value = matrix[3][row] + (0..3).map(|k| matrix[k][row] * point[k]).sum::<f32>();
+        }
+        result
+    }
+
+    fn rotation_z(angle: f32) -> [f32; 4] {
+        let half = angle.to_radians() * 0.5;
+        [0.0, 0.0, half.sin(), half.cos()]
+    }
+
+    #[test]
+    fn step_holds_the_previous_key() {
+        let sampler = scalar_sampler(Interpolation::Step, &[0.0, 1.0, 2.0], &[0.0, 10.0, 20.0]);
+        assert_eq!(sample(&sampler, -1.0), 0.0);
+        assert_eq!(sample(&sampler, 0.5), 0.0);
+        assert_eq!(sample(&sampler, 1.0), 10.0);
+        assert_eq!(sample(&sampler, 1.99), 10.0);
+        assert_eq!(sample(&sampler, 3.0), 20.0);
+    }
+
+    #[test]
+    fn linear_interpolates_between_keys() {
+        let sampler = scalar_sampler(Interpolation::Linear, &[0.0, 1.0, 3.0], &[0.0, 10.0, 30.0]);
+        assert_close(&[sample(&sampler, 0.25)], &[2.5]);
+        assert_close(&[sample(&sampler, 2.0)], &[20.0]);
+        assert_close(&[sample(&sampler, 5.0)], &[30.0]);
+    }
+
+    #[test]
+    fn cubic_spline_uses_the_tangents() {
+        // in-tangent, value, out-tangent for every key
+        let sampler = scalar_sampler(
+            Interpolation::CubicSpline,
+            &[0.0, 2.0],
+            &[0.0, 0.0, 1.0, 2.0, 4.0, 0.0],
+        );
+        assert_close(&[sample(&sampler, 0.0)], &[0.0]);
+        assert_close(&[sample(&sampler, 2.0)], &[4.0]);
+        // hermite at t = 0.5 with the tangents scaled by the key interval
+        // 0.5 * 0 + 0.125 * 2 * 1 + 0.5 * 4 - 0.125 * 2 * 2
+        assert_close(&[sample(&sampler, 1.0)], &[1.75]);
+    }
+
+    #[test]
+    fn rotations_take_the_shortest_path() {
+        // the second key is the same 90 degree rotation with every component negated
+        let end = rotation_z(90.0).map(|value| -value);
+        let mut outputs = rotation_z(0.0).to_vec();
+        outputs.extend_from_slice(&end);
+        let sampler = Sampler {
+            interpolation: Interpolation::Linear,
+            inputs: vec![0.0, 1.0],
+            outputs,
+            width: 4,
+        };
+
+        let mut rotation = [0.0; 4];
+        sampler.sample(0.5, true, &mut rotation);
+        assert_close(&rotation, &rotation_z(45.0));
+    }
+
+    #[test]
+    fn slerp_keeps_unit_length() {
+        let rotation = slerp(rotation_z(10.0), rotation_z(170.0), 0.3);
+        assert_close(&rotation, &rotation_z(58.0));
+    }
+
+    #[test]
+    fn looping_wraps_the_time() {
+        let mut animator = Animator {
+            time: 1.5,
+            ..Animator::default()
+        };
+        animator.advance(1.0, 2.0);
+        assert_close(&[animator.time], &[0.5]);
+
+        animator.speed = -1.0;
+        animator.advance(1.0, 2.0);
+        assert_close(&[animator.time], &[1.5]);
+    }
+
+    #[test]
+    fn clamping_stops_at_the_ends() {
+        let mut animator = Animator {
+            time: 1.5,
+            looping: false,
+            speed: 2.0,
+            ..Animator::default()
+        };
+        animator.advance(1.0, 2.0);
+        assert_eq!(animator.time, 2.0);
+
+        animator.speed = -1.0;
+        animator.advance(5.0, 2.0);
+        assert_eq!(animator.time, 0.0);
+    }
+
+    #[test]
+    fn paused_animators_do_not_advance() {
+        let mut animator = Animator {
+            time: 0.5,
+            playing: false,
+            ..Animator::default()
+        };
+        animator.advance(1.0, 2.0);
+        assert_eq!(animator.time, 0.5);
+    }
+
+    #[test]
+    fn joint_matrices_move_the_bind_pose() {
+        // a root joint at (1, 0, 0) with a child two units above it
+        let parents = [None, Some(0)];
+        let mut poses = [Pose::default(); 2];
+        poses[0].translation = [1.0, 0.0, 0.0];
+        poses[1].translation = [0.0, 2.0, 0.0];
+
+        let mut root_bind = IDENTITY;
+        root_bind[3] = [-1.0, 0.0, 0.0, 1.0];
+        let mut child_bind = IDENTITY;
+        child_bind[3] = [-1.0, -2.0, 0.0, 1.0];
+        let skin = Skin {
+            joints: vec![0, 1],
+            inverse_bind_matrices: vec![root_bind, child_bind],
+        };
+
+        // the rest pose is the bind pose
+        for matrix in skin.joint_matrices(&global_matrices(&parents, &poses)) {
+            assert_close(&matrix.concat(), &IDENTITY.concat());
+        }
+
+        // the clip moves the root to (3, 0, 0) over a second and bends the child
+        let clip = Clip {
+            name: String::from("bend"),
+            duration: 1.0,
+            channels: vec![
+                Channel {
+                    node: 0,
+                    property: Property::Translation,
+                    sampler: Sampler {
+                        interpolation: Interpolation::Linear,
+                        inputs: vec![0.0, 1.0],
+                        outputs: vec![1.0, 0.0, 0.0, 3.0, 0.0, 0.0],
+                        width: 3,
+                    },
+                },
+                Channel {
+                    node: 1,
+                    property: Property::Rotation,
+                    sampler: Sampler {
+                        interpolation: Interpolation::Step,
+                        inputs: vec![0.0],
+                        outputs: rotation_z(90.0).to_vec(),
+                        width: 4,
+                    },
+                },
+            ],
+        };
+        let mut weights = vec![Vec::new(); 2];
+        clip.evaluate(0.5, &mut poses, &mut weights);
+
+        let joint_matrices = skin.joint_matrices(&global_matrices(&parents, &poses));
+        assert_eq!(joint_matrices.len(), 2);
+        // a vertex bound to the root follows its translation
+        assert_close(
+            &transform_point(&joint_matrices[0], [1.0, 0.0, 0.0]),
+            &[2.0, 0.0, 0.0],
+        );
+        // one above the child joint in bind space, rotated about the moved joint
+        assert_close(
+            &transform_point(&joint_matrices[1], [1.0, 3.0, 0.0]),
+            &[1.0, 2.0, 0.0],
+        );
+    }
+}
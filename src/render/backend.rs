@@ -26,18 +26,55 @@ impl Backend {
     // pub fn updateCameraInfo(projection: math::Mat4, view: math::Mat4) {}
 
     pub fn set_attributes(attributes: &stream::Attributes) {
+        let mut enabled_slots = [false; stream::Slot::NUM as usize];
         for attribute in attributes {
             let format = &attribute.format;
 
             let slot = attribute.slot as u32;
             let format_dimension = format.dimension_size() as i32;
-            let stride = format.stride() as i32;
-            let offset = attribute.get_total_offset() as *const _;
+            let component_type = GLenum::from(format._type);
+            let stride = attribute.element_stride() as i32;
+            let offset = attribute.offset as *const _;
 
             unsafe {
-                gl::VertexAttribPointer(slot, format_dimension, gl::FLOAT, 0, stride, offset);
+                match attribute.interpretation {
+                    stream::Interpretation::Integer => gl::VertexAttribIPointer(
+                        slot,
+                        format_dimension,
+                        component_type,
+                        stride,
+                        offset,
+                    ),
+                    stream::Interpretation::Normalized => gl::VertexAttribPointer(
+                        slot,
+                        format_dimension,
+                        component_type,
+                        gl::TRUE,
+                        stride,
+                        offset,
+                    ),
+                    stream::Interpretation::Float => gl::VertexAttribPointer(
+                        slot,
+                        format_dimension,
+                        component_type,
+                        gl::FALSE,
+                        stride,
+                        offset,
+                    ),
+                }
                 gl::EnableVertexAttribArray(slot);
             }
+
+            enabled_slots[slot as usize] = true;
+        }
+
+        // slots left enabled by a previous model would still point into its buffer
+        for (slot, enabled) in enabled_slots.iter().enumerate() {
+            if !enabled {
+                unsafe {
+                    gl::DisableVertexAttribArray(slot as u32);
+                }
+            }
         }
     }
 
@@ -68,11 +105,21 @@ impl Backend {
                 let data_size = (buffer.data.len() * std::mem::size_of::<u8>()) as isize;
                 let data = buffer.data.as_ptr().cast();
 
-                println!("data size: {}", data_size);
                 gl::BufferData(resource_type.into(), data_size, data, gl::DYNAMIC_DRAW);
 
                 buffer.dirty = false;
+            } else {
+                for range in &buffer.dirty_ranges {
+                    let bytes = &buffer.data[range.clone()];
+                    gl::BufferSubData(
+                        resource_type,
+                        range.start as isize,
+                        bytes.len() as isize,
+                        bytes.as_ptr().cast(),
+                    );
+                }
             }
+            buffer.dirty_ranges.clear();
         }
     }
 }
@@ -0,0 +1,163 @@
+// bloom.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use super::{framebuffer::FrameBuffer, model, postprocess, shader};
+
+const MAX_LEVELS: usize = 6;
+// levels stop halving before they get smaller than this
+const MIN_LEVEL_SIZE: u32 = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BloomSettings {
+    // how much of the blurred image replaces the scene, 0 turns bloom off
+    pub intensity: f32,
+    // filter radius of the upsampling in uv units
+    pub radius: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 0.04,
+            radius: 0.005,
+        }
+    }
+}
+
+// the scene is halved down a chain of levels and blurred back up, every level adds
+// to the one above it. the result is blended over the scene instead of added, there
+// is no threshold and no energy is created.
+pub struct Bloom {
+    pub settings: BloomSettings,
+    levels: Vec<FrameBuffer>,
+    size: (u32, u32),
+    downsample: shader::Pipeline,
+    upsample: shader::Pipeline,
+    composite: shader::Pipeline,
+}
+
+impl Bloom {
+    pub fn new() -> Result<Bloom, String> {
+        Ok(Bloom {
+            settings: BloomSettings::default(),
+            levels: Vec::new(),
+            size: (0, 0),
+            downsample: shader::Pipeline::new(
+                "resources/shaders/fullscreen.vs",
+                "resources/shaders/bloom_downsample.fs",
+            )?,
+            upsample: shader::Pipeline::new(
+                "resources/shaders/fullscreen.vs",
+                "resources/shaders/bloom_upsample.fs",
+            )?,
+            composite: shader::Pipeline::new(
+                "resources/shaders/fullscreen.vs",
+                "resources/shaders/blit.fs",
+            )?,
+        })
+    }
+
+    // blooms the color of the scene target in place
+    pub fn render(&mut self, scene_target: &FrameBuffer, model_cache: &model::ModelCache) {
+        let scene_color = match scene_target.color_texture(0) {
+            Some(texture) => texture.clone(),
+            None => return,
+        };
+        self.resize(scene_color.width, scene_color.height);
+        if self.levels.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        unsafe {
+            gl::UseProgram(self.downsample.id);
+        }
+        self.downsample.set_uniform_1i("source\0", 0);
+        let mut source = scene_color;
+        for (index, level) in self.levels.iter().enumerate() {
+            level.bind();
+            self.downsample.set_uniform_vec2(
+                "sourceTexelSize\0",
+                &iml::Vec2::new(1.0 / source.width as f32, 1.0 / source.height as f32),
+            );
+            self.downsample
+                .set_uniform_1i("firstLevel\0", (index == 0) as i32);
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, source.id());
+            }
+            postprocess::draw_quad(model_cache);
+            source = level.color_texture(0).unwrap().clone();
+        }
+
+        unsafe {
+            gl::UseProgram(self.upsample.id);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        self.upsample.set_uniform_1i("source\0", 0);
+        self.upsample
+            .set_uniform_1f("filterRadius\0", self.settings.radius);
+        for pair in self.levels.windows(2).rev() {
+            let (target, source) = (&pair[0], &pair[1]);
+            target.bind();
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, source.color_texture(0).unwrap().id());
+            }
+            postprocess::draw_quad(model_cache);
+        }
+
+        // scene * (1 - intensity) + bloom * intensity
+        scene_target.bind();
+        unsafe {
+            gl::UseProgram(self.composite.id);
+            gl::BlendColor(0.0, 0.0, 0.0, self.settings.intensity);
+            gl::BlendFunc(gl::CONSTANT_ALPHA, gl::ONE_MINUS_CONSTANT_ALPHA);
+            gl::BindTexture(
+                gl::TEXTURE_2D,
+                self.levels[0].color_texture(0).unwrap().id(),
+            );
+        }
+        self.composite.set_uniform_1i("source\0", 0);
+        postprocess::draw_quad(model_cache);
+
+        unsafe {
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if self.size == (width, height) {
+            return;
+        }
+        self.size = (width, height);
+        self.levels.clear();
+
+        let (mut level_width, mut level_height) = (width / 2, height / 2);
+        while self.levels.len() < MAX_LEVELS
+            && level_width >= MIN_LEVEL_SIZE
+            && level_height >= MIN_LEVEL_SIZE
+        {
+            let mut level = FrameBuffer::new(level_width, level_height);
+            level.add_color_texture(0, gl::R11F_G11F_B10F);
+            if let Err(error) = level.check() {
+                println!("failed to create bloom level: {}", error);
+                self.levels.clear();
+                return;
+            }
+            self.levels.push(level);
+            level_width /= 2;
+            level_height /= 2;
+        }
+    }
+}
@@ -6,19 +6,27 @@
 // Distributed under the MIT Lisense
 // https://mit-license.org/
 
-use std::ops::Drop;
+use std::ops::{Drop, Range};
 
 use super::*;
 
-use gl::types::GLenum;
-
 pub struct Buffer {
     pub data: Vec<u8>,
     pub(crate) gpu_resource: resource::GPUResource,
+    // the whole buffer is uploaded again, the size may have changed
     pub(crate) dirty: bool,
+    // bytes changed in place since the last upload
+    pub(crate) dirty_ranges: Vec<Range<usize>>,
 }
 
 impl Buffer {
+    // only these bytes are uploaded on the next sync, the size has to stay the same
+    pub fn mark_dirty(&mut self, range: Range<usize>) {
+        if !range.is_empty() {
+            self.dirty_ranges.push(range);
+        }
+    }
+
     pub fn new(data: Vec<u8>) -> Buffer {
         Buffer {
             data,
@@ -27,6 +35,7 @@ impl Buffer {
                 resource_type: resource::Type::Invalid,
             },
             dirty: true,
+            dirty_ranges: Vec::new(),
         }
     }
 }
@@ -40,6 +49,7 @@ impl Default for Buffer {
                 resource_type: resource::Type::Invalid,
             },
             dirty: true,
+            dirty_ranges: Vec::new(),
         }
     }
 }
@@ -48,15 +58,64 @@ impl Drop for Buffer {
     fn drop(&mut self) {
         if self.gpu_resource.resource_type != resource::Type::Invalid {
             unsafe {
-                println!("deleting buffer: {:?}", self.gpu_resource.resource_type);
-
-                let resource_type = GLenum::from(self.gpu_resource.resource_type.clone());
-                gl::DeleteBuffers(resource_type as i32, &self.gpu_resource.handle);
+                gl::DeleteBuffers(1, &self.gpu_resource.handle);
             }
         }
     }
 }
 
+// a buffer read in shaders through a samplerBuffer, every texel is four floats
+pub struct TextureBuffer {
+    buffer: u32,
+    texture: u32,
+}
+
+impl TextureBuffer {
+    pub fn new() -> TextureBuffer {
+        let mut buffer = 0;
+        let mut texture = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut buffer);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, buffer);
+            gl::BufferData(gl::TEXTURE_BUFFER, 0, std::ptr::null(), gl::STREAM_DRAW);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, buffer);
+            gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+        }
+        TextureBuffer { buffer, texture }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.texture
+    }
+
+    // replaces the contents, the texture follows the new storage
+    pub fn update(&self, data: &[u8]) {
+        unsafe {
+            gl::BindBuffer(gl::TEXTURE_BUFFER, self.buffer);
+            gl::BufferData(
+                gl::TEXTURE_BUFFER,
+                data.len() as isize,
+                data.as_ptr().cast(),
+                gl::STREAM_DRAW,
+            );
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+        }
+    }
+}
+
+impl Drop for TextureBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+            gl::DeleteBuffers(1, &self.buffer);
+        }
+    }
+}
+
 // pub struct BufferView {
 //     buffer: *const Buffer,
 //     offset: usize,
@@ -0,0 +1,310 @@
+// capture.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use gl::types::GLenum;
+
+// framebuffer 0 is the back buffer of the window
+pub const DEFAULT_FRAMEBUFFER: u32 = 0;
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(io::Error),
+    Exr(exr::error::Error),
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureError::Io(error) => write!(f, "failed to write image: {}", error),
+            CaptureError::Exr(error) => write!(f, "failed to write exr: {}", error),
+            CaptureError::UnsupportedFormat(extension) => {
+                write!(f, "can not save '{}' images", extension)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(error: io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+impl From<exr::error::Error> for CaptureError {
+    fn from(error: exr::error::Error) -> Self {
+        CaptureError::Exr(error)
+    }
+}
+
+// what a framebuffer attachment holds, decides how it is read back
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorEncoding {
+    // the window and sRGB targets, the values are already display ready
+    Srgb,
+    // float targets with linear radiance
+    Linear,
+}
+
+// every read returns the rows top to bottom like image files, GL starts at the bottom
+pub fn read_color(framebuffer: u32, width: u32, height: u32) -> Vec<u8> {
+    let pixels: Vec<u8> = read_pixels(framebuffer, width, height, gl::RGBA, gl::UNSIGNED_BYTE, 4);
+    flip_rows(&pixels, width as usize * 4)
+}
+
+pub fn read_color_hdr(framebuffer: u32, width: u32, height: u32) -> Vec<f32> {
+    let pixels: Vec<f32> = read_pixels(framebuffer, width, height, gl::RGBA, gl::FLOAT, 4);
+    flip_rows(&pixels, width as usize * 4)
+}
+
+// window depth goes from 0 at the near plane to 1 at the far plane
+pub fn read_depth(framebuffer: u32, width: u32, height: u32) -> Vec<f32> {
+    let pixels: Vec<f32> = read_pixels(
+        framebuffer,
+        width,
+        height,
+        gl::DEPTH_COMPONENT,
+        gl::FLOAT,
+        1,
+    );
+    flip_rows(&pixels, width as usize)
+}
+
+fn read_pixels<T: Copy + Default>(
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    format: GLenum,
+    data_type: GLenum,
+    components: usize,
+) -> Vec<T> {
+    let mut pixels: Vec<T> = vec![T::default(); width as usize * height as usize * components];
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            format,
+            data_type,
+            pixels.as_mut_ptr().cast(),
+        );
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    }
+    pixels
+}
+
+// row_length is in elements, not bytes
+pub fn flip_rows<T: Copy>(pixels: &[T], row_length: usize) -> Vec<T> {
+    if row_length == 0 {
+        return pixels.to_vec();
+    }
+    pixels
+        .chunks_exact(row_length)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// RGBA floats to 8 bit, color is clamped and encoded, alpha stays linear
+pub fn encode_srgb8(pixels: &[f32]) -> Vec<u8> {
+    pixels
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let value = if index % 4 == 3 {
+                value.clamp(0.0, 1.0)
+            } else {
+                linear_to_srgb(*value)
+            };
+            (value * 255.0 + 0.5) as u8
+        })
+        .collect()
+}
+
+// 8 bit color is written as png, linear float color as exr
+pub fn save_color(
+    path: &Path,
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    encoding: ColorEncoding,
+) -> Result<(), CaptureError> {
+    match (extension(path).as_str(), encoding) {
+        ("png", ColorEncoding::Srgb) => {
+            save_png(path, &read_color(framebuffer, width, height), width, height)
+        }
+        ("png", ColorEncoding::Linear) => {
+            let pixels = encode_srgb8(&read_color_hdr(framebuffer, width, height));
+            save_png(path, &pixels, width, height)
+        }
+        ("exr", _) => save_exr(
+            path,
+            &read_color_hdr(framebuffer, width, height),
+            width,
+            height,
+        ),
+        (extension, _) => Err(CaptureError::UnsupportedFormat(extension.to_string())),
+    }
+}
+
+pub fn save_depth(
+    path: &Path,
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+) -> Result<(), CaptureError> {
+    save_depth_exr(path, &read_depth(framebuffer, width, height), width, height)
+}
+
+pub fn save_png(path: &Path, pixels: &[u8], width: u32, height: u32) -> Result<(), CaptureError> {
+    image::save_buffer(path, pixels, width, height, image::ColorType::RGBA(8))?;
+    Ok(())
+}
+
+pub fn save_exr(path: &Path, pixels: &[f32], width: u32, height: u32) -> Result<(), CaptureError> {
+    let width = width as usize;
+    exr::prelude::write_rgba_file(path, width, height as usize, |x, y| {
+        let start = (y * width + x) * 4;
+        (
+            pixels[start],
+            pixels[start + 1],
+            pixels[start + 2],
+            pixels[start + 3],
+        )
+    })?;
+    Ok(())
+}
+
+// a single Z channel like renderers write depth passes
+pub fn save_depth_exr(
+    path: &Path,
+    depth: &[f32],
+    width: u32,
+    height: u32,
+) -> Result<(), CaptureError> {
+    use exr::prelude::*;
+
+    let width = width as usize;
+    let channels = SpecificChannels::build()
+        .with_channel("Z")
+        .with_pixel_fn(|position: Vec2<usize>| (depth[position.y() * width + position.x()],));
+    Image::from_channels((width, height as usize), channels)
+        .write()
+        .to_file(path)?;
+    Ok(())
+}
+
+// screenshot-2026-10-18-142530.png, in UTC so names sort by capture time
+pub fn timestamped_name(prefix: &str, unix_seconds: u64, extension: &str) -> String {
+    let days = (unix_seconds / 86400) as i64;
+    let seconds = unix_seconds % 86400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}-{:04}-{:02}-{:02}-{:02}{:02}{:02}.{}",
+        prefix,
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        extension
+    )
+}
+
+// days since 1970-01-01 to a proleptic gregorian date, from Howard Hinnant's
+// chrono-compatible low-level date algorithms
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_flipped_top_to_bottom() {
+        // 2x3 pixels with one element each, GL order is bottom row first
+        let pixels = [1, 2, 3, 4, 5, 6];
+        assert_eq!(flip_rows(&pixels, 2), vec![5, 6, 3, 4, 1, 2]);
+        // RGBA rows keep their pixels in order
+        let pixels = [0u8, 1, 2, 3, 4, 5, 6, 7];
+        assert_eq!(flip_rows(&pixels, 4), vec![4, 5, 6, 7, 0, 1, 2, 3]);
+        assert_eq!(flip_rows(&pixels, 0), pixels.to_vec());
+    }
+
+    #[test]
+    fn linear_color_is_clamped_and_srgb_encoded() {
+        assert_eq!(linear_to_srgb(-1.0), 0.0);
+        assert!((linear_to_srgb(4.0) - 1.0).abs() < 1e-6);
+        // the linear segment near black
+        assert!((linear_to_srgb(0.001) - 0.01292).abs() < 1e-6);
+
+        let pixels = [0.5, 0.001, 2.0, 0.5, -0.5, 1.0, 0.0, 1.5];
+        assert_eq!(
+            encode_srgb8(&pixels),
+            vec![188, 3, 255, 128, 0, 255, 0, 255]
+        );
+    }
+
+    #[test]
+    fn names_are_stamped_in_utc() {
+        assert_eq!(
+            timestamped_name("screenshot", 0, "png"),
+            "screenshot-1970-01-01-000000.png"
+        );
+        assert_eq!(
+            timestamped_name("depth", 951867930, "exr"),
+            "depth-2000-02-29-234530.exr"
+        );
+        assert_eq!(
+            timestamped_name("screenshot", 1792332330, "png"),
+            "screenshot-2026-10-18-140530.png"
+        );
+    }
+}
@@ -66,7 +66,7 @@ impl EguiPainter {
 
         let texture_pointer = self.texture_map.get(&mesh.texture_id);
         let texture_id = match texture_pointer {
-            Some(texture) => texture.id,
+            Some(texture) => texture.id(),
             None => 0,
         };
 
@@ -174,11 +174,9 @@ impl EguiPainter {
         for texture in textures {
             let texture_pointer = self.texture_map.remove(&texture);
 
-            match texture_pointer {
-                Some(texture) => unsafe {
-                    gl::DeleteTextures(1, &texture.id);
-                },
-                None => println!("Invalid texture to delete"),
+            // dropping the pointer deletes the GL texture
+            if texture_pointer.is_none() {
+                println!("Invalid texture to delete");
             }
         }
     }
@@ -0,0 +1,774 @@
+// export.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use gl::types::GLenum;
+use serde_json::{json, Map, Value};
+
+use super::{
+    material::{AlphaMode, Material, MaterialTexture, TextureTransform},
+    model::Model,
+    stream::{self, Slot},
+    texture::{Texture, WrapMode},
+};
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+
+const MATERIAL_EXTENSIONS: [&str; 6] = [
+    "KHR_materials_clearcoat",
+    "KHR_materials_sheen",
+    "KHR_materials_transmission",
+    "KHR_materials_specular",
+    "KHR_materials_ior",
+    "KHR_materials_emissive_strength",
+];
+
+// a model placed in the scene, usually an entity and its transform
+pub struct Instance<'m> {
+    pub model: &'m Model,
+    pub matrix: iml::Mat4,
+}
+
+pub fn export_glb(path: &Path, instances: &[Instance]) -> io::Result<()> {
+    std::fs::write(path, to_glb(instances)?)
+}
+
+// needs the GL context, textures are read back from the GPU
+pub fn to_glb(instances: &[Instance]) -> io::Result<Vec<u8>> {
+    write_glb(instances, read_texture_png)
+}
+
+// encodes a texture as a png image
+type ImageEncoder = fn(&Texture) -> io::Result<Vec<u8>>;
+
+// the geometry and materials are written from their cpu copies, only the images go
+// through the encoder
+fn write_glb(instances: &[Instance], encode_image: ImageEncoder) -> io::Result<Vec<u8>> {
+    let mut writer = GltfWriter::new(encode_image);
+    let mut nodes: Vec<Value> = Vec::new();
+    // models shared by several entities are only written once
+    let mut model_meshes: HashMap<*const Model, Vec<usize>> = HashMap::new();
+
+    for instance in instances {
+        let meshes = match model_meshes.get(&(instance.model as *const Model)) {
+            Some(meshes) => meshes.clone(),
+            None => {
+                let meshes = writer.write_model(instance.model)?;
+                model_meshes.insert(instance.model as *const Model, meshes.clone());
+                meshes
+            }
+        };
+
+        for (mesh, gltf_mesh) in instance.model.meshes.iter().zip(meshes) {
+            // skinned meshes are drawn with the entity matrix only, see mesh_model_matrix
+            let matrix = match mesh.skin {
+                Some(_) => instance.matrix,
+                None => instance.matrix * mesh.matrix,
+            };
+            nodes.push(json!({
+                "mesh": gltf_mesh,
+                "matrix": matrix_to_array(&matrix),
+            }));
+        }
+    }
+
+    writer.finish(nodes)
+}
+
+struct GltfWriter {
+    encode_image: ImageEncoder,
+    binary: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    samplers: Vec<Value>,
+    // maps shared between materials point at one image
+    texture_indices: HashMap<*const Texture, usize>,
+    extensions_used: Vec<&'static str>,
+}
+
+impl GltfWriter {
+    fn new(encode_image: ImageEncoder) -> GltfWriter {
+        GltfWriter {
+            encode_image,
+            binary: Vec::new(),
+            buffer_views: Vec::new(),
+            accessors: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            images: Vec::new(),
+            samplers: Vec::new(),
+            texture_indices: HashMap::new(),
+            extensions_used: Vec::new(),
+        }
+    }
+
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        // accessors need their data aligned to the component size
+        align(&mut self.binary, 0);
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.binary.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+
+        self.binary.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn use_extension(&mut self, extension: &'static str) {
+        if !self.extensions_used.contains(&extension) {
+            self.extensions_used.push(extension);
+        }
+    }
+
+    // returns the gltf mesh index of every mesh in the model
+    fn write_model(&mut self, model: &Model) -> io::Result<Vec<usize>> {
+        let vertex_data = &model.vertex_buffer.data;
+        let vertex_count = stream::vertex_count(&model.attributes, vertex_data.len());
+
+        let mut attributes = serde_json::Map::new();
+        for attribute in &model.attributes {
+            // joints and weights need the skeleton, which is not exported, so skinned
+            // models are written in their bind pose
+            let (name, accessor_type) = match attribute.slot {
+                Slot::Position => ("POSITION", "VEC3"),
+                Slot::Normal => ("NORMAL", "VEC3"),
+                Slot::TexCoord => ("TEXCOORD_0", "VEC2"),
+                Slot::Tangent => ("TANGENT", "VEC4"),
+                Slot::TexCoord1 => ("TEXCOORD_1", "VEC2"),
+                _ => continue,
+            };
+
+            // the buffer may be interleaved or hold integer types, accessors are always
+            // written as tightly packed floats
+            let floats = stream::read_floats(vertex_data, attribute, vertex_count);
+            let bytes: Vec<u8> = floats
+                .iter()
+                .flat_map(|float| float.to_le_bytes())
+                .collect();
+            let view = self.push_view(&bytes, Some(ARRAY_BUFFER));
+
+            let mut accessor = json!({
+                "bufferView": view,
+                "componentType": COMPONENT_FLOAT,
+                "count": vertex_count,
+                "type": accessor_type,
+            });
+            // required for positions
+            if let Slot::Position = attribute.slot {
+                let (min, max) = bounds(&floats);
+                accessor["min"] = json!(min);
+                accessor["max"] = json!(max);
+            }
+            attributes.insert(name.to_string(), json!(self.push_accessor(accessor)));
+        }
+
+        // the index buffer holds native u32s, glTF buffers are little endian
+        let indices: Vec<u8> = model
+            .index_buffer
+            .data
+            .chunks_exact(4)
+            .flat_map(|index| {
+                u32::from_ne_bytes([index[0], index[1], index[2], index[3]]).to_le_bytes()
+            })
+            .collect();
+        let index_view = self.push_view(&indices, Some(ELEMENT_ARRAY_BUFFER));
+
+        let material_offset = self.materials.len();
+        for material in &model.materials {
+            let material = self.write_material(material)?;
+            self.materials.push(material);
+        }
+
+        let mut meshes: Vec<usize> = Vec::new();
+        for mesh in &model.meshes {
+            let mut primitives: Vec<Value> = Vec::new();
+            for sub_mesh in &mesh.sub_meshes {
+                let indices = self.push_accessor(json!({
+                    "bufferView": index_view,
+                    "byteOffset": sub_mesh.start_index * std::mem::size_of::<u32>(),
+                    "componentType": COMPONENT_UNSIGNED_INT,
+                    "count": sub_mesh.num_indices,
+                    "type": "SCALAR",
+                }));
+
+                primitives.push(json!({
+                    "attributes": attributes,
+                    "indices": indices,
+                    "material": material_offset + sub_mesh.material_index,
+                }));
+            }
+
+            self.meshes.push(json!({ "primitives": primitives }));
+            meshes.push(self.meshes.len() - 1);
+        }
+
+        Ok(meshes)
+    }
+
+    fn write_material(&mut self, material: &Material) -> io::Result<Value> {
+        let mut pbr = json!({
+            "baseColorFactor": [
+                material.color.x,
+                material.color.y,
+                material.color.z,
+                material.alpha,
+            ],
+            "metallicFactor": material.metallic,
+            "roughnessFactor": material.roughness,
+        });
+        if let Some(map) = &material.albedo_map {
+            pbr["baseColorTexture"] = self.write_texture_info(map)?;
+        }
+        if let Some(map) = &material.metallic_roughness_map {
+            pbr["metallicRoughnessTexture"] = self.write_texture_info(map)?;
+        }
+
+        // emissiveFactor is clamped to one, the rest of a brighter color goes into the
+        // emissive strength
+        let (emissive, emissive_strength) = split_emissive(material);
+        let mut gltf_material = json!({
+            "pbrMetallicRoughness": pbr,
+            "emissiveFactor": emissive,
+            "alphaMode": match material.alpha_mode {
+                AlphaMode::Opaque => "OPAQUE",
+                AlphaMode::Mask => "MASK",
+                AlphaMode::Blend => "BLEND",
+            },
+            "doubleSided": material.double_sided,
+        });
+
+        if material.alpha_mode == AlphaMode::Mask {
+            gltf_material["alphaCutoff"] = json!(material.alpha_cutoff);
+        }
+
+        if let Some(map) = &material.normal_map {
+            let mut info = self.write_texture_info(map)?;
+            info["scale"] = json!(material.normal_scale);
+            gltf_material["normalTexture"] = info;
+        }
+
+        if let Some(map) = &material.occlusion_map {
+            let mut info = self.write_texture_info(map)?;
+            info["strength"] = json!(material.occlusion_strength);
+            gltf_material["occlusionTexture"] = info;
+        }
+
+        if let Some(map) = &material.emissive_map {
+            gltf_material["emissiveTexture"] = self.write_texture_info(map)?;
+        }
+
+        // extensions are only written when they change the material from the default
+        let mut extensions = Map::new();
+        let clearcoat = &material.clearcoat;
+        if clearcoat.factor != 0.0 || clearcoat.map.is_some() {
+            let mut extension = json!({
+                "clearcoatFactor": clearcoat.factor,
+                "clearcoatRoughnessFactor": clearcoat.roughness,
+            });
+            if let Some(map) = &clearcoat.map {
+                extension["clearcoatTexture"] = self.write_texture_info(map)?;
+            }
+            if let Some(map) = &clearcoat.roughness_map {
+                extension["clearcoatRoughnessTexture"] = self.write_texture_info(map)?;
+            }
+            if let Some(map) = &clearcoat.normal_map {
+                let mut info = self.write_texture_info(map)?;
+                info["scale"] = json!(clearcoat.normal_scale);
+                extension["clearcoatNormalTexture"] = info;
+            }
+            extensions.insert(String::from("KHR_materials_clearcoat"), extension);
+        }
+
+        let sheen = &material.sheen;
+        let sheen_color = [sheen.color.x, sheen.color.y, sheen.color.z];
+        if sheen_color != [0.0; 3] || sheen.color_map.is_some() {
+            let mut extension = json!({
+                "sheenColorFactor": sheen_color,
+                "sheenRoughnessFactor": sheen.roughness,
+            });
+            if let Some(map) = &sheen.color_map {
+                extension["sheenColorTexture"] = self.write_texture_info(map)?;
+            }
+            if let Some(map) = &sheen.roughness_map {
+                extension["sheenRoughnessTexture"] = self.write_texture_info(map)?;
+            }
+            extensions.insert(String::from("KHR_materials_sheen"), extension);
+        }
+
+        let transmission = &material.transmission;
+        if transmission.factor != 0.0 || transmission.map.is_some() {
+            let mut extension = json!({ "transmissionFactor": transmission.factor });
+            if let Some(map) = &transmission.map {
+                extension["transmissionTexture"] = self.write_texture_info(map)?;
+            }
+            extensions.insert(String::from("KHR_materials_transmission"), extension);
+        }
+
+        let specular = &material.specular;
+        let specular_color = [specular.color.x, specular.color.y, specular.color.z];
+        if specular.factor != 1.0
+            || specular_color != [1.0; 3]
+            || specular.map.is_some()
+            || specular.color_map.is_some()
+        {
+            let mut extension = json!({
+                "specularFactor": specular.factor,
+                "specularColorFactor": specular_color,
+            });
+            if let Some(map) = &specular.map {
+                extension["specularTexture"] = self.write_texture_info(map)?;
+            }
+            if let Some(map) = &specular.color_map {
+                extension["specularColorTexture"] = self.write_texture_info(map)?;
+            }
+            extensions.insert(String::from("KHR_materials_specular"), extension);
+        }
+
+        if material.ior != 1.5 {
+            extensions.insert(
+                String::from("KHR_materials_ior"),
+                json!({ "ior": material.ior }),
+            );
+        }
+
+        if emissive_strength != 1.0 {
+            extensions.insert(
+                String::from("KHR_materials_emissive_strength"),
+                json!({ "emissiveStrength": emissive_strength }),
+            );
+        }
+
+        if !extensions.is_empty() {
+            for extension in MATERIAL_EXTENSIONS {
+                if extensions.contains_key(extension) {
+                    self.use_extension(extension);
+                }
+            }
+            gltf_material["extensions"] = Value::Object(extensions);
+        }
+
+        Ok(gltf_material)
+    }
+
+    fn write_texture_info(&mut self, map: &MaterialTexture) -> io::Result<Value> {
+        let index = self.write_texture(&map.texture)?;
+        let mut info = json!({
+            "index": index,
+            "texCoord": map.tex_coord,
+        });
+
+        let transform = &map.transform;
+        if *transform != TextureTransform::default() {
+            info["extensions"] = json!({
+                "KHR_texture_transform": {
+                    "offset": transform.offset,
+                    "rotation": transform.rotation,
+                    "scale": transform.scale,
+                },
+            });
+            self.use_extension("KHR_texture_transform");
+        }
+
+        Ok(info)
+    }
+
+    fn write_texture(&mut self, texture: &Texture) -> io::Result<usize> {
+        let key = texture as *const Texture;
+        if let Some(index) = self.texture_indices.get(&key) {
+            return Ok(*index);
+        }
+
+        let png = (self.encode_image)(texture)?;
+        let view = self.push_view(&png, None);
+        self.images.push(json!({
+            "bufferView": view,
+            "mimeType": "image/png",
+        }));
+
+        let desc = &texture.texture_desc;
+        self.samplers.push(json!({
+            "magFilter": GLenum::from(desc.mag_filter),
+            "minFilter": GLenum::from(desc.min_filter),
+            "wrapS": gltf_wrap_mode(desc.wrap_s),
+            "wrapT": gltf_wrap_mode(desc.wrap_t),
+        }));
+
+        self.textures.push(json!({
+            "source": self.images.len() - 1,
+            "sampler": self.samplers.len() - 1,
+        }));
+
+        let index = self.textures.len() - 1;
+        self.texture_indices.insert(key, index);
+        Ok(index)
+    }
+
+    fn finish(mut self, nodes: Vec<Value>) -> io::Result<Vec<u8>> {
+        align(&mut self.binary, 0);
+
+        let scene_nodes: Vec<usize> = (0..nodes.len()).collect();
+        let mut document = json!({
+            "asset": {
+                "version": "2.0",
+                "generator": "PhysicalBasedRenderer",
+            },
+            "scene": 0,
+            "scenes": [{ "nodes": scene_nodes }],
+        });
+
+        // the schema does not allow empty arrays
+        let arrays = [
+            ("nodes", nodes),
+            ("meshes", self.meshes),
+            ("materials", self.materials),
+            ("textures", self.textures),
+            ("images", self.images),
+            ("samplers", self.samplers),
+            ("accessors", self.accessors),
+            ("bufferViews", self.buffer_views),
+        ];
+        for (key, values) in arrays {
+            if !values.is_empty() {
+                document[key] = Value::Array(values);
+            }
+        }
+
+        if !self.binary.is_empty() {
+            document["buffers"] = json!([{ "byteLength": self.binary.len() }]);
+        }
+        if !self.extensions_used.is_empty() {
+            document["extensionsUsed"] = json!(self.extensions_used);
+        }
+
+        let mut json_chunk = serde_json::to_vec(&document)?;
+        align(&mut json_chunk, b' ');
+
+        let mut length = 12 + 8 + json_chunk.len();
+        if !self.binary.is_empty() {
+            length += 8 + self.binary.len();
+        }
+
+        let mut glb: Vec<u8> = Vec::with_capacity(length);
+        glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+
+        glb.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+        glb.extend_from_slice(&json_chunk);
+
+        if !self.binary.is_empty() {
+            glb.extend_from_slice(&(self.binary.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+            glb.extend_from_slice(&self.binary);
+        }
+
+        Ok(glb)
+    }
+}
+
+// glb chunks and buffer views start on four byte boundaries
+fn align(bytes: &mut Vec<u8>, padding: u8) {
+    bytes.resize(bytes.len().next_multiple_of(4), padding);
+}
+
+fn bounds(floats: &[f32]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for position in floats.chunks_exact(3) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    (min, max)
+}
+
+// the emissive color with its largest channel at most one, and the strength that
+// makes up for the scale
+fn split_emissive(material: &Material) -> ([f32; 3], f32) {
+    let emissive = [
+        material.emissive.x,
+        material.emissive.y,
+        material.emissive.z,
+    ];
+    let largest = emissive
+        .iter()
+        .fold(0.0f32, |largest, value| largest.max(*value));
+    if largest <= 1.0 {
+        return (emissive, material.emissive_strength);
+    }
+    (
+        emissive.map(|value| value / largest),
+        material.emissive_strength * largest,
+    )
+}
+
+// glTF only has the three wrap modes, the rest fall back to the closest one
+fn gltf_wrap_mode(wrap_mode: WrapMode) -> GLenum {
+    match wrap_mode {
+        WrapMode::REPEAT => gl::REPEAT,
+        WrapMode::MIRROR | WrapMode::MIRROR_REPEAT => gl::MIRRORED_REPEAT,
+        WrapMode::CLAMP | WrapMode::BORDER => gl::CLAMP_TO_EDGE,
+    }
+}
+
+// iml matrices are column major like glTF
+fn matrix_to_array(matrix: &iml::Mat4) -> Vec<f32> {
+    unsafe { std::slice::from_raw_parts(matrix.as_ptr(), 16) }.to_vec()
+}
+
+fn read_texture_png(texture: &Texture) -> io::Result<Vec<u8>> {
+    // the texture may still be waiting in the upload queue
+    texture.upload();
+    let mut pixels: Vec<u8> = vec![0; texture.width as usize * texture.height as usize * 4];
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture.id());
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::GetTexImage(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast(),
+        );
+    }
+
+    // rows come back in the order they were uploaded, which is already top to bottom
+    let mut png: Vec<u8> = Vec::new();
+    image::png::PNGEncoder::new(&mut png).encode(
+        &pixels,
+        texture.width,
+        texture.height,
+        image::ColorType::RGBA(8),
+    )?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{
+        material::AlphaMode,
+        model::{self, LoadOptions, ModelCache, Shape, SubMesh},
+        texture::TextureCache,
+    };
+
+    fn no_images(_: &Texture) -> io::Result<Vec<u8>> {
+        panic!("the exported models have no textures")
+    }
+
+    fn indices(model: &Model) -> Vec<u32> {
+        model
+            .index_buffer
+            .data
+            .chunks_exact(4)
+            .map(|index| u32::from_ne_bytes([index[0], index[1], index[2], index[3]]))
+            .collect()
+    }
+
+    // the position of every index, vertices may be split or merged on import
+    fn indexed_positions(model: &Model) -> Vec<f32> {
+        let data = &model.vertex_buffer.data;
+        let count = stream::vertex_count(&model.attributes, data.len());
+        let attribute = model
+            .attributes
+            .iter()
+            .find(|attribute| attribute.slot == Slot::Position)
+            .unwrap();
+        let positions = stream::read_floats(data, attribute, count);
+        indices(model)
+            .iter()
+            .flat_map(|index| positions[*index as usize * 3..*index as usize * 3 + 3].to_vec())
+            .collect()
+    }
+
+    fn sub_mesh_ranges(model: &Model) -> Vec<(usize, usize)> {
+        model.meshes[0]
+            .sub_meshes
+            .iter()
+            .map(|sub_mesh| (sub_mesh.start_index, sub_mesh.num_indices))
+            .collect()
+    }
+
+    // the emissive color may be split differently between the factor and the strength
+    fn factors(material: &Material) -> Vec<f32> {
+        let emissive = material.emissive_strength;
+        vec![
+            material.color.x,
+            material.color.y,
+            material.color.z,
+            material.alpha,
+            material.roughness,
+            material.metallic,
+            material.emissive.x * emissive,
+            material.emissive.y * emissive,
+            material.emissive.z * emissive,
+            material.alpha_cutoff,
+            material.clearcoat.factor,
+            material.clearcoat.roughness,
+            material.sheen.color.x,
+            material.sheen.color.y,
+            material.sheen.color.z,
+            material.sheen.roughness,
+            material.transmission.factor,
+            material.specular.factor,
+            material.specular.color.x,
+            material.specular.color.y,
+            material.specular.color.z,
+            material.ior,
+        ]
+    }
+
+    #[test]
+    fn exported_shapes_import_the_same() {
+        // the cube split in two sub meshes with their own materials
+        let mut model = ModelCache::get_shape(Shape::Cube).into_inner();
+        let index_count = model.meshes[0].sub_meshes[0].num_indices;
+        model.meshes[0].sub_meshes = vec![
+            SubMesh {
+                start_index: 0,
+                num_indices: 12,
+                material_index: 0,
+                center: [0.0; 3],
+            },
+            SubMesh {
+                start_index: 12,
+                num_indices: index_count - 12,
+                material_index: 1,
+                center: [0.0; 3],
+            },
+        ];
+        let mut red = Material::new(iml::Vec3::new(1.0, 0.0, 0.0), 0.25, 1.0, 1.0);
+        red.alpha = 0.5;
+        red.alpha_mode = AlphaMode::Mask;
+        red.alpha_cutoff = 0.3;
+        red.clearcoat.factor = 0.5;
+        red.clearcoat.roughness = 0.25;
+        red.sheen.color = iml::Vec3::new(0.5, 0.25, 0.0);
+        red.sheen.roughness = 0.75;
+        red.transmission.factor = 0.5;
+        red.specular.factor = 0.5;
+        red.specular.color = iml::Vec3::new(1.0, 0.5, 0.25);
+        red.ior = 1.25;
+        // brighter than the emissive factor allows, as OBJ Ke values can be
+        let mut glowing = Material::new(iml::Vec3::new(0.0, 0.5, 1.0), 0.75, 0.0, 1.0);
+        glowing.emissive = iml::Vec3::new(2.0, 1.0, 0.0);
+        glowing.emissive_strength = 2.0;
+        model.materials = vec![red, glowing];
+
+        let glb = write_glb(
+            &[Instance {
+                model: &model,
+                matrix: iml::Mat4::identity(),
+            }],
+            no_images,
+        )
+        .unwrap();
+
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(gltf.meshes().len(), 1);
+        assert_eq!(gltf.meshes().next().unwrap().primitives().len(), 2);
+        let mut extensions_used: Vec<&str> = gltf.extensions_used().collect();
+        extensions_used.sort_unstable();
+        let mut expected = MATERIAL_EXTENSIONS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(extensions_used, expected);
+        let emissive: Vec<[f32; 3]> = gltf
+            .materials()
+            .map(|material| material.emissive_factor())
+            .collect();
+        assert_eq!(emissive, [[0.0, 0.0, 0.0], [1.0, 0.5, 0.0]]);
+
+        let imported = model::load_gltf_model_from_slice(
+            &glb,
+            &LoadOptions::default(),
+            &TextureCache::deferred(),
+        )
+        .unwrap()
+        .into_inner();
+
+        assert_eq!(indexed_positions(&imported), indexed_positions(&model));
+        assert_eq!(sub_mesh_ranges(&imported), sub_mesh_ranges(&model));
+        for (sub_mesh, original) in imported.meshes[0]
+            .sub_meshes
+            .iter()
+            .zip(&model.meshes[0].sub_meshes)
+        {
+            assert_eq!(
+                factors(&imported.materials[sub_mesh.material_index]),
+                factors(&model.materials[original.material_index])
+            );
+            assert_eq!(
+                imported.materials[sub_mesh.material_index].alpha_mode,
+                model.materials[original.material_index].alpha_mode
+            );
+        }
+    }
+
+    #[test]
+    fn buffers_are_little_endian() {
+        let model = ModelCache::get_shape(Shape::Quad).into_inner();
+        let glb = write_glb(
+            &[Instance {
+                model: &model,
+                matrix: iml::Mat4::identity(),
+            }],
+            no_images,
+        )
+        .unwrap();
+
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        let binary = gltf.blob.as_ref().unwrap();
+        let view = |accessor: gltf::Accessor| {
+            let view = accessor.view().unwrap();
+            let start = view.offset() + accessor.offset();
+            &binary[start..start + accessor.count() * accessor.size()]
+        };
+
+        let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+        let indices: Vec<u32> = view(primitive.indices().unwrap())
+            .chunks_exact(4)
+            .map(|index| u32::from_le_bytes([index[0], index[1], index[2], index[3]]))
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 1, 2, 3]);
+
+        let positions: Vec<f32> = view(primitive.get(&gltf::Semantic::Positions).unwrap())
+            .chunks_exact(4)
+            .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .collect();
+        assert_eq!(&positions[..3], &[-1.0, 1.0, 0.0]);
+    }
+}
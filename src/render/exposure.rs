@@ -0,0 +1,383 @@
+// exposure.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::ptr;
+
+use gl::types::GLsync;
+
+use super::texture;
+
+const HISTOGRAM_BINS: usize = 64;
+// the frame is metered from a mip level no larger than this
+const METERING_SIZE: u32 = 64;
+// the darkest and brightest pixels are left out of the average so a few highlights
+// or a black border do not pull the exposure around
+const LOW_PERCENT: f32 = 0.1;
+const HIGH_PERCENT: f32 = 0.9;
+// the average luminance is exposed to 18% grey
+const MIDDLE_GREY: f32 = 0.18;
+// metering reads are used this many frames after they are queued, by then the copy
+// has finished on the GPU and reading it does not stall the pipeline
+const READBACK_FRAMES: usize = 2;
+
+// log2 luminance covered by the histogram. bin 0 counts everything darker than min,
+// the other bins split the range evenly and the last one also takes what is brighter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HistogramRange {
+    pub min_log_luminance: f32,
+    pub max_log_luminance: f32,
+}
+
+impl Default for HistogramRange {
+    fn default() -> Self {
+        Self {
+            min_log_luminance: -10.0,
+            max_log_luminance: 10.0,
+        }
+    }
+}
+
+impl HistogramRange {
+    pub fn bin(&self, luminance: f32, bins: usize) -> usize {
+        if luminance <= 0.0 || bins < 2 {
+            return 0;
+        }
+        let log_luminance = luminance.log2();
+        if log_luminance < self.min_log_luminance {
+            return 0;
+        }
+        let position = (log_luminance - self.min_log_luminance)
+            / (self.max_log_luminance - self.min_log_luminance);
+        let bin = 1 + (position * (bins - 1) as f32) as usize;
+        bin.min(bins - 1)
+    }
+
+    // log2 luminance in the middle of a bin, bin 0 has no meaningful center
+    pub fn bin_center(&self, bin: usize, bins: usize) -> f32 {
+        self.min_log_luminance + (bin as f32 - 0.5) * self.bin_width(bins)
+    }
+
+    pub fn bin_width(&self, bins: usize) -> f32 {
+        (self.max_log_luminance - self.min_log_luminance) / (bins - 1).max(1) as f32
+    }
+}
+
+// Rec. 709 weights, the scene is rendered with sRGB primaries
+pub fn luminance(red: f32, green: f32, blue: f32) -> f32 {
+    0.2126 * red + 0.7152 * green + 0.0722 * blue
+}
+
+// pixels are RGBA floats
+pub fn build_histogram(pixels: &[f32], range: &HistogramRange, bins: usize) -> Vec<u32> {
+    let mut histogram = vec![0; bins];
+    for pixel in pixels.chunks_exact(4) {
+        histogram[range.bin(luminance(pixel[0], pixel[1], pixel[2]), bins)] += 1;
+    }
+    histogram
+}
+
+// average log2 luminance of the pixels between the low and high fractions of the
+// histogram, black pixels are ignored. None when nothing is lit.
+pub fn average_log_luminance(
+    histogram: &[u32],
+    range: &HistogramRange,
+    low: f32,
+    high: f32,
+) -> Option<f32> {
+    let total: u32 = histogram.iter().skip(1).sum();
+    if total == 0 {
+        return None;
+    }
+    let low_count = total as f32 * low;
+    let high_count = total as f32 * high;
+
+    let mut below = 0.0;
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for (bin, count) in histogram.iter().enumerate().skip(1) {
+        let start = below;
+        let end = below + *count as f32;
+        below = end;
+
+        let inside = (end.min(high_count) - start.max(low_count)).max(0.0);
+        sum += inside * range.bin_center(bin, histogram.len());
+        weight += inside;
+    }
+
+    if weight > 0.0 {
+        Some(sum / weight)
+    } else {
+        None
+    }
+}
+
+// the exposure in stops that brings the average to middle grey
+pub fn target_exposure(average_log_luminance: f32, min_ev: f32, max_ev: f32) -> f32 {
+    (MIDDLE_GREY.log2() - average_log_luminance).clamp(min_ev, max_ev)
+}
+
+// exponential approach, speed is how fast the eye adapts per second and does not
+// depend on the frame rate
+pub fn adapt(current: f32, target: f32, speed: f32, delta_time: f32) -> f32 {
+    current + (target - current) * (1.0 - (-delta_time * speed).exp())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AutoExposureSettings {
+    pub enabled: bool,
+    pub speed: f32,
+    // limits of the adapted exposure in stops
+    pub min_ev: f32,
+    pub max_ev: f32,
+}
+
+impl Default for AutoExposureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 1.5,
+            min_ev: -8.0,
+            max_ev: 8.0,
+        }
+    }
+}
+
+// meters the scene target every frame and adapts the exposure towards middle grey
+pub struct AutoExposure {
+    pub settings: AutoExposureSettings,
+    pub range: HistogramRange,
+    histogram: Vec<u32>,
+    exposure: f32,
+    metered: bool,
+    readback: MeteringReadback,
+}
+
+impl AutoExposure {
+    pub fn new() -> AutoExposure {
+        AutoExposure {
+            settings: AutoExposureSettings::default(),
+            range: HistogramRange::default(),
+            histogram: vec![0; HISTOGRAM_BINS],
+            exposure: 0.0,
+            metered: false,
+            readback: MeteringReadback::new(),
+        }
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    pub fn histogram(&self) -> &[u32] {
+        &self.histogram
+    }
+
+    // returns the adapted exposure in stops. the histogram is from a frame queued
+    // READBACK_FRAMES ago and adaptation keeps going on the last one until a new read
+    // is ready. the first metered frame jumps straight to the target instead of
+    // fading in from 0
+    pub fn update(&mut self, hdr_texture: &texture::Texture, delta_time: f32) -> f32 {
+        if let Some(pixels) = self.readback.take() {
+            self.histogram = build_histogram(&pixels, &self.range, HISTOGRAM_BINS);
+        }
+        self.readback.queue(hdr_texture);
+
+        let settings = &self.settings;
+        if let Some(average) =
+            average_log_luminance(&self.histogram, &self.range, LOW_PERCENT, HIGH_PERCENT)
+        {
+            let target = target_exposure(average, settings.min_ev, settings.max_ev);
+            self.exposure = if self.metered {
+                adapt(self.exposure, target, settings.speed, delta_time)
+            } else {
+                target
+            };
+            self.metered = true;
+        }
+        self.exposure = self.exposure.clamp(settings.min_ev, settings.max_ev);
+        self.exposure
+    }
+}
+
+// the first mip level that fits in METERING_SIZE, with its width and height
+fn metering_level(width: u32, height: u32) -> (i32, u32, u32) {
+    let mut level = 0;
+    while (width.max(height) >> level) > METERING_SIZE {
+        level += 1;
+    }
+    (level, (width >> level).max(1), (height >> level).max(1))
+}
+
+// the mip chain averages the frame down on the GPU, only a small level is copied
+// into a pixel buffer. a ring of READBACK_FRAMES buffers with a fence each lets the
+// copy finish while the next frames render.
+struct MeteringReadback {
+    buffers: [u32; READBACK_FRAMES],
+    // floats the buffer holds, it is resized with the scene target
+    lengths: [usize; READBACK_FRAMES],
+    // null when the buffer has nothing pending
+    fences: [GLsync; READBACK_FRAMES],
+    next: usize,
+}
+
+impl MeteringReadback {
+    fn new() -> MeteringReadback {
+        let mut buffers = [0; READBACK_FRAMES];
+        unsafe {
+            gl::GenBuffers(READBACK_FRAMES as i32, buffers.as_mut_ptr());
+        }
+        MeteringReadback {
+            buffers,
+            lengths: [0; READBACK_FRAMES],
+            fences: [ptr::null(); READBACK_FRAMES],
+            next: 0,
+        }
+    }
+
+    // the pixels of the oldest queued read, None until the GPU is done with it
+    fn take(&mut self) -> Option<Vec<f32>> {
+        let fence = self.fences[self.next];
+        if fence.is_null() {
+            return None;
+        }
+        let mut pixels = vec![0.0f32; self.lengths[self.next]];
+        unsafe {
+            let status = gl::ClientWaitSync(fence, 0, 0);
+            if status != gl::ALREADY_SIGNALED && status != gl::CONDITION_SATISFIED {
+                return None;
+            }
+            gl::DeleteSync(fence);
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.buffers[self.next]);
+            gl::GetBufferSubData(
+                gl::PIXEL_PACK_BUFFER,
+                0,
+                std::mem::size_of_val(pixels.as_slice()) as isize,
+                pixels.as_mut_ptr().cast(),
+            );
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        }
+        self.fences[self.next] = ptr::null();
+        Some(pixels)
+    }
+
+    // copies the metering level of this frame, a read that was never taken is dropped
+    fn queue(&mut self, hdr_texture: &texture::Texture) {
+        let (level, width, height) = metering_level(hdr_texture.width, hdr_texture.height);
+        let length = width as usize * height as usize * 4;
+        let slot = self.next;
+        unsafe {
+            if !self.fences[slot].is_null() {
+                gl::DeleteSync(self.fences[slot]);
+            }
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.buffers[slot]);
+            if self.lengths[slot] != length {
+                gl::BufferData(
+                    gl::PIXEL_PACK_BUFFER,
+                    (length * std::mem::size_of::<f32>()) as isize,
+                    ptr::null(),
+                    gl::STREAM_READ,
+                );
+                self.lengths[slot] = length;
+            }
+
+            gl::BindTexture(gl::TEXTURE_2D, hdr_texture.id());
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            // with a pack buffer bound the pointer is an offset into it
+            gl::GetTexImage(gl::TEXTURE_2D, level, gl::RGBA, gl::FLOAT, ptr::null_mut());
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+
+            self.fences[slot] = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        }
+        self.next = (slot + 1) % READBACK_FRAMES;
+    }
+}
+
+impl Drop for MeteringReadback {
+    fn drop(&mut self) {
+        unsafe {
+            for fence in self.fences {
+                if !fence.is_null() {
+                    gl::DeleteSync(fence);
+                }
+            }
+            gl::DeleteBuffers(READBACK_FRAMES as i32, self.buffers.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn bins_split_the_range_after_the_black_bin() {
+        let range = HistogramRange::default();
+        assert_close(range.bin_width(5), 5.0);
+        assert_close(range.bin_center(1, 5), -7.5);
+        assert_close(range.bin_center(4, 5), 7.5);
+        // a single bin covers the whole range
+        assert_close(range.bin_width(1), 20.0);
+
+        assert_eq!(range.bin(0.0, 5), 0);
+        assert_eq!(range.bin(2.0f32.powf(-11.0), 5), 0);
+        assert_eq!(range.bin(2.0f32.powf(-7.5), 5), 1);
+        assert_eq!(range.bin(2.0f32.powf(20.0), 5), 4);
+    }
+
+    #[test]
+    fn the_average_leaves_out_the_darkest_and_brightest_pixels() {
+        let range = HistogramRange::default();
+        // 10% on each side falls entirely in the outer bins
+        let histogram = [100, 10, 80, 10, 0];
+        assert_close(
+            average_log_luminance(&histogram, &range, 0.1, 0.9).unwrap(),
+            -2.5,
+        );
+        // only half of the outer bins are kept, the bright one pulls the average up
+        let histogram = [0, 20, 60, 0, 20];
+        assert_close(
+            average_log_luminance(&histogram, &range, 0.1, 0.9).unwrap(),
+            -1.875,
+        );
+        assert_eq!(
+            average_log_luminance(&[50, 0, 0, 0, 0], &range, 0.1, 0.9),
+            None
+        );
+    }
+
+    #[test]
+    fn the_target_exposure_is_clamped() {
+        assert_close(target_exposure(MIDDLE_GREY.log2(), -8.0, 8.0), 0.0);
+        assert_close(target_exposure(MIDDLE_GREY.log2() - 1.0, -8.0, 8.0), 1.0);
+        assert_close(target_exposure(10.0, -8.0, 8.0), -8.0);
+        assert_close(target_exposure(-20.0, -8.0, 8.0), 8.0);
+    }
+
+    #[test]
+    fn adaptation_is_exponential_and_independent_of_the_frame_rate() {
+        assert_close(adapt(1.0, 4.0, 1.5, 0.0), 1.0);
+        assert_close(adapt(0.0, 4.0, 1.0, 2.0f32.ln()), 2.0);
+        assert_close(adapt(0.0, 4.0, 1.5, 100.0), 4.0);
+
+        let two_frames = adapt(adapt(0.0, 4.0, 1.5, 0.1), 4.0, 1.5, 0.1);
+        assert_close(two_frames, adapt(0.0, 4.0, 1.5, 0.2));
+    }
+
+    #[test]
+    fn metering_reads_the_first_level_that_fits() {
+        assert_eq!(metering_level(64, 32), (0, 64, 32));
+        assert_eq!(metering_level(1920, 1080), (5, 60, 33));
+        assert_eq!(metering_level(4096, 1), (6, 64, 1));
+    }
+}
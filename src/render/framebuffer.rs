@@ -6,35 +6,479 @@
 // Distributed under the MIT Lisense
 // https://mit-license.org/
 
-use gl33::{gl_core_types::*, gl_enumerations::*, global_loader::*};
+use std::fmt;
 
-use std::rc;
+use gl::types::GLenum;
 
-use super::texture;
+use super::{stream, texture};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameBufferError {
+    // an attachment has a format that can not be rendered to or a zero size
+    IncompleteAttachment,
+    MissingAttachment,
+    IncompleteDrawBuffer,
+    IncompleteReadBuffer,
+    // the driver does not support this combination of formats
+    Unsupported,
+    IncompleteMultisample,
+    IncompleteLayerTargets,
+    // more color attachments than GL_MAX_COLOR_ATTACHMENTS
+    TooManyColorAttachments { index: u32, max: u32 },
+    Unknown(GLenum),
+}
+
+impl FrameBufferError {
+    fn from_status(status: GLenum) -> Option<FrameBufferError> {
+        match status {
+            gl::FRAMEBUFFER_COMPLETE => None,
+            gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Some(FrameBufferError::IncompleteAttachment),
+            gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+                Some(FrameBufferError::MissingAttachment)
+            }
+            gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => Some(FrameBufferError::IncompleteDrawBuffer),
+            gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => Some(FrameBufferError::IncompleteReadBuffer),
+            gl::FRAMEBUFFER_UNSUPPORTED => Some(FrameBufferError::Unsupported),
+            gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => Some(FrameBufferError::IncompleteMultisample),
+            gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => {
+                Some(FrameBufferError::IncompleteLayerTargets)
+            }
+            status => Some(FrameBufferError::Unknown(status)),
+        }
+    }
+}
+
+impl fmt::Display for FrameBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameBufferError::IncompleteAttachment => {
+                write!(f, "an attachment can not be rendered to")
+            }
+            FrameBufferError::MissingAttachment => write!(f, "the framebuffer has no attachments"),
+            FrameBufferError::IncompleteDrawBuffer => {
+                write!(f, "a draw buffer has no attachment")
+            }
+            FrameBufferError::IncompleteReadBuffer => {
+                write!(f, "the read buffer has no attachment")
+            }
+            FrameBufferError::Unsupported => {
+                write!(f, "the attachment formats are not supported together")
+            }
+            FrameBufferError::IncompleteMultisample => {
+                write!(f, "the attachments have different sample counts")
+            }
+            FrameBufferError::IncompleteLayerTargets => {
+                write!(f, "layered and non layered attachments are mixed")
+            }
+            FrameBufferError::TooManyColorAttachments { index, max } => write!(
+                f,
+                "color attachment {} is out of range, the driver supports {}",
+                index, max
+            ),
+            FrameBufferError::Unknown(status) => {
+                write!(f, "framebuffer is incomplete (0x{:X})", status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrameBufferError {}
+
+// which image of a texture is rendered to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    Texture2D,
+    // 0 to 5 in GL order, +x -x +y -y +z -z
+    CubeFace(u32),
+}
+
+impl Target {
+    fn gl_target(&self) -> GLenum {
+        match self {
+            Target::Texture2D => gl::TEXTURE_2D,
+            Target::CubeFace(face) => gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DepthFormat {
+    Depth24,
+    Depth24Stencil8,
+}
+
+impl DepthFormat {
+    fn internal_format(&self) -> GLenum {
+        match self {
+            DepthFormat::Depth24 => gl::DEPTH_COMPONENT24,
+            DepthFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+        }
+    }
+
+    fn attachment(&self) -> GLenum {
+        match self {
+            DepthFormat::Depth24Stencil8 => gl::DEPTH_STENCIL_ATTACHMENT,
+            _ => gl::DEPTH_ATTACHMENT,
+        }
+    }
+}
+
+enum Attachment {
+    // created by the framebuffer and reallocated when it is resized
+    Owned {
+        texture: texture::TexturePointer,
+        format: GLenum,
+    },
+    // a face or mip level of a texture owned by the caller, kept as is on resize
+    Texture {
+        texture: texture::TexturePointer,
+        target: Target,
+        level: i32,
+    },
+    Renderbuffer {
+        id: u32,
+        format: GLenum,
+    },
+}
+
+// every method that changes an attachment leaves the framebuffer bound
 pub struct FrameBuffer {
     id: u32,
     width: u32,
     height: u32,
-    texture_buffer: Option<rc::Rc<texture::Texture>>,
+    colors: Vec<(u32, Attachment)>,
+    depth: Option<(DepthFormat, Attachment)>,
 }
 
 impl FrameBuffer {
-    fn create(width: u32, height: u32) -> FrameBuffer {
+    pub fn new(width: u32, height: u32) -> FrameBuffer {
         let mut id: u32 = 0;
-
         unsafe {
-            glGenFramebuffers(1, &mut id);
+            gl::GenFramebuffers(1, &mut id);
         }
 
         FrameBuffer {
             id,
             width,
             height,
-            texture_buffer: None,
+            colors: Vec::new(),
+            depth: None,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    // a 2D texture the size of the framebuffer, sampled with linear filtering. it is
+    // replaced on resize, fetch it with color_texture every frame
+    pub fn add_color_texture(&mut self, index: u32, format: GLenum) {
+        let texture = create_texture(format, self.width, self.height);
+        self.set_color(index, Attachment::Owned { texture, format });
+    }
+
+    // for targets that are only drawn and read back, never sampled
+    pub fn add_color_renderbuffer(&mut self, index: u32, format: GLenum) {
+        let id = create_renderbuffer(format, self.width, self.height);
+        self.set_color(index, Attachment::Renderbuffer { id, format });
+    }
+
+    // renders into a face or mip level of an existing texture, resize the framebuffer
+    // to the size of that level first
+    pub fn attach_color(
+        &mut self,
+        index: u32,
+        texture: &texture::TexturePointer,
+        target: Target,
+        level: i32,
+    ) {
+        self.set_color(
+            index,
+            Attachment::Texture {
+                texture: texture.clone(),
+                target,
+                level,
+            },
+        );
+    }
+
+    pub fn add_depth_renderbuffer(&mut self, format: DepthFormat) {
+        let id = create_renderbuffer(format.internal_format(), self.width, self.height);
+        self.set_depth(
+            format,
+            Attachment::Renderbuffer {
+                id,
+                format: format.internal_format(),
+            },
+        );
+    }
+
+    pub fn color_texture(&self, index: u32) -> Option<&texture::TexturePointer> {
+        self.colors
+            .iter()
+            .find(|(color, _)| *color == index)
+            .and_then(|(_, attachment)| match attachment {
+                Attachment::Owned { texture, .. } | Attachment::Texture { texture, .. } => {
+                    Some(texture)
+                }
+                Attachment::Renderbuffer { .. } => None,
+            })
+    }
+
+    pub fn check(&self) -> Result<(), FrameBufferError> {
+        let mut max: i32 = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max);
+        }
+        let max = max as u32;
+        if let Some((index, _)) = self.colors.iter().find(|(index, _)| *index >= max) {
+            return Err(FrameBufferError::TooManyColorAttachments { index: *index, max });
+        }
+
+        let status = unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::CheckFramebufferStatus(gl::FRAMEBUFFER)
+        };
+        match FrameBufferError::from_status(status) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // binds for drawing and sets the viewport to the framebuffer size
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    // reallocates what the framebuffer created, the old textures stay valid for whoever
+    // still holds them. attached textures keep their size.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+        for (index, attachment) in &mut self.colors {
+            resize_attachment(attachment, width, height);
+            attach(gl::COLOR_ATTACHMENT0 + *index, attachment);
+        }
+        if let Some((format, attachment)) = &mut self.depth {
+            resize_attachment(attachment, width, height);
+            attach(format.attachment(), attachment);
+        }
+    }
+
+    fn set_color(&mut self, index: u32, attachment: Attachment) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
         }
+        attach(gl::COLOR_ATTACHMENT0 + index, &attachment);
+
+        if let Some(position) = self.colors.iter().position(|(color, _)| *color == index) {
+            delete_attachment(&self.colors[position].1);
+            self.colors[position].1 = attachment;
+        } else {
+            self.colors.push((index, attachment));
+            self.colors.sort_by_key(|(index, _)| *index);
+        }
+
+        // every color attachment is drawn to, a gap in the indices draws nothing
+        let max_index = self.colors.last().map_or(0, |(index, _)| *index);
+        let draw_buffers: Vec<GLenum> = (0..=max_index)
+            .map(|index| {
+                if self.colors.iter().any(|(color, _)| *color == index) {
+                    gl::COLOR_ATTACHMENT0 + index
+                } else {
+                    gl::NONE
+                }
+            })
+            .collect();
+        unsafe {
+            gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
+        }
+    }
+
+    fn set_depth(&mut self, format: DepthFormat, attachment: Attachment) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+        if let Some((old_format, old_attachment)) = self.depth.take() {
+            // a depth only attachment would keep the old stencil attached
+            if old_format.attachment() != format.attachment() {
+                unsafe {
+                    gl::FramebufferRenderbuffer(
+                        gl::FRAMEBUFFER,
+                        old_format.attachment(),
+                        gl::RENDERBUFFER,
+                        0,
+                    );
+                }
+            }
+            delete_attachment(&old_attachment);
+        }
+        attach(format.attachment(), &attachment);
+        self.depth = Some((format, attachment));
     }
+}
+
+impl Drop for FrameBuffer {
+    fn drop(&mut self) {
+        for (_, attachment) in &self.colors {
+            delete_attachment(attachment);
+        }
+        if let Some((_, attachment)) = &self.depth {
+            delete_attachment(attachment);
+        }
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
+
+// the framebuffer has to be bound
+fn attach(point: GLenum, attachment: &Attachment) {
+    unsafe {
+        match attachment {
+            Attachment::Owned { texture, .. } => {
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, point, gl::TEXTURE_2D, texture.id(), 0);
+            }
+            Attachment::Texture {
+                texture,
+                target,
+                level,
+            } => {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    point,
+                    target.gl_target(),
+                    texture.id(),
+                    *level,
+                );
+            }
+            Attachment::Renderbuffer { id, .. } => {
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, point, gl::RENDERBUFFER, *id);
+            }
+        }
+    }
+}
+
+fn resize_attachment(attachment: &mut Attachment, width: u32, height: u32) {
+    match attachment {
+        Attachment::Owned { texture, format } => {
+            *texture = create_texture(*format, width, height);
+        }
+        Attachment::Texture { .. } => {}
+        Attachment::Renderbuffer { id, format } => unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, *id);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, *format, width as i32, height as i32);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        },
+    }
+}
+
+// textures are freed when the last pointer to them is dropped
+fn delete_attachment(attachment: &Attachment) {
+    if let Attachment::Renderbuffer { id, .. } = attachment {
+        unsafe {
+            gl::DeleteRenderbuffers(1, id);
+        }
+    }
+}
+
+fn create_renderbuffer(format: GLenum, width: u32, height: u32) -> u32 {
+    let mut id: u32 = 0;
+    unsafe {
+        gl::GenRenderbuffers(1, &mut id);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, id);
+        gl::RenderbufferStorage(gl::RENDERBUFFER, format, width as i32, height as i32);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+    }
+    id
+}
+
+fn create_texture(format: GLenum, width: u32, height: u32) -> texture::TexturePointer {
+    let (pixel_format, pixel_type) = pixel_transfer(format);
+    let mut id: u32 = 0;
+    unsafe {
+        gl::GenTextures(1, &mut id);
+        gl::BindTexture(gl::TEXTURE_2D, id);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            format as i32,
+            width as i32,
+            height as i32,
+            0,
+            pixel_format,
+            pixel_type,
+            std::ptr::null(),
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    texture::Texture::from_id(
+        id,
+        texture::TextureDesc::default(),
+        width,
+        height,
+        stream_format(format),
+        texture::Type::Tex2D,
+    )
+}
+
+// no pixels are uploaded but GL still wants a transfer format that fits the internal one
+fn pixel_transfer(format: GLenum) -> (GLenum, GLenum) {
+    match format {
+        gl::R8 | gl::R16F | gl::R32F => (gl::RED, gl::FLOAT),
+        gl::RG8 | gl::RG16F | gl::RG32F => (gl::RG, gl::FLOAT),
+        gl::RGB8 | gl::SRGB8 | gl::RGB16F | gl::RGB32F | gl::R11F_G11F_B10F => (gl::RGB, gl::FLOAT),
+        _ => (gl::RGBA, gl::FLOAT),
+    }
+}
 
-    fn set_texture(&self, texture: rc::Rc<texture::Texture>) {
-	if p
+fn stream_format(format: GLenum) -> stream::Format {
+    match format {
+        gl::RGBA8 | gl::SRGB8_ALPHA8 => stream::Format::new(
+            stream::Dimension::VEC4,
+            stream::Type::UINT8,
+            stream::Usage::RGBA,
+        ),
+        gl::R8 | gl::R16F | gl::R32F => stream::Format::new(
+            stream::Dimension::SCALAR,
+            stream::Type::FLOAT,
+            stream::Usage::RED,
+        ),
+        gl::RG8 | gl::RG16F | gl::RG32F => stream::Format::new(
+            stream::Dimension::VEC2,
+            stream::Type::FLOAT,
+            stream::Usage::RG,
+        ),
+        gl::RGB8 | gl::SRGB8 | gl::RGB16F | gl::RGB32F | gl::R11F_G11F_B10F => stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        ),
+        _ => stream::Format::new(
+            stream::Dimension::VEC4,
+            stream::Type::FLOAT,
+            stream::Usage::RGBA,
+        ),
     }
 }
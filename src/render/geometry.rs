@@ -0,0 +1,632 @@
+// geometry.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Topology {
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+pub fn sequential_indices(vertex_count: usize) -> Vec<u32> {
+    (0..vertex_count as u32).collect()
+}
+
+// converts strips and fans into a plain triangle list
+pub fn triangulate(topology: Topology, indices: &[u32]) -> Vec<u32> {
+    match topology {
+        Topology::Triangles => indices.to_vec(),
+        Topology::TriangleStrip => {
+            let mut triangles: Vec<u32> = Vec::new();
+            for index in 0..indices.len().saturating_sub(2) {
+                // flip every other triangle to keep the winding consistent
+                if index % 2 == 0 {
+                    triangles.extend_from_slice(&[
+                        indices[index],
+                        indices[index + 1],
+                        indices[index + 2],
+                    ]);
+                } else {
+                    triangles.extend_from_slice(&[
+                        indices[index],
+                        indices[index + 2],
+                        indices[index + 1],
+                    ]);
+                }
+            }
+            triangles
+        }
+        Topology::TriangleFan => {
+            let mut triangles: Vec<u32> = Vec::new();
+            for index in 1..indices.len().saturating_sub(1) {
+                triangles.extend_from_slice(&[indices[index], indices[index + 1], indices[0]]);
+            }
+            triangles
+        }
+    }
+}
+
+// expands an indexed attribute so every index gets its own copy of the value
+pub fn unweld(values: &[f32], width: usize, indices: &[u32]) -> Vec<f32> {
+    let mut unwelded: Vec<f32> = Vec::with_capacity(indices.len() * width);
+    for index in indices {
+        let start = *index as usize * width;
+        unwelded.extend_from_slice(&values[start..start + width]);
+    }
+    unwelded
+}
+
+// expects unwelded positions, three consecutive vertices per triangle
+pub fn flat_normals(positions: &[f32]) -> Vec<f32> {
+    let mut normals: Vec<f32> = Vec::with_capacity(positions.len());
+    for triangle in positions.chunks_exact(9) {
+        let normal = normalize(face_normal(
+            [triangle[0], triangle[1], triangle[2]],
+            [triangle[3], triangle[4], triangle[5]],
+            [triangle[6], triangle[7], triangle[8]],
+        ));
+
+        for _ in 0..3 {
+            normals.extend_from_slice(&normal);
+        }
+    }
+    normals
+}
+
+// area weighted average of the faces sharing each vertex
+pub fn smooth_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let mut normals: Vec<f32> = vec![0.0; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let vertex = |index: u32| {
+            let start = index as usize * 3;
+            [positions[start], positions[start + 1], positions[start + 2]]
+        };
+
+        let normal = face_normal(
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2]),
+        );
+        for index in triangle {
+            let start = *index as usize * 3;
+            normals[start] += normal[0];
+            normals[start + 1] += normal[1];
+            normals[start + 2] += normal[2];
+        }
+    }
+
+    for normal in normals.chunks_exact_mut(3) {
+        let normalized = normalize([normal[0], normal[1], normal[2]]);
+        normal.copy_from_slice(&normalized);
+    }
+    normals
+}
+
+// a port of the MikkTSpace reference implementation with its default 180 degree
+// angular threshold, giving the tangents bakers expect. the result has one tangent
+// per triangle corner, w holds the handedness of the bitangent like the glTF TANGENT
+// attribute. corners only share a tangent when their triangles are connected through
+// an edge around the same position, normal and uv and agree on the uv winding, so uv
+// seams and mirrored halves get their own.
+pub fn generate_tangents(
+    positions: &[f32],
+    normals: &[f32],
+    tex_coords: &[f32],
+    indices: &[u32],
+) -> Vec<f32> {
+    let position = |corner: usize| read_vec3(positions, indices[corner]);
+    let normal = |corner: usize| read_vec3(normals, indices[corner]);
+
+    let vertices = shared_vertices(positions, normals, tex_coords, indices);
+    let mut faces: Vec<TangentFace> = indices
+        .chunks_exact(3)
+        .map(|triangle| TangentFace::new(positions, tex_coords, triangle))
+        .collect();
+    let neighbors = face_neighbors(&vertices, &faces);
+
+    // groups of corners around one vertex that end up with the same tangent
+    let mut groups: Vec<TangentGroup> = Vec::new();
+    let mut corner_groups: Vec<Option<usize>> = vec![None; faces.len() * 3];
+    for face in 0..faces.len() {
+        if faces[face].degenerate {
+            continue;
+        }
+        for corner in face * 3..face * 3 + 3 {
+            if corner_groups[corner].is_some() {
+                continue;
+            }
+            groups.push(TangentGroup {
+                vertex: vertices[corner],
+                orientation_preserving: faces[face].orientation_preserving,
+                faces: Vec::new(),
+            });
+            let group = groups.len() - 1;
+            assign_group(
+                face,
+                &mut groups[group],
+                group,
+                &vertices,
+                &neighbors,
+                &mut faces,
+                &mut corner_groups,
+            );
+        }
+    }
+
+    let mut tangents: Vec<Option<[f32; 4]>> = vec![None; indices.len()];
+    for group in &groups {
+        let corner_of = |face: usize| {
+            (face * 3..face * 3 + 3)
+                .find(|corner| vertices[*corner] == group.vertex)
+                .unwrap()
+        };
+        let handedness = if group.orientation_preserving {
+            1.0
+        } else {
+            -1.0
+        };
+
+        for face in &group.faces {
+            let corner = corner_of(*face);
+            let vertex_normal = normal(corner);
+            let (s, t) = faces[*face].projected(vertex_normal);
+
+            // angle weighted average over the faces with a similar uv direction
+            let mut sum_s = [0.0; 3];
+            for other in &group.faces {
+                let other_face = &faces[*other];
+                let (other_s, other_t) = other_face.projected(vertex_normal);
+                let any = faces[*face].group_with_any || other_face.group_with_any;
+                let similar = dot(s, other_s) > ANGULAR_THRESHOLD_COS
+                    && dot(t, other_t) > ANGULAR_THRESHOLD_COS;
+                if !(any || face == other || similar) || other_face.group_with_any {
+                    continue;
+                }
+
+                let other_corner = corner_of(*other);
+                let first = other_corner - other_corner % 3;
+                let previous = first + (other_corner + 2) % 3;
+                let next = first + (other_corner + 1) % 3;
+                let to_previous = reject(
+                    subtract(position(previous), position(other_corner)),
+                    vertex_normal,
+                );
+                let to_next = reject(
+                    subtract(position(next), position(other_corner)),
+                    vertex_normal,
+                );
+                let cos_angle = dot(normalize_or_zero(to_previous), normalize_or_zero(to_next));
+                let angle = cos_angle.clamp(-1.0, 1.0).acos();
+                for axis in 0..3 {
+                    sum_s[axis] += other_s[axis] * angle;
+                }
+            }
+
+            let tangent = if dot(sum_s, sum_s) > 0.0 {
+                normalize(sum_s)
+            } else {
+                perpendicular(vertex_normal)
+            };
+            tangents[corner] = Some([tangent[0], tangent[1], tangent[2], handedness]);
+        }
+    }
+
+    // degenerate triangles take the tangent of a good corner of the same vertex
+    let mut vertex_tangents: HashMap<u32, [f32; 4]> = HashMap::new();
+    for (corner, tangent) in tangents.iter().enumerate() {
+        if let Some(tangent) = tangent {
+            vertex_tangents.entry(vertices[corner]).or_insert(*tangent);
+        }
+    }
+
+    let mut result: Vec<f32> = Vec::with_capacity(indices.len() * 4);
+    for (corner, tangent) in tangents.iter().enumerate() {
+        let tangent = tangent
+            .or_else(|| vertex_tangents.get(&vertices[corner]).copied())
+            .unwrap_or_else(|| {
+                let tangent = perpendicular(normal(corner));
+                [tangent[0], tangent[1], tangent[2], 1.0]
+            });
+        result.extend_from_slice(&tangent);
+    }
+    result
+}
+
+// cos of the 180 degree default, faces around a vertex are only kept apart by the
+// uv winding
+const ANGULAR_THRESHOLD_COS: f32 = -1.0;
+
+struct TangentFace {
+    // unit uv directions in object space, flipped for mirrored uvs
+    s: [f32; 3],
+    t: [f32; 3],
+    orientation_preserving: bool,
+    // no usable uv area, it joins the winding of the first group it meets
+    group_with_any: bool,
+    // two corners at the same position
+    degenerate: bool,
+}
+
+impl TangentFace {
+    fn new(positions: &[f32], tex_coords: &[f32], triangle: &[u32]) -> TangentFace {
+        let p = [triangle[0], triangle[1], triangle[2]].map(|index| read_vec3(positions, index));
+        let uv = [triangle[0], triangle[1], triangle[2]].map(|index| {
+            let start = index as usize * 2;
+            [tex_coords[start], tex_coords[start + 1]]
+        });
+
+        let edge1 = subtract(p[1], p[0]);
+        let edge2 = subtract(p[2], p[0]);
+        let delta_uv1 = [uv[1][0] - uv[0][0], uv[1][1] - uv[0][1]];
+        let delta_uv2 = [uv[2][0] - uv[0][0], uv[2][1] - uv[0][1]];
+        let signed_area = delta_uv1[0] * delta_uv2[1] - delta_uv1[1] * delta_uv2[0];
+        let s = subtract(scale(edge1, delta_uv2[1]), scale(edge2, delta_uv1[1]));
+        let t = subtract(scale(edge2, delta_uv1[0]), scale(edge1, delta_uv2[0]));
+
+        let mut face = TangentFace {
+            s: [0.0; 3],
+            t: [0.0; 3],
+            orientation_preserving: signed_area > 0.0,
+            group_with_any: true,
+            degenerate: p[0] == p[1] || p[0] == p[2] || p[1] == p[2],
+        };
+        if signed_area.abs() > f32::MIN_POSITIVE {
+            let sign = signed_area.signum();
+            let length_s = dot(s, s).sqrt();
+            let length_t = dot(t, t).sqrt();
+            if length_s > f32::MIN_POSITIVE {
+                face.s = scale(s, sign / length_s);
+            }
+            if length_t > f32::MIN_POSITIVE {
+                face.t = scale(t, sign / length_t);
+            }
+            let area = signed_area.abs();
+            face.group_with_any =
+                length_s / area <= f32::MIN_POSITIVE || length_t / area <= f32::MIN_POSITIVE;
+        }
+        face
+    }
+
+    // the uv directions in the tangent plane of a vertex normal
+    fn projected(&self, normal: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+        (
+            normalize_or_zero(reject(self.s, normal)),
+            normalize_or_zero(reject(self.t, normal)),
+        )
+    }
+}
+
+struct TangentGroup {
+    vertex: u32,
+    orientation_preserving: bool,
+    faces: Vec<usize>,
+}
+
+// walks the faces around the group's vertex across shared edges, stopping at faces of
+// the other uv winding
+fn assign_group(
+    face: usize,
+    group: &mut TangentGroup,
+    group_index: usize,
+    vertices: &[u32],
+    neighbors: &[Option<usize>],
+    faces: &mut [TangentFace],
+    corner_groups: &mut [Option<usize>],
+) {
+    let mut pending = vec![face];
+    while let Some(face) = pending.pop() {
+        let corner = match (face * 3..face * 3 + 3).find(|corner| vertices[*corner] == group.vertex)
+        {
+            Some(corner) => corner,
+            None => continue,
+        };
+        if corner_groups[corner].is_some() {
+            continue;
+        }
+
+        let unassigned = corner_groups[face * 3..face * 3 + 3]
+            .iter()
+            .all(Option::is_none);
+        let tangent_face = &mut faces[face];
+        if tangent_face.degenerate {
+            continue;
+        }
+        if tangent_face.group_with_any && unassigned {
+            tangent_face.orientation_preserving = group.orientation_preserving;
+        }
+        if tangent_face.orientation_preserving != group.orientation_preserving {
+            continue;
+        }
+
+        corner_groups[corner] = Some(group_index);
+        group.faces.push(face);
+
+        // the edges leaving and entering the corner
+        let first = face * 3;
+        pending.extend(neighbors[corner]);
+        pending.extend(neighbors[first + (corner + 2) % 3]);
+    }
+}
+
+// an id per corner, corners with the same position, normal and uv share one
+fn shared_vertices(
+    positions: &[f32],
+    normals: &[f32],
+    tex_coords: &[f32],
+    indices: &[u32],
+) -> Vec<u32> {
+    let mut ids: HashMap<[u32; 8], u32> = HashMap::new();
+    indices
+        .iter()
+        .map(|index| {
+            let vertex = *index as usize;
+            let mut key = [0; 8];
+            for axis in 0..3 {
+                key[axis] = positions[vertex * 3 + axis].to_bits();
+                key[axis + 3] = normals[vertex * 3 + axis].to_bits();
+            }
+            key[6] = tex_coords[vertex * 2].to_bits();
+            key[7] = tex_coords[vertex * 2 + 1].to_bits();
+            let next_id = ids.len() as u32;
+            *ids.entry(key).or_insert(next_id)
+        })
+        .collect()
+}
+
+// the face on the other side of the edge from each corner to the next one, matched
+// by the reversed edge. degenerate faces have no neighbors.
+fn face_neighbors(vertices: &[u32], faces: &[TangentFace]) -> Vec<Option<usize>> {
+    let edge = |corner: usize| {
+        let first = corner - corner % 3;
+        (vertices[corner], vertices[first + (corner + 1) % 3])
+    };
+
+    let corners = || (0..vertices.len()).filter(|corner| !faces[corner / 3].degenerate);
+
+    let mut open_edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for corner in corners() {
+        open_edges.entry(edge(corner)).or_default().push(corner);
+    }
+
+    let mut neighbors: Vec<Option<usize>> = vec![None; vertices.len()];
+    for corner in corners() {
+        if neighbors[corner].is_some() {
+            continue;
+        }
+        let (from, to) = edge(corner);
+        let twin = open_edges.get_mut(&(to, from)).and_then(|twins| {
+            let position = twins
+                .iter()
+                .position(|twin| neighbors[*twin].is_none() && twin / 3 != corner / 3)?;
+            Some(twins.remove(position))
+        });
+        if let Some(twin) = twin {
+            neighbors[corner] = Some(twin / 3);
+            neighbors[twin] = Some(corner / 3);
+        }
+    }
+    neighbors
+}
+
+// per vertex values from per corner ones. a vertex whose corners disagree is split,
+// the copies are appended after the existing vertices
+pub struct Welded {
+    pub values: Vec<f32>,
+    pub indices: Vec<u32>,
+    // the vertex each appended copy was made from
+    pub copies: Vec<u32>,
+}
+
+pub fn weld(corner_values: &[f32], width: usize, indices: &[u32], vertex_count: usize) -> Welded {
+    let mut welded = Welded {
+        values: vec![0.0; vertex_count * width],
+        indices: Vec::with_capacity(indices.len()),
+        copies: Vec::new(),
+    };
+    let mut assigned = vec![false; vertex_count];
+    let mut splits: HashMap<(u32, Vec<u32>), u32> = HashMap::new();
+
+    for (index, value) in indices.iter().zip(corner_values.chunks_exact(width)) {
+        let vertex = *index as usize;
+        let existing = &welded.values[vertex * width..(vertex + 1) * width];
+        if !assigned[vertex] {
+            assigned[vertex] = true;
+            welded.values[vertex * width..(vertex + 1) * width].copy_from_slice(value);
+            welded.indices.push(*index);
+        } else if existing == value {
+            welded.indices.push(*index);
+        } else {
+            let key = (*index, value.iter().map(|value| value.to_bits()).collect());
+            let next = (vertex_count + welded.copies.len()) as u32;
+            let split = *splits.entry(key).or_insert(next);
+            if split == next {
+                welded.copies.push(*index);
+                welded.values.extend_from_slice(value);
+            }
+            welded.indices.push(split);
+        }
+    }
+    welded
+}
+
+pub fn bounds_center(positions: &[f32]) -> [f32; 3] {
+    if positions.len() < 3 {
+        return [0.0; 3];
+    }
+
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for position in positions.chunks_exact(3) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+
+    [
+        (min[0] + max[0]) * 0.5,
+        (min[1] + max[1]) * 0.5,
+        (min[2] + max[2]) * 0.5,
+    ]
+}
+
+// not normalized, the length is twice the triangle area
+fn face_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    cross(subtract(b, a), subtract(c, a))
+}
+
+fn subtract(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(vector: [f32; 3], factor: f32) -> [f32; 3] {
+    [vector[0] * factor, vector[1] * factor, vector[2] * factor]
+}
+
+// the part of the vector perpendicular to the unit normal
+fn reject(vector: [f32; 3], normal: [f32; 3]) -> [f32; 3] {
+    subtract(vector, scale(normal, dot(normal, vector)))
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// any unit vector perpendicular to the normal
+fn perpendicular(normal: [f32; 3]) -> [f32; 3] {
+    let axis = if normal[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    normalize(cross(normal, axis))
+}
+
+fn normalize(vector: [f32; 3]) -> [f32; 3] {
+    let length = dot(vector, vector).sqrt();
+    if length > 0.0 {
+        [vector[0] / length, vector[1] / length, vector[2] / length]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+fn normalize_or_zero(vector: [f32; 3]) -> [f32; 3] {
+    let length = dot(vector, vector).sqrt();
+    if length > f32::MIN_POSITIVE {
+        scale(vector, 1.0 / length)
+    } else {
+        [0.0; 3]
+    }
+}
+
+fn read_vec3(values: &[f32], index: u32) -> [f32; 3] {
+    let start = index as usize * 3;
+    [values[start], values[start + 1], values[start + 2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    // a flat grid facing +z, the uvs of the vertices are given per column
+    fn strip(u: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>, Vec<u32>) {
+        let columns = u.len();
+        let mut positions = Vec::new();
+        let mut tex_coords = Vec::new();
+        for y in 0..2 {
+            for (x, u) in u.iter().enumerate() {
+                positions.extend_from_slice(&[x as f32, y as f32, 0.0]);
+                tex_coords.extend_from_slice(&[*u, y as f32]);
+            }
+        }
+        let normals = [0.0, 0.0, 1.0].repeat(columns * 2);
+
+        let mut indices = Vec::new();
+        for x in 0..columns as u32 - 1 {
+            let top = x + columns as u32;
+            indices.extend_from_slice(&[x, x + 1, top + 1, x, top + 1, top]);
+        }
+        (positions, normals, tex_coords, indices)
+    }
+
+    #[test]
+    fn tangents_follow_the_u_direction() {
+        let (positions, normals, tex_coords, indices) = strip(&[0.0, 1.0]);
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+        assert_close(&tangents, &[1.0, 0.0, 0.0, 1.0].repeat(6));
+
+        // u running up the quad and v to the left keeps the handedness
+        let rotated: Vec<f32> = positions
+            .chunks_exact(3)
+            .flat_map(|position| [position[1], -position[0]])
+            .collect();
+        let tangents = generate_tangents(&positions, &normals, &rotated, &indices);
+        assert_close(&tangents, &[0.0, 1.0, 0.0, 1.0].repeat(6));
+    }
+
+    #[test]
+    fn mirrored_uvs_get_their_own_tangents() {
+        // the uvs run back at the middle column, the vertices there are shared
+        let (positions, normals, tex_coords, indices) = strip(&[0.0, 1.0, 0.0]);
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+
+        let mut expected = [1.0, 0.0, 0.0, 1.0].repeat(6);
+        expected.extend([-1.0, 0.0, 0.0, -1.0].repeat(6));
+        assert_close(&tangents, &expected);
+    }
+
+    #[test]
+    fn degenerate_triangles_take_the_tangent_of_their_vertices() {
+        let (positions, normals, tex_coords, mut indices) = strip(&[0.0, 1.0]);
+        indices.extend_from_slice(&[2, 2, 3]);
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+        assert_close(&tangents, &[1.0, 0.0, 0.0, 1.0].repeat(9));
+    }
+
+    #[test]
+    fn uv_less_triangles_get_a_tangent_perpendicular_to_the_normal() {
+        let (positions, normals, _, indices) = strip(&[0.0, 1.0]);
+        let tex_coords = vec![0.0; 8];
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+        // like the reference, faces without uv area count as mirrored
+        assert_close(&tangents, &[0.0, 1.0, 0.0, -1.0].repeat(6));
+    }
+
+    #[test]
+    fn welding_splits_vertices_with_different_values() {
+        // vertex 1 is seen with 2.0, 3.0, 2.0 and 4.0
+        let corner_values = [1.0, 2.0, 1.0, 3.0, 2.0, 3.0, 1.0, 4.0, 3.0];
+        let welded = weld(&corner_values, 1, &[0, 1, 0, 1, 1, 2, 0, 1, 2], 3);
+        assert_eq!(welded.indices, vec![0, 1, 0, 3, 1, 2, 0, 4, 2]);
+        assert_eq!(welded.copies, vec![1, 1]);
+        assert_eq!(welded.values, vec![1.0, 2.0, 3.0, 3.0, 4.0]);
+    }
+}
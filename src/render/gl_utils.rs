@@ -72,8 +72,10 @@ impl From<stream::Type> for gl::types::GLenum {
         match _type {
             stream::Type::FLOAT => gl::FLOAT,
             stream::Type::INT32 => gl::INT,
+            stream::Type::INT16 => gl::SHORT,
             stream::Type::INT8 => gl::BYTE,
             stream::Type::UINT32 => gl::UNSIGNED_INT,
+            stream::Type::UINT16 => gl::UNSIGNED_SHORT,
             stream::Type::UINT8 => gl::UNSIGNED_BYTE,
             _ => gl::FLOAT,
         }
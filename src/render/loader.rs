@@ -0,0 +1,191 @@
+// loader.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use super::{
+    backend::Backend,
+    model::{self, LoadError, LoadOptions, ModelPointer},
+    texture,
+};
+
+pub type LoadId = usize;
+
+enum Request {
+    Model(LoadId, PathBuf, LoadOptions),
+    Environment(PathBuf),
+}
+
+// decoded RGB float pixels, width and height
+pub type HdrImage = (Vec<u8>, u32, u32);
+
+pub enum Loaded {
+    // the model has no GL objects yet, hand it to the upload queue
+    Model(LoadId, Result<ModelPointer, LoadError>),
+    Environment(image::ImageResult<HdrImage>),
+}
+
+// reads and decodes assets on worker threads. nothing here touches GL, the
+// results are polled on the main thread which creates the GL objects.
+pub struct AssetLoader {
+    requests: Option<mpsc::Sender<Request>>,
+    results: mpsc::Receiver<Loaded>,
+    workers: Vec<thread::JoinHandle<()>>,
+    next_id: LoadId,
+}
+
+impl AssetLoader {
+    pub fn new(worker_count: usize, texture_cache: Arc<texture::TextureCache>) -> AssetLoader {
+        let (request_sender, request_receiver) = mpsc::channel::<Request>();
+        let (result_sender, results) = mpsc::channel::<Loaded>();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+
+        let workers = (0..worker_count.max(1))
+            .map(|index| {
+                let requests = Arc::clone(&request_receiver);
+                let results = result_sender.clone();
+                let texture_cache = Arc::clone(&texture_cache);
+                thread::Builder::new()
+                    .name(format!("asset loader {}", index))
+                    .spawn(move || loop {
+                        // the lock is released before loading so the other workers can
+                        // pick up requests
+                        let request = requests.lock().unwrap().recv();
+                        let loaded = match request {
+                            Ok(request) => load(request, &texture_cache),
+                            Err(_) => break,
+                        };
+                        if results.send(loaded).is_err() {
+                            break;
+                        }
+                    })
+                    .expect("failed to spawn asset loader thread")
+            })
+            .collect();
+
+        AssetLoader {
+            requests: Some(request_sender),
+            results,
+            workers,
+            next_id: 0,
+        }
+    }
+
+    pub fn load_model(&mut self, path: PathBuf, options: LoadOptions) -> LoadId {
+        let id = self.next_id();
+        self.send(Request::Model(id, path, options));
+        id
+    }
+
+    pub fn load_environment(&mut self, path: PathBuf) {
+        self.send(Request::Environment(path));
+    }
+
+    // everything that finished since the last poll, never blocks
+    pub fn poll(&self) -> Vec<Loaded> {
+        self.results.try_iter().collect()
+    }
+
+    fn next_id(&mut self) -> LoadId {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn send(&self, request: Request) {
+        if let Some(requests) = &self.requests {
+            // the workers only stop once the sender is dropped
+            requests.send(request).unwrap();
+        }
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // closing the channel lets the workers finish what they are loading and exit
+        self.requests = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn load(request: Request, texture_cache: &texture::TextureCache) -> Loaded {
+    match request {
+        Request::Model(id, path, options) => {
+            Loaded::Model(id, model::load_model(&path, &options, texture_cache))
+        }
+        Request::Environment(path) => Loaded::Environment(texture::load_hdr_texture(&path)),
+    }
+}
+
+// creates the GL objects of loaded models on the main thread. every frame uploads
+// up to budget bytes, at least one buffer or texture, so a big model streams in over a
+// few frames instead of stalling one.
+pub struct UploadQueue {
+    models: VecDeque<(LoadId, ModelPointer)>,
+    textures: VecDeque<texture::TexturePointer>,
+    pub budget: usize,
+}
+
+impl UploadQueue {
+    pub fn new(budget: usize) -> UploadQueue {
+        UploadQueue {
+            models: VecDeque::new(),
+            textures: VecDeque::new(),
+            budget,
+        }
+    }
+
+    pub fn push_model(&mut self, id: LoadId, model: ModelPointer) {
+        self.models.push_back((id, model));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty() && self.textures.is_empty()
+    }
+
+    // returns the models whose buffers are on the GPU, their textures follow in
+    // later frames and the renderer uses the fallback textures until then
+    pub fn process(&mut self) -> Vec<(LoadId, ModelPointer)> {
+        let mut uploaded = 0;
+        let mut ready = Vec::new();
+
+        while uploaded == 0 || uploaded < self.budget {
+            if let Some((id, model)) = self.models.pop_front() {
+                {
+                    let model = &mut *model.borrow_mut();
+                    uploaded += model.vertex_buffer.data.len() + model.index_buffer.data.len();
+                    Backend::set_vertex_buffer(&mut model.vertex_buffer);
+                    Backend::set_index_buffer(&mut model.index_buffer);
+
+                    for material in &model.materials {
+                        self.textures.extend(
+                            material
+                                .textures()
+                                .into_iter()
+                                .filter(|texture| !texture.is_uploaded())
+                                .cloned(),
+                        );
+                    }
+                }
+                ready.push((id, model));
+            } else if let Some(texture) = self.textures.pop_front() {
+                // textures shared with an earlier model are already uploaded
+                if texture.upload() {
+                    uploaded += texture.byte_size();
+                }
+            } else {
+                break;
+            }
+        }
+        ready
+    }
+}
@@ -0,0 +1,297 @@
+// lut.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum CubeError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    MissingSize,
+    // only 3D tables are supported
+    Unsupported(String),
+    WrongEntryCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for CubeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CubeError::Io(error) => write!(f, "failed to read lut: {}", error),
+            CubeError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            CubeError::MissingSize => write!(f, "LUT_3D_SIZE is missing"),
+            CubeError::Unsupported(keyword) => write!(f, "{} is not supported", keyword),
+            CubeError::WrongEntryCount { expected, found } => {
+                write!(f, "expected {} entries, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CubeError {}
+
+impl From<io::Error> for CubeError {
+    fn from(error: io::Error) -> Self {
+        CubeError::Io(error)
+    }
+}
+
+// a 3D color table in the Adobe/Resolve .cube layout, red changes fastest
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cube {
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    pub data: Vec<[f32; 3]>,
+}
+
+impl Cube {
+    // maps every color to itself
+    pub fn identity(size: usize) -> Cube {
+        let scale = 1.0 / (size - 1).max(1) as f32;
+        let mut data = Vec::with_capacity(size * size * size);
+        for blue in 0..size {
+            for green in 0..size {
+                for red in 0..size {
+                    data.push([
+                        red as f32 * scale,
+                        green as f32 * scale,
+                        blue as f32 * scale,
+                    ]);
+                }
+            }
+        }
+        Cube {
+            size,
+            domain_min: [0.0, 0.0, 0.0],
+            domain_max: [1.0, 1.0, 1.0],
+            data,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Cube, CubeError> {
+        parse_cube(&fs::read_to_string(path)?)
+    }
+}
+
+pub fn parse_cube(text: &str) -> Result<Cube, CubeError> {
+    let mut size = None;
+    let mut domain_min = [0.0, 0.0, 0.0];
+    let mut domain_max = [1.0, 1.0, 1.0];
+    let mut data = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or_default();
+        match keyword {
+            "TITLE" => {}
+            "LUT_3D_SIZE" => {
+                let value = words.next().and_then(|value| value.parse::<usize>().ok());
+                size = match value {
+                    Some(value) if value >= 2 => Some(value),
+                    _ => {
+                        return Err(CubeError::Parse {
+                            line: line_number,
+                            message: String::from("LUT_3D_SIZE needs a size of at least 2"),
+                        })
+                    }
+                };
+            }
+            "DOMAIN_MIN" => domain_min = parse_triple(words, line_number)?,
+            "DOMAIN_MAX" => domain_max = parse_triple(words, line_number)?,
+            "LUT_1D_SIZE" | "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                return Err(CubeError::Unsupported(keyword.to_string()))
+            }
+            _ => data.push(parse_triple(line.split_whitespace(), line_number)?),
+        }
+    }
+
+    let size = size.ok_or(CubeError::MissingSize)?;
+    let expected = size * size * size;
+    if data.len() != expected {
+        return Err(CubeError::WrongEntryCount {
+            expected,
+            found: data.len(),
+        });
+    }
+
+    Ok(Cube {
+        size,
+        domain_min,
+        domain_max,
+        data,
+    })
+}
+
+fn parse_triple<'a, I>(mut words: I, line: usize) -> Result<[f32; 3], CubeError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut triple = [0.0; 3];
+    for value in triple.iter_mut() {
+        *value = words
+            .next()
+            .and_then(|word| word.parse().ok())
+            .ok_or_else(|| CubeError::Parse {
+                line,
+                message: String::from("expected three numbers"),
+            })?;
+    }
+    if words.next().is_some() {
+        return Err(CubeError::Parse {
+            line,
+            message: String::from("expected three numbers"),
+        });
+    }
+    Ok(triple)
+}
+
+// the cube as a GL_TEXTURE_3D, red along x
+pub struct Lut3D {
+    pub id: u32,
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+}
+
+impl Lut3D {
+    pub fn new(cube: &Cube) -> Lut3D {
+        let mut id: u32 = 0;
+        let size = cube.size as i32;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_3D, id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                gl::RGB16F as i32,
+                size,
+                size,
+                size,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                cube.data.as_ptr().cast(),
+            );
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+            gl::BindTexture(gl::TEXTURE_3D, 0);
+        }
+
+        Lut3D {
+            id,
+            size: cube.size,
+            domain_min: cube.domain_min,
+            domain_max: cube.domain_max,
+        }
+    }
+}
+
+impl Drop for Lut3D {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 2x2x2 table, red changes fastest
+    const ENTRIES: &str = "
+0 0 0
+1 0 0
+0 1 0
+1 1 0
+0 0 1
+1 0 1
+0 1 1
+1 1 1
+";
+
+    #[test]
+    fn cubes_are_parsed() {
+        let text = format!(
+            "# comment\nTITLE \"identity\"\nLUT_3D_SIZE 2\n\n{}",
+            ENTRIES
+        );
+        assert_eq!(parse_cube(&text).unwrap(), Cube::identity(2));
+    }
+
+    #[test]
+    fn domains_are_read() {
+        let text = format!(
+            "LUT_3D_SIZE 2\nDOMAIN_MIN -0.5 0 0.25\nDOMAIN_MAX 2 1.5 1\n{}",
+            ENTRIES
+        );
+        let cube = parse_cube(&text).unwrap();
+        assert_eq!(cube.domain_min, [-0.5, 0.0, 0.25]);
+        assert_eq!(cube.domain_max, [2.0, 1.5, 1.0]);
+
+        let text = format!("LUT_3D_SIZE 2\nDOMAIN_MIN 0 0\n{}", ENTRIES);
+        assert!(matches!(
+            parse_cube(&text),
+            Err(CubeError::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn the_size_is_required() {
+        assert!(matches!(parse_cube(ENTRIES), Err(CubeError::MissingSize)));
+
+        let text = format!("LUT_3D_SIZE 1\n{}", ENTRIES);
+        assert!(matches!(
+            parse_cube(&text),
+            Err(CubeError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn the_entries_have_to_fill_the_cube() {
+        let text = format!("LUT_3D_SIZE 2\n{}0 0 0\n", ENTRIES);
+        assert!(matches!(
+            parse_cube(&text),
+            Err(CubeError::WrongEntryCount {
+                expected: 8,
+                found: 9
+            })
+        ));
+
+        let text = "LUT_3D_SIZE 2\n0 0 0\n";
+        assert!(matches!(
+            parse_cube(text),
+            Err(CubeError::WrongEntryCount {
+                expected: 8,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn one_dimensional_tables_are_rejected() {
+        let text = "LUT_1D_SIZE 2\n0 0 0\n1 1 1\n";
+        match parse_cube(text) {
+            Err(CubeError::Unsupported(keyword)) => assert_eq!(keyword, "LUT_1D_SIZE"),
+            result => panic!("expected LUT_1D_SIZE to be unsupported, got {:?}", result),
+        }
+    }
+}
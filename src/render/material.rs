@@ -1,55 +1,227 @@
-// material.rs
-//
-// Created on 2022/07/16 by Dante Ruiz
-// Copyright 2022 Dante Ruiz
-//
-// Distributed under the MIT Lisense
-// https://mit-license.org/
-
-use iml;
-
-use super::texture::TexturePointer;
-pub struct Material {
-    pub albedo_map: Option<TexturePointer>,
-    pub normal_map: Option<TexturePointer>,
-    pub specular_map: Option<TexturePointer>,
-    pub emissive_map: Option<TexturePointer>,
-    pub color: iml::Vec3,
-    pub roughness: f32,
-    pub metallic: f32,
-    pub ao: f32,
-}
-
-impl Material {
-    pub fn new(color: iml::Vec3, roughness: f32, metallic: f32, ao: f32) -> Material {
-        Material {
-            albedo_map: None,
-            normal_map: None,
-            specular_map: None,
-            emissive_map: None,
-            color,
-            roughness,
-            metallic,
-            ao,
-        }
-    }
-}
-
-impl Default for Material {
-    fn default() -> Material {
-        Material {
-            albedo_map: None,
-            normal_map: None,
-            specular_map: None,
-            emissive_map: None,
-            color: iml::Vec3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            roughness: 1.0,
-            metallic: 0.0,
-            ao: 1.0,
-        }
-    }
-}
+// material.rs
+//
+// Created on 2022/07/16 by Dante Ruiz
+// Copyright 2022 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use iml;
+
+use super::texture::TexturePointer;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+// KHR_texture_transform, rotation is in radians
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+impl TextureTransform {
+    // column major translation * rotation * scale, as the extension defines it
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [
+            [cos * self.scale[0], -sin * self.scale[0], 0.0],
+            [sin * self.scale[1], cos * self.scale[1], 0.0],
+            [self.offset[0], self.offset[1], 1.0],
+        ]
+    }
+}
+
+pub struct MaterialTexture {
+    pub texture: TexturePointer,
+    // which TEXCOORD_n set the texture is sampled with
+    pub tex_coord: u32,
+    pub transform: TextureTransform,
+}
+
+// KHR_materials_clearcoat
+pub struct Clearcoat {
+    pub factor: f32,
+    pub roughness: f32,
+    pub normal_scale: f32,
+    pub map: Option<MaterialTexture>,
+    pub roughness_map: Option<MaterialTexture>,
+    pub normal_map: Option<MaterialTexture>,
+}
+
+impl Default for Clearcoat {
+    fn default() -> Clearcoat {
+        Clearcoat {
+            factor: 0.0,
+            roughness: 0.0,
+            normal_scale: 1.0,
+            map: None,
+            roughness_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+// KHR_materials_sheen
+pub struct Sheen {
+    pub color: iml::Vec3,
+    pub roughness: f32,
+    pub color_map: Option<MaterialTexture>,
+    pub roughness_map: Option<MaterialTexture>,
+}
+
+impl Default for Sheen {
+    fn default() -> Sheen {
+        Sheen {
+            color: iml::Vec3::new(0.0, 0.0, 0.0),
+            roughness: 0.0,
+            color_map: None,
+            roughness_map: None,
+        }
+    }
+}
+
+// KHR_materials_transmission
+#[derive(Default)]
+pub struct Transmission {
+    pub factor: f32,
+    pub map: Option<MaterialTexture>,
+}
+
+// KHR_materials_specular
+pub struct Specular {
+    pub factor: f32,
+    pub color: iml::Vec3,
+    pub map: Option<MaterialTexture>,
+    pub color_map: Option<MaterialTexture>,
+}
+
+impl Default for Specular {
+    fn default() -> Specular {
+        Specular {
+            factor: 1.0,
+            color: iml::Vec3::new(1.0, 1.0, 1.0),
+            map: None,
+            color_map: None,
+        }
+    }
+}
+
+pub struct Material {
+    pub albedo_map: Option<MaterialTexture>,
+    pub normal_map: Option<MaterialTexture>,
+    pub metallic_roughness_map: Option<MaterialTexture>,
+    pub occlusion_map: Option<MaterialTexture>,
+    pub emissive_map: Option<MaterialTexture>,
+    pub color: iml::Vec3,
+    pub alpha: f32,
+    pub roughness: f32,
+    pub metallic: f32,
+    pub ao: f32,
+    pub emissive: iml::Vec3,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+    pub clearcoat: Clearcoat,
+    pub sheen: Sheen,
+    pub transmission: Transmission,
+    pub specular: Specular,
+    // KHR_materials_ior
+    pub ior: f32,
+    // KHR_materials_emissive_strength
+    pub emissive_strength: f32,
+}
+
+impl Material {
+    pub fn new(color: iml::Vec3, roughness: f32, metallic: f32, ao: f32) -> Material {
+        Material {
+            color,
+            roughness,
+            metallic,
+            ao,
+            ..Material::default()
+        }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha_mode == AlphaMode::Blend
+    }
+
+    // every texture the material samples, extensions included
+    pub fn textures(&self) -> Vec<&TexturePointer> {
+        [
+            &self.albedo_map,
+            &self.normal_map,
+            &self.metallic_roughness_map,
+            &self.occlusion_map,
+            &self.emissive_map,
+            &self.clearcoat.map,
+            &self.clearcoat.roughness_map,
+            &self.clearcoat.normal_map,
+            &self.sheen.color_map,
+            &self.sheen.roughness_map,
+            &self.transmission.map,
+            &self.specular.map,
+            &self.specular.color_map,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|map| &map.texture)
+        .collect()
+    }
+}
+
+// defaults follow the glTF 2.0 spec
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            albedo_map: None,
+            normal_map: None,
+            metallic_roughness_map: None,
+            occlusion_map: None,
+            emissive_map: None,
+            color: iml::Vec3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            alpha: 1.0,
+            roughness: 1.0,
+            metallic: 0.0,
+            ao: 1.0,
+            emissive: iml::Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+            clearcoat: Clearcoat::default(),
+            sheen: Sheen::default(),
+            transmission: Transmission::default(),
+            specular: Specular::default(),
+            ior: 1.5,
+            emissive_strength: 1.0,
+        }
+    }
+}
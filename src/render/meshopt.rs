@@ -0,0 +1,882 @@
+// meshopt.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+// decoders for EXT_meshopt_compression, a port of the meshoptimizer reference decoders
+
+use serde_json::Value;
+
+use super::model::LoadError;
+
+const VERTEX_HEADER: u8 = 0xa0;
+const INDEX_HEADER: u8 = 0xe0;
+const SEQUENCE_HEADER: u8 = 0xd0;
+
+const VERTEX_BLOCK_SIZE_BYTES: usize = 8192;
+const VERTEX_BLOCK_MAX_SIZE: usize = 256;
+const BYTE_GROUP_SIZE: usize = 16;
+const BYTE_GROUP_DECODE_LIMIT: usize = 24;
+const TAIL_MAX_SIZE: usize = 32;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Mode {
+    Attributes,
+    Triangles,
+    Indices,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Filter {
+    None,
+    Octahedral,
+    Quaternion,
+    Exponential,
+}
+
+// the EXT_meshopt_compression object of a buffer view
+pub struct CompressedView {
+    pub buffer: usize,
+    pub offset: usize,
+    pub length: usize,
+    pub stride: usize,
+    pub count: usize,
+    pub mode: Mode,
+    pub filter: Filter,
+}
+
+impl CompressedView {
+    pub fn from_json(extension: &Value) -> Result<CompressedView, LoadError> {
+        let field = |name: &str| -> Result<usize, LoadError> {
+            extension
+                .get(name)
+                .and_then(|value| value.as_u64())
+                .map(|value| value as usize)
+                .ok_or_else(|| LoadError::Parse(format!("meshopt view has no '{}'", name)))
+        };
+
+        let mode = match extension.get("mode").and_then(|mode| mode.as_str()) {
+            Some("ATTRIBUTES") => Mode::Attributes,
+            Some("TRIANGLES") => Mode::Triangles,
+            Some("INDICES") => Mode::Indices,
+            mode => return Err(LoadError::Parse(format!("unknown meshopt mode {:?}", mode))),
+        };
+
+        let filter = match extension.get("filter").and_then(|filter| filter.as_str()) {
+            None | Some("NONE") => Filter::None,
+            Some("OCTAHEDRAL") => Filter::Octahedral,
+            Some("QUATERNION") => Filter::Quaternion,
+            Some("EXPONENTIAL") => Filter::Exponential,
+            Some(filter) => {
+                return Err(LoadError::Parse(format!(
+                    "unknown meshopt filter '{}'",
+                    filter
+                )))
+            }
+        };
+
+        Ok(CompressedView {
+            buffer: field("buffer")?,
+            offset: extension
+                .get("byteOffset")
+                .and_then(|value| value.as_u64())
+                .unwrap_or(0) as usize,
+            length: field("byteLength")?,
+            stride: field("byteStride")?,
+            count: field("count")?,
+            mode,
+            filter,
+        })
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>, LoadError> {
+        let mut decoded = match self.mode {
+            Mode::Attributes => decode_vertex_buffer(data, self.count, self.stride)?,
+            Mode::Triangles => decode_index_buffer(data, self.count, self.stride)?,
+            Mode::Indices => decode_index_sequence(data, self.count, self.stride)?,
+        };
+
+        match self.filter {
+            Filter::None => {}
+            Filter::Octahedral => decode_filter_octahedral(&mut decoded, self.stride)?,
+            Filter::Quaternion => decode_filter_quaternion(&mut decoded, self.stride)?,
+            Filter::Exponential => decode_filter_exponential(&mut decoded, self.stride)?,
+        }
+        Ok(decoded)
+    }
+}
+
+fn truncated() -> LoadError {
+    LoadError::Parse(String::from("meshopt data is truncated"))
+}
+
+fn byte(data: &[u8], position: usize) -> Result<u8, LoadError> {
+    data.get(position).copied().ok_or_else(truncated)
+}
+
+fn unzigzag8(value: u8) -> u8 {
+    (value & 1).wrapping_neg() ^ (value >> 1)
+}
+
+fn unzigzag32(value: u32) -> u32 {
+    (value & 1).wrapping_neg() ^ (value >> 1)
+}
+
+fn vertex_block_size(vertex_size: usize) -> usize {
+    let size = (VERTEX_BLOCK_SIZE_BYTES / vertex_size) & !(BYTE_GROUP_SIZE - 1);
+    size.min(VERTEX_BLOCK_MAX_SIZE)
+}
+
+pub fn decode_vertex_buffer(
+    data: &[u8],
+    vertex_count: usize,
+    vertex_size: usize,
+) -> Result<Vec<u8>, LoadError> {
+    if vertex_size == 0 || vertex_size > 256 || vertex_size % 4 != 0 {
+        return Err(LoadError::Parse(format!(
+            "invalid meshopt vertex size {}",
+            vertex_size
+        )));
+    }
+
+    let header = byte(data, 0)?;
+    // only version 0 is allowed by the glTF extension
+    if header != VERTEX_HEADER {
+        return Err(LoadError::Parse(format!(
+            "unsupported meshopt vertex codec {:#x}",
+            header
+        )));
+    }
+
+    let tail_size = vertex_size.max(TAIL_MAX_SIZE);
+    if data.len() < 1 + tail_size {
+        return Err(truncated());
+    }
+
+    // the tail holds the baseline the first block is delta encoded from
+    let mut last_vertex = data[data.len() - vertex_size..].to_vec();
+
+    let mut vertices = vec![0u8; vertex_count * vertex_size];
+    let block_size = vertex_block_size(vertex_size);
+    let mut position = 1;
+    let mut vertex_offset = 0;
+    while vertex_offset < vertex_count {
+        let count = block_size.min(vertex_count - vertex_offset);
+        let block =
+            &mut vertices[vertex_offset * vertex_size..(vertex_offset + count) * vertex_size];
+        position =
+            decode_vertex_block(data, position, block, count, vertex_size, &mut last_vertex)?;
+        vertex_offset += count;
+    }
+
+    if data.len() - position != tail_size {
+        return Err(LoadError::Parse(String::from(
+            "meshopt vertex data has trailing bytes",
+        )));
+    }
+    Ok(vertices)
+}
+
+fn decode_vertex_block(
+    data: &[u8],
+    mut position: usize,
+    vertices: &mut [u8],
+    vertex_count: usize,
+    vertex_size: usize,
+    last_vertex: &mut [u8],
+) -> Result<usize, LoadError> {
+    let aligned_count = (vertex_count + BYTE_GROUP_SIZE - 1) & !(BYTE_GROUP_SIZE - 1);
+    let mut buffer = [0u8; VERTEX_BLOCK_MAX_SIZE];
+
+    // the bytes are stored transposed, all the first bytes of the block, then the second...
+    for k in 0..vertex_size {
+        position = decode_bytes(data, position, &mut buffer[..aligned_count])?;
+
+        let mut previous = last_vertex[k];
+        for (vertex, delta) in buffer.iter().take(vertex_count).enumerate() {
+            let value = unzigzag8(*delta).wrapping_add(previous);
+            vertices[vertex * vertex_size + k] = value;
+            previous = value;
+        }
+    }
+
+    last_vertex.copy_from_slice(&vertices[(vertex_count - 1) * vertex_size..]);
+    Ok(position)
+}
+
+fn decode_bytes(data: &[u8], position: usize, buffer: &mut [u8]) -> Result<usize, LoadError> {
+    // two bits per group of sixteen bytes select how many bits each byte takes
+    let header_size = (buffer.len() / BYTE_GROUP_SIZE).div_ceil(4);
+    if data.len() < position + header_size {
+        return Err(truncated());
+    }
+    let header = &data[position..position + header_size];
+    let mut position = position + header_size;
+
+    for (group, bytes) in buffer.chunks_exact_mut(BYTE_GROUP_SIZE).enumerate() {
+        if data.len() < position + BYTE_GROUP_DECODE_LIMIT {
+            return Err(truncated());
+        }
+        let bits_log2 = (header[group / 4] >> ((group % 4) * 2)) & 3;
+        position = decode_bytes_group(data, position, bytes, bits_log2)?;
+    }
+    Ok(position)
+}
+
+fn decode_bytes_group(
+    data: &[u8],
+    position: usize,
+    bytes: &mut [u8],
+    bits_log2: u8,
+) -> Result<usize, LoadError> {
+    match bits_log2 {
+        0 => {
+            bytes.fill(0);
+            Ok(position)
+        }
+        3 => {
+            let end = position + BYTE_GROUP_SIZE;
+            bytes.copy_from_slice(data.get(position..end).ok_or_else(truncated)?);
+            Ok(end)
+        }
+        _ => {
+            // packed 2 or 4 bit values, the all ones value escapes to a full byte stored
+            // after the packed values
+            let bits = if bits_log2 == 1 { 2 } else { 4 };
+            let sentinel = (1u8 << bits) - 1;
+            let mut escape = position + BYTE_GROUP_SIZE * bits / 8;
+            for (index, value) in bytes.iter_mut().enumerate() {
+                let packed = byte(data, position + index * bits / 8)?;
+                let shift = 8 - bits - (index * bits) % 8;
+                let encoded = (packed >> shift) & sentinel;
+                *value = if encoded == sentinel {
+                    escape += 1;
+                    byte(data, escape - 1)?
+                } else {
+                    encoded
+                };
+            }
+            Ok(escape)
+        }
+    }
+}
+
+fn read_vbyte(data: &[u8], position: &mut usize) -> Result<u32, LoadError> {
+    let lead = byte(data, *position)?;
+    *position += 1;
+    if lead < 128 {
+        return Ok(lead as u32);
+    }
+
+    let mut result = (lead & 127) as u32;
+    let mut shift = 7;
+    for _ in 0..4 {
+        let group = byte(data, *position)?;
+        *position += 1;
+        result |= ((group & 127) as u32) << shift;
+        shift += 7;
+        if group < 128 {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+fn read_index(data: &[u8], position: &mut usize, last: u32) -> Result<u32, LoadError> {
+    let value = read_vbyte(data, position)?;
+    Ok(last.wrapping_add(unzigzag32(value)))
+}
+
+fn write_index(indices: &mut [u8], index_size: usize, position: usize, index: u32) {
+    let start = position * index_size;
+    match index_size {
+        2 => indices[start..start + 2].copy_from_slice(&(index as u16).to_le_bytes()),
+        _ => indices[start..start + 4].copy_from_slice(&index.to_le_bytes()),
+    }
+}
+
+fn push_vertex(fifo: &mut [u32; 16], offset: &mut usize, vertex: u32, advance: bool) {
+    fifo[*offset] = vertex;
+    *offset = (*offset + advance as usize) & 15;
+}
+
+fn push_edge(fifo: &mut [[u32; 2]; 16], offset: &mut usize, a: u32, b: u32) {
+    fifo[*offset] = [a, b];
+    *offset = (*offset + 1) & 15;
+}
+
+// the index codec rebuilds triangles from a fifo of recent edges and vertices
+pub fn decode_index_buffer(
+    data: &[u8],
+    index_count: usize,
+    index_size: usize,
+) -> Result<Vec<u8>, LoadError> {
+    if index_count % 3 != 0 || (index_size != 2 && index_size != 4) {
+        return Err(LoadError::Parse(String::from(
+            "meshopt triangles need a multiple of three 16 or 32 bit indices",
+        )));
+    }
+    let triangle_count = index_count / 3;
+    if data.len() < 1 + triangle_count + 16 {
+        return Err(truncated());
+    }
+
+    let header = data[0];
+    let version = header & 0x0f;
+    if header & 0xf0 != INDEX_HEADER || version > 1 {
+        return Err(LoadError::Parse(format!(
+            "unsupported meshopt index codec {:#x}",
+            header
+        )));
+    }
+
+    let codes = &data[1..1 + triangle_count];
+    // the last 16 bytes are a lookup table for the common triangle codes
+    let data_end = data.len() - 16;
+    let aux_table = &data[data_end..];
+    let mut position = 1 + triangle_count;
+
+    let mut edge_fifo = [[u32::MAX; 2]; 16];
+    let mut vertex_fifo = [u32::MAX; 16];
+    let mut edge_offset = 0usize;
+    let mut vertex_offset = 0usize;
+    let mut next = 0u32;
+    let mut last = 0u32;
+    let fec_max = if version >= 1 { 13 } else { 15 };
+
+    let mut indices = vec![0u8; index_count * index_size];
+    for (triangle, code) in codes.iter().enumerate() {
+        if position > data_end {
+            return Err(truncated());
+        }
+
+        let (a, b, c) = if *code < 0xf0 {
+            // the triangle shares an edge with a recent one
+            let fe = (*code >> 4) as usize;
+            let [a, b] = edge_fifo[(edge_offset.wrapping_sub(1 + fe)) & 15];
+            let fec = (*code & 15) as usize;
+
+            let c = if fec < fec_max {
+                let c = match fec {
+                    0 => next,
+                    _ => vertex_fifo[(vertex_offset.wrapping_sub(1 + fec)) & 15],
+                };
+                if fec == 0 {
+                    next += 1;
+                }
+                push_vertex(&mut vertex_fifo, &mut vertex_offset, c, fec == 0);
+                c
+            } else {
+                // 13 and 14 are the neighbours of the last free index
+                last = match fec {
+                    13 => last.wrapping_sub(1),
+                    14 => last.wrapping_add(1),
+                    _ => read_index(data, &mut position, last)?,
+                };
+                push_vertex(&mut vertex_fifo, &mut vertex_offset, last, true);
+                last
+            };
+
+            push_edge(&mut edge_fifo, &mut edge_offset, c, b);
+            push_edge(&mut edge_fifo, &mut edge_offset, a, c);
+            (a, b, c)
+        } else if *code < 0xfe {
+            let aux = aux_table[(*code & 15) as usize];
+            let feb = (aux >> 4) as usize;
+            let fec = (aux & 15) as usize;
+
+            let a = next;
+            next += 1;
+            let b = match feb {
+                0 => next,
+                _ => vertex_fifo[(vertex_offset.wrapping_sub(feb)) & 15],
+            };
+            if feb == 0 {
+                next += 1;
+            }
+            let c = match fec {
+                0 => next,
+                _ => vertex_fifo[(vertex_offset.wrapping_sub(fec)) & 15],
+            };
+            if fec == 0 {
+                next += 1;
+            }
+
+            push_vertex(&mut vertex_fifo, &mut vertex_offset, a, true);
+            push_vertex(&mut vertex_fifo, &mut vertex_offset, b, feb == 0);
+            push_vertex(&mut vertex_fifo, &mut vertex_offset, c, fec == 0);
+            push_edge(&mut edge_fifo, &mut edge_offset, b, a);
+            push_edge(&mut edge_fifo, &mut edge_offset, c, b);
+            push_edge(&mut edge_fifo, &mut edge_offset, a, c);
+            (a, b, c)
+        } else {
+            let aux = byte(data, position)?;
+            position += 1;
+            let fea = if *code == 0xfe { 0 } else { 15 };
+            let feb = (aux >> 4) as usize;
+            let fec = (aux & 15) as usize;
+
+            // a zero aux outside the table restarts the vertex numbering
+            if aux == 0 {
+                next = 0;
+            }
+
+            let mut a = 0;
+            if fea == 0 {
+                a = next;
+                next += 1;
+            }
+            let mut b = match feb {
+                0 => {
+                    next += 1;
+                    next - 1
+                }
+                _ => vertex_fifo[(vertex_offset.wrapping_sub(feb)) & 15],
+            };
+            let mut c = match fec {
+                0 => {
+                    next += 1;
+                    next - 1
+                }
+                _ => vertex_fifo[(vertex_offset.wrapping_sub(fec)) & 15],
+            };
+
+            if fea == 15 {
+                last = read_index(data, &mut position, last)?;
+                a = last;
+            }
+            if feb == 15 {
+                last = read_index(data, &mut position, last)?;
+                b = last;
+            }
+            if fec == 15 {
+                last = read_index(data, &mut position, last)?;
+                c = last;
+            }
+
+            push_vertex(&mut vertex_fifo, &mut vertex_offset, a, true);
+            push_vertex(
+                &mut vertex_fifo,
+                &mut vertex_offset,
+                b,
+                feb == 0 || feb == 15,
+            );
+            push_vertex(
+                &mut vertex_fifo,
+                &mut vertex_offset,
+                c,
+                fec == 0 || fec == 15,
+            );
+            push_edge(&mut edge_fifo, &mut edge_offset, b, a);
+            push_edge(&mut edge_fifo, &mut edge_offset, c, b);
+            push_edge(&mut edge_fifo, &mut edge_offset, a, c);
+            (a, b, c)
+        };
+
+        write_index(&mut indices, index_size, triangle * 3, a);
+        write_index(&mut indices, index_size, triangle * 3 + 1, b);
+        write_index(&mut indices, index_size, triangle * 3 + 2, c);
+    }
+
+    if position != data_end {
+        return Err(LoadError::Parse(String::from(
+            "meshopt index data has trailing bytes",
+        )));
+    }
+    Ok(indices)
+}
+
+// index sequences are deltas from one of two baselines, used for non triangle indices
+pub fn decode_index_sequence(
+    data: &[u8],
+    index_count: usize,
+    index_size: usize,
+) -> Result<Vec<u8>, LoadError> {
+    if index_size != 2 && index_size != 4 {
+        return Err(LoadError::Parse(String::from(
+            "meshopt indices need to be 16 or 32 bit",
+        )));
+    }
+    if data.len() < 1 + index_count + 4 {
+        return Err(truncated());
+    }
+
+    let header = data[0];
+    if header & 0xf0 != SEQUENCE_HEADER || header & 0x0f > 1 {
+        return Err(LoadError::Parse(format!(
+            "unsupported meshopt index sequence codec {:#x}",
+            header
+        )));
+    }
+
+    let data_end = data.len() - 4;
+    let mut position = 1;
+    let mut last = [0u32; 2];
+    let mut indices = vec![0u8; index_count * index_size];
+    for index in 0..index_count {
+        if position >= data_end {
+            return Err(truncated());
+        }
+
+        let value = read_vbyte(data, &mut position)?;
+        let baseline = (value & 1) as usize;
+        let delta = unzigzag32(value >> 1);
+        last[baseline] = last[baseline].wrapping_add(delta);
+        write_index(&mut indices, index_size, index, last[baseline]);
+    }
+
+    if position != data_end {
+        return Err(LoadError::Parse(String::from(
+            "meshopt index data has trailing bytes",
+        )));
+    }
+    Ok(indices)
+}
+
+fn round(value: f32) -> i32 {
+    (value + if value >= 0.0 { 0.5 } else { -0.5 }) as i32
+}
+
+// normals and tangents stored as octahedral x, y with the scale in z, w is left alone
+pub fn decode_filter_octahedral(data: &mut [u8], stride: usize) -> Result<(), LoadError> {
+    match stride {
+        4 => {
+            for element in data.chunks_exact_mut(4) {
+                let [x, y, z] = decode_octahedral(
+                    [
+                        element[0] as i8 as f32,
+                        element[1] as i8 as f32,
+                        element[2] as i8 as f32,
+                    ],
+                    i8::MAX as f32,
+                );
+                element[0] = x as i8 as u8;
+                element[1] = y as i8 as u8;
+                element[2] = z as i8 as u8;
+            }
+            Ok(())
+        }
+        8 => {
+            for element in data.chunks_exact_mut(8) {
+                let component = |index: usize| {
+                    i16::from_le_bytes([element[index * 2], element[index * 2 + 1]]) as f32
+                };
+                let decoded =
+                    decode_octahedral([component(0), component(1), component(2)], i16::MAX as f32);
+                for (index, value) in decoded.iter().enumerate() {
+                    element[index * 2..index * 2 + 2]
+                        .copy_from_slice(&(*value as i16).to_le_bytes());
+                }
+            }
+            Ok(())
+        }
+        _ => Err(LoadError::Parse(format!(
+            "invalid stride {} for the octahedral filter",
+            stride
+        ))),
+    }
+}
+
+fn decode_octahedral(encoded: [f32; 3], max: f32) -> [i32; 3] {
+    let [mut x, mut y, z] = encoded;
+    let z = z - x.abs() - y.abs();
+
+    // fold the lower hemisphere back
+    let t = z.min(0.0);
+    x += if x >= 0.0 { t } else { -t };
+    y += if y >= 0.0 { t } else { -t };
+
+    let scale = max / (x * x + y * y + z * z).sqrt();
+    [round(x * scale), round(y * scale), round(z * scale)]
+}
+
+// rotations stored as the three smallest components, the largest one is rebuilt
+pub fn decode_filter_quaternion(data: &mut [u8], stride: usize) -> Result<(), LoadError> {
+    if stride != 8 {
+        return Err(LoadError::Parse(format!(
+            "invalid stride {} for the quaternion filter",
+            stride
+        )));
+    }
+
+    for element in data.chunks_exact_mut(8) {
+        let component =
+            |index: usize| i16::from_le_bytes([element[index * 2], element[index * 2 + 1]]);
+        let encoded = [component(0), component(1), component(2), component(3)];
+
+        // the scale lives in the high bits of w, the index of the largest component in the
+        // low two
+        let scale = std::f32::consts::FRAC_1_SQRT_2 / (encoded[3] | 3) as f32;
+        let x = encoded[0] as f32 * scale;
+        let y = encoded[1] as f32 * scale;
+        let z = encoded[2] as f32 * scale;
+        let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+
+        let largest = (encoded[3] & 3) as usize;
+        let decoded = [
+            ((largest + 1) & 3, x),
+            ((largest + 2) & 3, y),
+            ((largest + 3) & 3, z),
+            (largest, w),
+        ];
+        for (index, value) in decoded {
+            let value = round(value * i16::MAX as f32) as i16;
+            element[index * 2..index * 2 + 2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+    Ok(())
+}
+
+// 24 bit mantissas with a shared 8 bit exponent per value
+pub fn decode_filter_exponential(data: &mut [u8], stride: usize) -> Result<(), LoadError> {
+    if stride % 4 != 0 {
+        return Err(LoadError::Parse(format!(
+            "invalid stride {} for the exponential filter",
+            stride
+        )));
+    }
+
+    for value in data.chunks_exact_mut(4) {
+        let encoded = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+        let mantissa = ((encoded << 8) as i32) >> 8;
+        let exponent = (encoded as i32) >> 24;
+        let decoded = 2f32.powi(exponent) * mantissa as f32;
+        value.copy_from_slice(&decoded.to_le_bytes());
+    }
+    Ok(())
+}
+
+// decodes every compressed buffer view into the buffer it points at, the fallback
+// buffers of these files usually carry no data of their own
+pub fn decode_buffer_views(json: &Value, buffers: &mut [Vec<u8>]) -> Result<(), LoadError> {
+    let views = match json.get("bufferViews").and_then(|views| views.as_array()) {
+        Some(views) => views,
+        None => return Ok(()),
+    };
+
+    for view in views {
+        let extension = match view
+            .get("extensions")
+            .and_then(|extensions| extensions.get("EXT_meshopt_compression"))
+        {
+            Some(extension) => extension,
+            None => continue,
+        };
+
+        let compressed = CompressedView::from_json(extension)?;
+        let source = buffers
+            .get(compressed.buffer)
+            .and_then(|buffer| buffer.get(compressed.offset..compressed.offset + compressed.length))
+            .ok_or_else(truncated)?;
+        let decoded = compressed.decode(source)?;
+
+        let buffer = view
+            .get("buffer")
+            .and_then(|buffer| buffer.as_u64())
+            .unwrap_or(0) as usize;
+        let offset = view
+            .get("byteOffset")
+            .and_then(|offset| offset.as_u64())
+            .unwrap_or(0) as usize;
+        let target = buffers
+            .get_mut(buffer)
+            .and_then(|buffer| buffer.get_mut(offset..offset + decoded.len()))
+            .ok_or_else(truncated)?;
+        target.copy_from_slice(&decoded);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the encoded buffers are the ones meshoptimizer tests its own decoders with
+
+    // position u16 x3, octahedral normal u8 x2, uv u16 x2
+    const VERTICES: [[u16; 5]; 4] = [
+        [0, 0, 0, 0, 0],
+        [300, 0, 0, 500, 0],
+        [0, 300, 0, 0, 500],
+        [300, 300, 0, 500, 500],
+    ];
+
+    const VERTEX_DATA_V0: [u8; 85] = [
+        0xa0, 0x01, 0x3f, 0x00, 0x00, 0x00, 0x58, 0x57, 0x58, 0x01, 0x26, 0x00, 0x00, 0x00, 0x01,
+        0x0c, 0x00, 0x00, 0x00, 0x58, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0x3f, 0x00, 0x00, 0x00, 0x17, 0x18, 0x17, 0x01, 0x26, 0x00, 0x00, 0x00, 0x01, 0x0c, 0x00,
+        0x00, 0x00, 0x17, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    const INDICES: [u32; 12] = [0, 1, 2, 2, 1, 3, 4, 6, 5, 7, 8, 9];
+
+    const INDEX_DATA_V0: [u8; 27] = [
+        0xe0, 0xf0, 0x10, 0xfe, 0xff, 0xf0, 0x0c, 0xff, 0x02, 0x02, 0x02, 0x00, 0x76, 0x87, 0x56,
+        0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68, 0x98, 0x01, 0x69, 0x00, 0x00,
+    ];
+
+    // restarts the vertex numbering and uses the last free index
+    const INDICES_V1: [u32; 15] = [0, 1, 2, 2, 1, 3, 0, 1, 2, 2, 1, 5, 2, 1, 4];
+
+    const INDEX_DATA_V1: [u8; 24] = [
+        0xe1, 0xf0, 0x10, 0xfe, 0x1f, 0x3d, 0x00, 0x0a, 0x00, 0x76, 0x87, 0x56, 0x67, 0x78, 0xa9,
+        0x86, 0x65, 0x89, 0x68, 0x98, 0x01, 0x69, 0x00, 0x00,
+    ];
+
+    const INDEX_SEQUENCE: [u32; 6] = [0, 1, 51, 2, 49, 1000];
+
+    const INDEX_SEQUENCE_DATA: [u8; 13] = [
+        0xd1, 0x00, 0x04, 0xcd, 0x01, 0x04, 0x07, 0x98, 0x1f, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn u32_bytes(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn u16_bytes(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn is_parse_error<T>(result: Result<T, LoadError>) -> bool {
+        matches!(result, Err(LoadError::Parse(_)))
+    }
+
+    #[test]
+    fn vertex_codec_v0() {
+        let expected: Vec<u8> = VERTICES
+            .iter()
+            .flat_map(|&[x, y, z, u, v]| {
+                let mut vertex = u16_bytes(&[x, y, z]);
+                // the normal bytes are all zero
+                vertex.extend([0, 0]);
+                vertex.extend(u16_bytes(&[u, v]));
+                vertex
+            })
+            .collect();
+        assert_eq!(
+            decode_vertex_buffer(&VERTEX_DATA_V0, 4, 12).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn index_codec_v0() {
+        let decoded = decode_index_buffer(&INDEX_DATA_V0, INDICES.len(), 4).unwrap();
+        assert_eq!(decoded, u32_bytes(&INDICES));
+
+        let short_indices: Vec<u16> = INDICES.iter().map(|index| *index as u16).collect();
+        let decoded = decode_index_buffer(&INDEX_DATA_V0, INDICES.len(), 2).unwrap();
+        assert_eq!(decoded, u16_bytes(&short_indices));
+    }
+
+    #[test]
+    fn index_codec_v1() {
+        let decoded = decode_index_buffer(&INDEX_DATA_V1, INDICES_V1.len(), 4).unwrap();
+        assert_eq!(decoded, u32_bytes(&INDICES_V1));
+    }
+
+    #[test]
+    fn index_sequence() {
+        let decoded = decode_index_sequence(&INDEX_SEQUENCE_DATA, INDEX_SEQUENCE.len(), 4).unwrap();
+        assert_eq!(decoded, u32_bytes(&INDEX_SEQUENCE));
+    }
+
+    #[test]
+    fn octahedral_filter_8_bit() {
+        // the last normal points below the xy plane and is folded back
+        let mut data = [
+            0, 1, 127, 0, //
+            0, 187, 127, 1, //
+            255, 1, 127, 0, //
+            14, 130, 127, 1,
+        ];
+        decode_filter_octahedral(&mut data, 4).unwrap();
+        assert_eq!(
+            data,
+            [
+                0, 1, 127, 0, //
+                0, 159, 82, 1, //
+                255, 1, 127, 0, //
+                1, 130, 241, 1,
+            ]
+        );
+    }
+
+    #[test]
+    fn octahedral_filter_16_bit() {
+        let mut data = u16_bytes(&[
+            0, 1, 2047, 0, //
+            0, 1870, 2047, 1, //
+            2017, 1, 2047, 0, //
+            14, 1300, 2047, 1,
+        ]);
+        decode_filter_octahedral(&mut data, 8).unwrap();
+        let expected = u16_bytes(&[
+            0, 16, 32767, 0, //
+            0, 32621, 3088, 1, //
+            32764, 16, 471, 0, //
+            307, 28541, 16093, 1,
+        ]);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn quaternion_filter() {
+        let mut data = u16_bytes(&[
+            0, 1, 0, 0x7fc, //
+            0, 1870, 0, 0x7fd, //
+            2017, 1, 0, 0x7fe, //
+            14, 1300, 0, 0x7ff,
+        ]);
+        decode_filter_quaternion(&mut data, 8).unwrap();
+        let expected = u16_bytes(&[
+            32767, 0, 11, 0, //
+            0, 25013, 0, 21166, //
+            11, 0, 23504, 22830, //
+            158, 14715, 0, 29277,
+        ]);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn exponential_filter() {
+        let mut data = u32_bytes(&[0, 0xff000003, 0x02fffff7, 0xfe7fffff]);
+        decode_filter_exponential(&mut data, 4).unwrap();
+        assert_eq!(data, u32_bytes(&[0, 0x3fc00000, 0xc2100000, 0x49fffffe]));
+    }
+
+    #[test]
+    fn truncated_data_fails_to_decode() {
+        let vertex_data = &VERTEX_DATA_V0[..VERTEX_DATA_V0.len() - 1];
+        assert!(is_parse_error(decode_vertex_buffer(vertex_data, 4, 12)));
+        let index_data = &INDEX_DATA_V0[..INDEX_DATA_V0.len() - 1];
+        assert!(is_parse_error(decode_index_buffer(index_data, 12, 4)));
+        let sequence_data = &INDEX_SEQUENCE_DATA[..INDEX_SEQUENCE_DATA.len() - 1];
+        assert!(is_parse_error(decode_index_sequence(sequence_data, 6, 4)));
+        assert!(is_parse_error(decode_vertex_buffer(&[], 4, 12)));
+    }
+
+    #[test]
+    fn trailing_bytes_fail_to_decode() {
+        let mut vertex_data = VERTEX_DATA_V0.to_vec();
+        vertex_data.push(0);
+        assert!(is_parse_error(decode_vertex_buffer(&vertex_data, 4, 12)));
+        let mut index_data = INDEX_DATA_V0.to_vec();
+        index_data.push(0);
+        assert!(is_parse_error(decode_index_buffer(&index_data, 12, 4)));
+        let mut sequence_data = INDEX_SEQUENCE_DATA.to_vec();
+        sequence_data.push(0);
+        assert!(is_parse_error(decode_index_sequence(&sequence_data, 6, 4)));
+    }
+}
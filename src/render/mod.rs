@@ -6,14 +6,29 @@
 // Distributed under the MIT Lisense
 // https://mit-license.org/
 
+pub mod animation;
 pub mod backend;
+pub mod bloom;
 pub mod buffer;
+pub mod capture;
 pub mod egui_painter;
+pub mod export;
+pub mod exposure;
+pub mod framebuffer;
+pub mod geometry;
+pub mod loader;
+pub mod lut;
+pub mod meshopt;
 pub mod model;
+pub mod morph;
+pub mod obj;
+pub mod ply;
+pub mod postprocess;
 pub mod shader;
 pub mod skybox;
 pub mod stream;
 pub mod texture;
+pub mod tonemap;
 pub use buffer::Buffer;
 pub use model::{Model, ModelCache, Shape, SubMesh};
 pub mod gl_utils;
@@ -8,18 +8,92 @@
 
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
 
 use super::{
-    buffer,
-    material::Material,
-    stream::{Attribute, Dimension, Format, Slot, Type, Usage},
+    animation, buffer, geometry,
+    material::{AlphaMode, Material, MaterialTexture, TextureTransform},
+    meshopt, morph, obj, ply,
+    stream::{self, Attribute, Attributes, Dimension, Format, Slot, Type, Usage},
     texture,
 };
 
 use gltf;
 use iml;
+use serde::{Deserialize, Serialize};
+
+const FLOAT_SIZE: usize = std::mem::size_of::<f32>();
+
+#[derive(Debug)]
+pub enum LoadError {
+    Import(gltf::Error),
+    NoScene,
+    MissingPositions { mesh: usize, primitive: usize },
+    Io(std::io::Error),
+    // malformed OBJ, MTL or PLY data
+    Parse(String),
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Import(error) => write!(f, "failed to import gltf: {}", error),
+            LoadError::NoScene => write!(f, "no default scene in gltf"),
+            LoadError::MissingPositions { mesh, primitive } => write!(
+                f,
+                "primitive {} of mesh {} has no POSITION attribute",
+                primitive, mesh
+            ),
+            LoadError::Io(error) => write!(f, "failed to read model: {}", error),
+            LoadError::Parse(message) => write!(f, "failed to parse model: {}", message),
+            LoadError::UnsupportedFormat(extension) => {
+                write!(f, "no importer for '{}' files", extension)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
 
-#[derive(Hash, PartialEq, Eq)]
+impl From<gltf::Error> for LoadError {
+    fn from(error: gltf::Error) -> Self {
+        LoadError::Import(error)
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+// how normals are generated for primitives that do not provide them
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NormalGeneration {
+    Flat,
+    Smooth,
+}
+
+pub struct LoadOptions {
+    pub normals: NormalGeneration,
+    // applied to every texture of the model, 1.0 disables it
+    pub anisotropy: f32,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        // the glTF spec asks for flat normals when they are missing
+        Self {
+            normals: NormalGeneration::Flat,
+            anisotropy: 8.0,
+        }
+    }
+}
+
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Shape {
     Cube,
     Sphere,
@@ -30,38 +104,67 @@ pub struct SubMesh {
     pub start_index: usize,
     pub num_indices: usize,
     pub material_index: usize,
+    // bounding box center in mesh space, used to sort transparent sub meshes
+    pub center: [f32; 3],
 }
 
 impl SubMesh {
-    fn new(start_index: usize, num_indices: usize, material_index: usize) -> SubMesh {
+    fn new(
+        start_index: usize,
+        num_indices: usize,
+        material_index: usize,
+        center: [f32; 3],
+    ) -> SubMesh {
         SubMesh {
             start_index,
             num_indices,
             material_index,
+            center,
         }
     }
 }
 
 pub struct Mesh {
     pub matrix: iml::Mat4,
+    // the glTF node the mesh hangs off, animated nodes move the mesh with them
+    pub node: Option<usize>,
     pub sub_meshes: Vec<SubMesh>,
+    pub skin: Option<usize>,
+    pub morph: Option<morph::MorphMesh>,
 }
 
 impl Default for Mesh {
     fn default() -> Self {
         Self {
             matrix: iml::Mat4::identity(),
+            node: None,
             sub_meshes: Vec::new(),
+            skin: None,
+            morph: None,
         }
     }
 }
 
+pub struct Node {
+    pub parent: Option<usize>,
+    pub rest_pose: animation::Pose,
+}
+
 pub struct Model {
     pub index_buffer: buffer::Buffer,
     pub vertex_buffer: buffer::Buffer,
     pub attributes: Vec<Attribute>,
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
+    pub nodes: Vec<Node>,
+    pub skins: Vec<animation::Skin>,
+    pub animations: Vec<animation::Clip>,
+    pub animator: animation::Animator,
+    // one palette per skin, refreshed by update_animation
+    pub joint_matrices: Vec<Vec<animation::Matrix4>>,
+    // problems that did not stop the load, like a missing texture, for the caller to
+    // report
+    pub warnings: Vec<String>,
 }
 
 impl Default for Model {
@@ -72,6 +175,88 @@ impl Default for Model {
             meshes: Vec::new(),
             attributes: Vec::new(),
             materials: Vec::new(),
+            nodes: Vec::new(),
+            skins: Vec::new(),
+            animations: Vec::new(),
+            animator: animation::Animator::default(),
+            joint_matrices: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Model {
+    pub fn update_animation(&mut self, delta_time: f32) {
+        let morphed = self.meshes.iter().any(|mesh| mesh.morph.is_some());
+        if self.skins.is_empty() && !morphed && self.animator.clip.is_none() {
+            return;
+        }
+
+        let mut poses: Vec<animation::Pose> =
+            self.nodes.iter().map(|node| node.rest_pose).collect();
+        // empty until a channel animates the node, the mesh weights are used instead
+        let mut weights: Vec<Vec<f32>> = vec![Vec::new(); self.nodes.len()];
+
+        if let Some(clip) = self
+            .animator
+            .clip
+            .and_then(|index| self.animations.get(index))
+        {
+            self.animator.advance(delta_time, clip.duration);
+            clip.evaluate(self.animator.time, &mut poses, &mut weights);
+        }
+
+        let parents: Vec<Option<usize>> = self.nodes.iter().map(|node| node.parent).collect();
+        let global_matrices = animation::global_matrices(&parents, &poses);
+
+        // skinned meshes are placed by their joints instead
+        for mesh in &mut self.meshes {
+            if let (Some(node), None) = (mesh.node, mesh.skin) {
+                mesh.matrix = to_iml_matrix(&global_matrices[node]);
+            }
+        }
+
+        self.joint_matrices = self
+            .skins
+            .iter()
+            .map(|skin| skin.joint_matrices(&global_matrices))
+            .collect();
+
+        self.apply_morph_weights(&weights);
+    }
+
+    // blends the morph targets on the cpu and writes the result over the vertex buffer,
+    // only the morphed bytes are uploaded again
+    fn apply_morph_weights(&mut self, node_weights: &[Vec<f32>]) {
+        let vertex_buffer = &mut self.vertex_buffer;
+
+        for mesh in &mut self.meshes {
+            let morph = match &mut mesh.morph {
+                Some(morph) => morph,
+                None => continue,
+            };
+
+            let weights = match node_weights.get(morph.node) {
+                Some(weights) if !weights.is_empty() => weights.clone(),
+                _ => morph.default_weights.clone(),
+            };
+            if weights == morph.applied_weights {
+                continue;
+            }
+
+            for primitive in &morph.primitives {
+                let (positions, normals, tangents) = primitive.blend(&weights);
+                for (offset, values) in [
+                    (primitive.position_offset, &positions),
+                    (primitive.normal_offset, &normals),
+                    (primitive.tangent_offset, &tangents),
+                ] {
+                    let range = write_floats(&mut vertex_buffer.data, offset, values);
+                    vertex_buffer.mark_dirty(range);
+                }
+            }
+
+            morph.applied_weights = weights;
         }
     }
 }
@@ -110,6 +295,14 @@ static PI: f32 = 3.14159265359;
 static X_SEGMENTS: f32 = 64.0;
 static Y_SEGMENTS: f32 = 64.0;
 
+// the generated shapes do not have a consistent winding order, so never cull them
+fn shape_material() -> Material {
+    Material {
+        double_sided: true,
+        ..Material::default()
+    }
+}
+
 fn generate_quad_model() -> ModelPointer {
     let positions: [f32; 12] = [
         -1.0, 1.0, 0.0, -1.0, -1.0, 0.0, 1.0, 1.0, 0.0, 1.0, -1.0, 0.0,
@@ -123,25 +316,26 @@ fn generate_quad_model() -> ModelPointer {
         start_index: 0,
         num_indices: 6,
         material_index: 0,
+        center: [0.0; 3],
     };
 
-    let position_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Position,
-        offset: 0,
-    };
+    let position_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Position,
+        0,
+    );
 
-    let normal_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Normal,
-        offset: positions.len(),
-    };
+    let normal_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Normal,
+        positions.len() * FLOAT_SIZE,
+    );
 
-    let tex_coord_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC2, Type::FLOAT, Usage::DATA),
-        slot: Slot::TexCoord,
-        offset: positions.len() + normals.len(),
-    };
+    let tex_coord_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC2, Type::FLOAT, Usage::DATA),
+        Slot::TexCoord,
+        (positions.len() + normals.len()) * FLOAT_SIZE,
+    );
 
     let mut buffer_data: Vec<u8> = Vec::new();
     buffer_data.extend_from_slice(to_byte_slice(&positions));
@@ -156,7 +350,10 @@ fn generate_quad_model() -> ModelPointer {
 
     let mesh: Mesh = Mesh {
         matrix: iml::Mat4::identity(),
+        node: None,
         sub_meshes: vec![sub_mesh],
+        skin: None,
+        morph: None,
     };
 
     ModelPointer::new(Model {
@@ -164,7 +361,8 @@ fn generate_quad_model() -> ModelPointer {
         vertex_buffer,
         meshes: vec![mesh],
         attributes: vec![position_attribute, normal_attribute, tex_coord_attribute],
-        materials: vec![Material::default()],
+        materials: vec![shape_material()],
+        ..Model::default()
     })
 }
 
@@ -202,22 +400,23 @@ fn generate_cube_model() -> ModelPointer {
         20, 21, 22, 20, 22, 23, // back
     ];
 
-    let position_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Position,
-        offset: 0,
-    };
+    let position_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Position,
+        0,
+    );
 
-    let normal_attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Normal,
-        offset: positions.len(),
-    };
+    let normal_attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Normal,
+        positions.len() * FLOAT_SIZE,
+    );
 
     let sub_mesh: SubMesh = SubMesh {
         start_index: 0,
         num_indices: indices.len(),
         material_index: 0,
+        center: [0.0; 3],
     };
 
     let mut buffer_data: Vec<u8> = Vec::new();
@@ -232,7 +431,10 @@ fn generate_cube_model() -> ModelPointer {
 
     let mesh: Mesh = Mesh {
         matrix: iml::Mat4::identity(),
+        node: None,
         sub_meshes: vec![sub_mesh],
+        skin: None,
+        morph: None,
     };
 
     ModelPointer::new(Model {
@@ -240,7 +442,8 @@ fn generate_cube_model() -> ModelPointer {
         vertex_buffer,
         meshes: vec![mesh],
         attributes: vec![position_attribute, normal_attribute],
-        materials: vec![Material::default()],
+        materials: vec![shape_material()],
+        ..Model::default()
     })
 }
 
@@ -293,19 +496,20 @@ fn generate_sphere_model() -> ModelPointer {
         start_index: 0,
         num_indices: indices.len(),
         material_index: 0,
+        center: [0.0; 3],
     };
 
-    let position_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Position,
-        offset: 0,
-    };
+    let position_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Position,
+        0,
+    );
 
-    let normal_attribute: Attribute = Attribute {
-        format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-        slot: Slot::Normal,
-        offset: positions.len(),
-    };
+    let normal_attribute: Attribute = Attribute::new(
+        Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
+        Slot::Normal,
+        positions.len() * FLOAT_SIZE,
+    );
 
     let mut buffer_data: Vec<u8> = Vec::new();
     buffer_data.extend_from_slice(to_byte_slice(&positions[..]));
@@ -318,7 +522,10 @@ fn generate_sphere_model() -> ModelPointer {
 
     let mesh: Mesh = Mesh {
         matrix: iml::Mat4::identity(),
+        node: None,
         sub_meshes: vec![sub_mesh],
+        skin: None,
+        morph: None,
     };
 
     ModelPointer::new(Model {
@@ -326,31 +533,617 @@ fn generate_sphere_model() -> ModelPointer {
         vertex_buffer,
         meshes: vec![mesh],
         attributes: vec![position_attribute, normal_attribute],
-        materials: vec![Material::default()],
+        materials: vec![shape_material()],
+        ..Model::default()
     })
 }
 
-fn process_gltf_node_tree<F: FnMut(&gltf::scene::Node, iml::Mat4)>(
+fn gltf_node_matrix(node: &gltf::scene::Node) -> iml::Mat4 {
+    let (translation, rotation, scale) = node.transform().decomposed();
+
+    let mut transform = iml::Transform::default();
+    transform.translation = iml::Point3::new(translation[0], translation[1], translation[2]);
+    transform.rotation = iml::Quat::new(rotation[3], rotation[0], rotation[1], rotation[2]);
+    transform.scale = iml::Vec3::from(scale);
+    transform.matrix()
+}
+
+fn process_gltf_node_tree<F>(
     node: &gltf::scene::Node,
-    matrix: iml::Mat4,
+    parent_matrix: iml::Mat4,
     callback: &mut F,
-) {
-    callback(&node, matrix);
+) -> Result<(), LoadError>
+where
+    F: FnMut(&gltf::scene::Node, iml::Mat4) -> Result<(), LoadError>,
+{
+    let matrix = parent_matrix * gltf_node_matrix(node);
+    callback(&node, matrix)?;
     for child in node.children() {
-        process_gltf_node_tree(&child, matrix, callback);
+        process_gltf_node_tree(&child, matrix, callback)?;
+    }
+    Ok(())
+}
+
+// one primitive of any importer, streams the source does not have are empty
+pub(crate) struct PrimitiveData {
+    pub(crate) positions: Vec<f32>,
+    pub(crate) normals: Vec<f32>,
+    pub(crate) tex_coords: Vec<f32>,
+    pub(crate) tex_coords1: Vec<f32>,
+    pub(crate) tangents: Vec<f32>,
+    pub(crate) joints: Vec<f32>,
+    pub(crate) weights: Vec<f32>,
+    pub(crate) indices: Vec<u32>,
+    pub(crate) targets: Vec<morph::Target>,
+    // compact copies of the quantized streams, the float streams are still filled in for
+    // bounds, tangent generation and morphing
+    pub(crate) packed: Vec<PackedAttribute>,
+}
+
+impl PrimitiveData {
+    // fills in the normals and tangents the source did not provide, the tangents follow
+    // the uv set the normal map is sampled with
+    pub(crate) fn generate_missing_attributes(
+        &mut self,
+        options: &LoadOptions,
+        normal_map_tex_coord: u32,
+    ) {
+        if self.normals.is_empty() {
+            self.normals = match options.normals {
+                NormalGeneration::Smooth => {
+                    geometry::smooth_normals(&self.positions, &self.indices)
+                }
+                NormalGeneration::Flat => {
+                    // flat shading needs a vertex per corner so faces do not share normals
+                    self.unweld();
+                    geometry::flat_normals(&self.positions)
+                }
+            };
+        }
+
+        if self.tangents.is_empty() {
+            let tex_coords = match normal_map_tex_coord {
+                1 => &self.tex_coords1,
+                _ => &self.tex_coords,
+            };
+            let corner_tangents = geometry::generate_tangents(
+                &self.positions,
+                &self.normals,
+                tex_coords,
+                &self.indices,
+            );
+
+            // corners of one vertex can get different tangents at uv seams and mirrors
+            let welded = geometry::weld(&corner_tangents, 4, &self.indices, self.vertex_count());
+            self.append_copies(&welded.copies);
+            self.tangents = welded.values;
+            self.indices = welded.indices;
+        }
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    // appends a copy of the given vertices to every stream but the tangents
+    fn append_copies(&mut self, vertices: &[u32]) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        append_stream(&mut self.positions, 3, vertices);
+        append_stream(&mut self.normals, 3, vertices);
+        append_stream(&mut self.tex_coords, 2, vertices);
+        append_stream(&mut self.tex_coords1, 2, vertices);
+        append_stream(&mut self.joints, 4, vertices);
+        append_stream(&mut self.weights, 4, vertices);
+        for target in &mut self.targets {
+            append_stream(&mut target.positions, 3, vertices);
+            append_stream(&mut target.normals, 3, vertices);
+            append_stream(&mut target.tangents, 3, vertices);
+        }
+        for packed in &mut self.packed {
+            let stride = packed.attribute.element_stride();
+            for vertex in vertices {
+                let start = *vertex as usize * stride;
+                packed.data.extend_from_within(start..start + stride);
+            }
+        }
+    }
+
+    fn unweld(&mut self) {
+        let indices = &self.indices;
+        self.positions = unweld_stream(&self.positions, 3, indices);
+        self.tex_coords = unweld_stream(&self.tex_coords, 2, indices);
+        self.tex_coords1 = unweld_stream(&self.tex_coords1, 2, indices);
+        self.tangents = unweld_stream(&self.tangents, 4, indices);
+        self.joints = unweld_stream(&self.joints, 4, indices);
+        self.weights = unweld_stream(&self.weights, 4, indices);
+        for target in &mut self.targets {
+            target.positions = unweld_stream(&target.positions, 3, indices);
+            target.normals = unweld_stream(&target.normals, 3, indices);
+            target.tangents = unweld_stream(&target.tangents, 3, indices);
+        }
+        for packed in &mut self.packed {
+            let stride = packed.attribute.element_stride();
+            packed.data = indices
+                .iter()
+                .flat_map(|index| {
+                    let start = *index as usize * stride;
+                    packed.data[start..start + stride].iter().copied()
+                })
+                .collect();
+        }
+        self.indices = geometry::sequential_indices(self.indices.len());
+    }
+}
+
+fn append_stream(values: &mut Vec<f32>, width: usize, vertices: &[u32]) {
+    let copies = unweld_stream(values, width, vertices);
+    values.extend(copies);
+}
+
+fn unweld_stream(values: &[f32], width: usize, indices: &[u32]) -> Vec<f32> {
+    if values.is_empty() {
+        Vec::new()
+    } else {
+        geometry::unweld(values, width, indices)
     }
 }
 
-fn load_gltf_texture(image_data: &gltf::image::Data) -> texture::TexturePointer {
-    let format = Format::new(Dimension::VEC3, Type::UINT8, Usage::RGB);
-    let texture_desc = texture::TextureDesc {
-        wrap_s: texture::WrapMode::REPEAT,
-        wrap_t: texture::WrapMode::REPEAT,
-        min_filter: texture::Filter::LINEAR,
-        mag_filter: texture::Filter::LINEAR,
+// a stream in the type it was stored with, elements padded to four bytes
+#[derive(Clone)]
+pub(crate) struct PackedAttribute {
+    pub(crate) attribute: Attribute,
+    pub(crate) data: Vec<u8>,
+}
+
+impl PackedAttribute {
+    fn floats(&self) -> Vec<f32> {
+        let count = self.data.len() / self.attribute.element_stride();
+        stream::read_floats(&self.data, &self.attribute, count)
+    }
+}
+
+// planar vertex data of every primitive in a model, they all share one vertex buffer
+#[derive(Default)]
+pub(crate) struct VertexStreams {
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    tex_coords: Vec<f32>,
+    tex_coords1: Vec<f32>,
+    tangents: Vec<f32>,
+    joints: Vec<f32>,
+    weights: Vec<f32>,
+    indices: Vec<u32>,
+    // compact sections indexed by slot, a section stays compact while every primitive
+    // pushed so far stored it with the same format
+    packed: Vec<Option<PackedAttribute>>,
+}
+
+impl VertexStreams {
+    pub(crate) fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    // the indices are rebased onto the shared buffer
+    pub(crate) fn push(&mut self, primitive: &PrimitiveData, material_index: usize) -> SubMesh {
+        let base_vertex = self.vertex_count() as u32;
+        self.push_packed(primitive, base_vertex == 0);
+
+        self.positions.extend_from_slice(&primitive.positions[..]);
+        self.normals.extend_from_slice(&primitive.normals[..]);
+        self.tex_coords.extend_from_slice(&primitive.tex_coords[..]);
+        self.tex_coords1
+            .extend_from_slice(&primitive.tex_coords1[..]);
+        self.tangents.extend_from_slice(&primitive.tangents[..]);
+        self.joints.extend_from_slice(&primitive.joints[..]);
+        self.weights.extend_from_slice(&primitive.weights[..]);
+
+        let sub_mesh = SubMesh::new(
+            self.indices.len(),
+            primitive.indices.len(),
+            material_index,
+            geometry::bounds_center(&primitive.positions),
+        );
+        self.indices
+            .extend(primitive.indices.iter().map(|index| index + base_vertex));
+        sub_mesh
+    }
+
+    fn push_packed(&mut self, primitive: &PrimitiveData, first: bool) {
+        if first {
+            self.packed = vec![None; Slot::NUM as usize];
+            for packed in &primitive.packed {
+                self.packed[packed.attribute.slot as usize] = Some(packed.clone());
+            }
+            return;
+        }
+
+        for (slot, section) in self.packed.iter_mut().enumerate() {
+            let incoming = primitive
+                .packed
+                .iter()
+                .find(|packed| packed.attribute.slot as usize == slot);
+            match (section.as_mut(), incoming) {
+                (Some(section), Some(incoming))
+                    if section.attribute.format == incoming.attribute.format
+                        && section.attribute.interpretation
+                            == incoming.attribute.interpretation =>
+                {
+                    section.data.extend_from_slice(&incoming.data);
+                }
+                // mixed formats fall back to floats
+                _ => *section = None,
+            }
+        }
+    }
+
+    fn packed(&self, slot: Slot) -> Option<&PackedAttribute> {
+        self.packed.get(slot as usize).and_then(Option::as_ref)
+    }
+
+    // the float data of every section in buffer order, joints and weights are only
+    // written for skinned models
+    fn sections(&self) -> Vec<(Slot, Dimension, &[f32])> {
+        let mut sections = vec![
+            (Slot::Position, Dimension::VEC3, &self.positions[..]),
+            (Slot::Normal, Dimension::VEC3, &self.normals[..]),
+            (Slot::TexCoord, Dimension::VEC2, &self.tex_coords[..]),
+            (Slot::Tangent, Dimension::VEC4, &self.tangents[..]),
+            (Slot::TexCoord1, Dimension::VEC2, &self.tex_coords1[..]),
+        ];
+        if !self.joints.is_empty() {
+            sections.push((Slot::Joints, Dimension::VEC4, &self.joints[..]));
+            sections.push((Slot::Weights, Dimension::VEC4, &self.weights[..]));
+        }
+        sections
+    }
+
+    // compact sections keep their type, joint indices stay integers so the skinning
+    // shader reads them as a uvec4 and everything else is float
+    fn attributes(&self) -> Attributes {
+        let vertex_count = self.vertex_count();
+        let mut offset = 0;
+        self.sections()
+            .into_iter()
+            .map(|(slot, dimension, _)| {
+                let attribute = match self.packed(slot) {
+                    Some(packed) => Attribute {
+                        offset,
+                        ..packed.attribute
+                    },
+                    None if slot == Slot::Joints => Attribute::new(
+                        Format::new(dimension, Type::UINT16, Usage::DATA),
+                        slot,
+                        offset,
+                    )
+                    .integer(),
+                    None => Attribute::new(
+                        Format::new(dimension, Type::FLOAT, Usage::DATA),
+                        slot,
+                        offset,
+                    ),
+                };
+                offset = stream::align(offset + vertex_count * attribute.element_stride(), 4);
+                attribute
+            })
+            .collect()
+    }
+
+    // byte offset of a section in the vertex buffer, morphed sections are always float
+    fn section_start(&self, slot: Slot) -> usize {
+        self.attributes()
+            .iter()
+            .find(|attribute| attribute.slot == slot)
+            .map_or(0, |attribute| attribute.offset)
+    }
+
+    // fills in the buffers and attributes of the model
+    pub(crate) fn write(self, model: &mut Model) {
+        let attributes = self.attributes();
+
+        let mut vertex_buffer = buffer::Buffer::default();
+        let vertex_buffer_data = &mut vertex_buffer.data;
+        for ((slot, _, values), attribute) in self.sections().into_iter().zip(&attributes) {
+            vertex_buffer_data.resize(attribute.offset, 0);
+            match self.packed(slot) {
+                Some(packed) => vertex_buffer_data.extend_from_slice(&packed.data),
+                None if slot == Slot::Joints => {
+                    let joints: Vec<u16> = values.iter().map(|joint| *joint as u16).collect();
+                    vertex_buffer_data.extend_from_slice(to_byte_slice(&joints[..]));
+                }
+                None => vertex_buffer_data.extend_from_slice(to_byte_slice(values)),
+            }
+        }
+
+        let mut index_buffer = buffer::Buffer::default();
+        index_buffer
+            .data
+            .extend_from_slice(to_byte_slice(&self.indices[..]));
+
+        model.attributes = attributes;
+        model.vertex_buffer = vertex_buffer;
+        model.index_buffer = index_buffer;
+    }
+}
+
+fn read_gltf_primitive(
+    gltf_mesh: &gltf::Mesh,
+    prim: &gltf::Primitive,
+    buffers: &Vec<gltf::buffer::Data>,
+    skinned: bool,
+    options: &LoadOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Option<PrimitiveData>, LoadError> {
+    let topology = match prim.mode() {
+        gltf::mesh::Mode::Triangles => geometry::Topology::Triangles,
+        gltf::mesh::Mode::TriangleStrip => geometry::Topology::TriangleStrip,
+        gltf::mesh::Mode::TriangleFan => geometry::Topology::TriangleFan,
+        mode => {
+            warnings.push(format!(
+                "mesh {} primitive {}: skipping unsupported mode {:?}",
+                gltf_mesh.index(),
+                prim.index(),
+                mode
+            ));
+            return Ok(None);
+        }
+    };
+
+    let reader = prim.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    // quantized streams keep a compact copy, float ones go through the gltf reader
+    let mut packed: Vec<PackedAttribute> = Vec::new();
+    let mut read_packed = |semantic: gltf::Semantic, slot: Slot| {
+        let attribute = read_gltf_packed(&prim.get(&semantic)?, slot, buffers)?;
+        let values = attribute.floats();
+        packed.push(attribute);
+        Some(values)
     };
-    texture::Texture::new(
-        &image_data.pixels,
+
+    let positions = match read_packed(gltf::Semantic::Positions, Slot::Position).or_else(|| {
+        reader
+            .read_positions()
+            .map(|iterator| iterator.flatten().collect())
+    }) {
+        Some(positions) => positions,
+        None => {
+            return Err(LoadError::MissingPositions {
+                mesh: gltf_mesh.index(),
+                primitive: prim.index(),
+            })
+        }
+    };
+    let vertex_count = positions.len() / 3;
+
+    let tex_coords = read_packed(gltf::Semantic::TexCoords(0), Slot::TexCoord)
+        .or_else(|| {
+            reader
+                .read_tex_coords(0)
+                .map(|iterator| iterator.into_f32().flatten().collect())
+        })
+        .unwrap_or_else(|| vec![0.0; vertex_count * 2]);
+
+    // lightmaps and atlases use a second set, fall back to the first one
+    let tex_coords1 = read_packed(gltf::Semantic::TexCoords(1), Slot::TexCoord1)
+        .or_else(|| {
+            reader
+                .read_tex_coords(1)
+                .map(|iterator| iterator.into_f32().flatten().collect())
+        })
+        .unwrap_or_else(|| tex_coords.clone());
+
+    let (joints, weights) = if skinned {
+        let joints = match reader.read_joints(0) {
+            Some(iterator) => iterator
+                .into_u16()
+                .flat_map(|joint| joint.map(|index| index as f32))
+                .collect::<Vec<_>>(),
+            None => vec![0.0; vertex_count * 4],
+        };
+
+        let weights = read_packed(gltf::Semantic::Weights(0), Slot::Weights)
+            .or_else(|| {
+                reader
+                    .read_weights(0)
+                    .map(|iterator| iterator.into_f32().flatten().collect())
+            })
+            .unwrap_or_else(|| vec![0.0; vertex_count * 4]);
+        (joints, weights)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let tangents = read_packed(gltf::Semantic::Tangents, Slot::Tangent)
+        .or_else(|| {
+            reader
+                .read_tangents()
+                .map(|iterator| iterator.flatten().collect())
+        })
+        .unwrap_or_default();
+
+    let normals = read_packed(gltf::Semantic::Normals, Slot::Normal)
+        .or_else(|| {
+            reader
+                .read_normals()
+                .map(|iterator| iterator.flatten().collect())
+        })
+        .unwrap_or_default();
+
+    let targets: Vec<morph::Target> = prim
+        .morph_targets()
+        .map(|target| morph::Target {
+            positions: read_gltf_vec3(target.positions(), buffers),
+            normals: read_gltf_vec3(target.normals(), buffers),
+            tangents: read_gltf_vec3(target.tangents(), buffers),
+        })
+        .collect();
+
+    // morphing rewrites these sections with floats every frame
+    if !targets.is_empty() {
+        packed.retain(|packed| {
+            !matches!(
+                packed.attribute.slot,
+                Slot::Position | Slot::Normal | Slot::Tangent
+            )
+        });
+    }
+
+    let indices = match reader.read_indices() {
+        Some(iterator) => iterator.into_u32().collect::<Vec<_>>(),
+        None => geometry::sequential_indices(vertex_count),
+    };
+    let indices = geometry::triangulate(topology, &indices);
+
+    let mut primitive = PrimitiveData {
+        positions,
+        normals,
+        tex_coords,
+        tex_coords1,
+        tangents,
+        joints,
+        weights,
+        indices,
+        targets,
+        packed,
+    };
+    let normal_map_tex_coord = prim
+        .material()
+        .normal_texture()
+        .map_or(0, |normal_texture| normal_texture.tex_coord());
+    primitive.generate_missing_attributes(options, normal_map_tex_coord);
+    Ok(Some(primitive))
+}
+
+// KHR_mesh_quantization accessors, float and sparse accessors are left to the gltf reader
+fn read_gltf_packed(
+    accessor: &gltf::Accessor,
+    slot: Slot,
+    buffers: &Vec<gltf::buffer::Data>,
+) -> Option<PackedAttribute> {
+    use gltf::accessor::{DataType, Dimensions};
+
+    let _type = match accessor.data_type() {
+        DataType::F32 => return None,
+        DataType::I8 => Type::INT8,
+        DataType::U8 => Type::UINT8,
+        DataType::I16 => Type::INT16,
+        DataType::U16 => Type::UINT16,
+        DataType::U32 => Type::UINT32,
+    };
+    let dimension = match accessor.dimensions() {
+        Dimensions::Scalar => Dimension::SCALAR,
+        Dimensions::Vec2 => Dimension::VEC2,
+        Dimensions::Vec3 => Dimension::VEC3,
+        Dimensions::Vec4 => Dimension::VEC4,
+        _ => return None,
+    };
+    if accessor.sparse().is_some() {
+        return None;
+    }
+
+    let format = Format::new(dimension, _type, Usage::DATA);
+    let element_size = format.stride();
+    // the extension pads every element to four bytes, keep that for the GPU
+    let stride = stream::align(element_size, 4);
+
+    let view = accessor.view()?;
+    let buffer = &buffers[view.buffer().index()];
+    let source_stride = view.stride().unwrap_or(element_size);
+    let start = view.offset() + accessor.offset();
+
+    let mut data = vec![0u8; accessor.count() * stride];
+    for (index, element) in data.chunks_exact_mut(stride).enumerate() {
+        let source = start + index * source_stride;
+        element[..element_size].copy_from_slice(buffer.get(source..source + element_size)?);
+    }
+
+    let mut attribute = Attribute::new(format, slot, 0);
+    attribute.stride = stride;
+    if accessor.normalized() {
+        attribute = attribute.normalized();
+    }
+    Some(PackedAttribute { attribute, data })
+}
+
+// morph target displacements, which may be quantized as well
+fn read_gltf_vec3(accessor: Option<gltf::Accessor>, buffers: &Vec<gltf::buffer::Data>) -> Vec<f32> {
+    let accessor = match accessor {
+        Some(accessor) => accessor,
+        None => return Vec::new(),
+    };
+
+    if let Some(packed) = read_gltf_packed(&accessor, Slot::Position, buffers) {
+        return packed.floats();
+    }
+    gltf::accessor::Iter::<[f32; 3]>::new(accessor, |buffer| {
+        buffers.get(buffer.index()).map(|data| &data[..])
+    })
+    .map_or(Vec::new(), |iterator| iterator.flatten().collect())
+}
+
+fn gltf_wrap_mode(wrap_mode: gltf::texture::WrappingMode) -> texture::WrapMode {
+    match wrap_mode {
+        gltf::texture::WrappingMode::ClampToEdge => texture::WrapMode::CLAMP,
+        gltf::texture::WrappingMode::MirroredRepeat => texture::WrapMode::MIRROR_REPEAT,
+        gltf::texture::WrappingMode::Repeat => texture::WrapMode::REPEAT,
+    }
+}
+
+fn gltf_texture_desc(sampler: &gltf::texture::Sampler, anisotropy: f32) -> texture::TextureDesc {
+    // the spec leaves missing filters up to the renderer, use trilinear filtering
+    let min_filter = match sampler.min_filter() {
+        Some(gltf::texture::MinFilter::Nearest) => texture::Filter::NEAREST,
+        Some(gltf::texture::MinFilter::Linear) => texture::Filter::LINEAR,
+        Some(gltf::texture::MinFilter::NearestMipmapNearest) => {
+            texture::Filter::NEAREST_MIP_NEAREST
+        }
+        Some(gltf::texture::MinFilter::LinearMipmapNearest) => texture::Filter::LINEAR_MIP_NEAREST,
+        Some(gltf::texture::MinFilter::NearestMipmapLinear) => texture::Filter::NEAREST_MIP_LINEAR,
+        Some(gltf::texture::MinFilter::LinearMipmapLinear) | None => {
+            texture::Filter::LINEAR_MIP_LINEAR
+        }
+    };
+
+    let mag_filter = match sampler.mag_filter() {
+        Some(gltf::texture::MagFilter::Nearest) => texture::Filter::NEAREST,
+        Some(gltf::texture::MagFilter::Linear) | None => texture::Filter::LINEAR,
+    };
+
+    texture::TextureDesc {
+        wrap_s: gltf_wrap_mode(sampler.wrap_s()),
+        wrap_t: gltf_wrap_mode(sampler.wrap_t()),
+        min_filter,
+        mag_filter,
+        anisotropy,
+        ..texture::TextureDesc::default()
+    }
+}
+
+fn gltf_pixel_layout(format: gltf::image::Format) -> texture::PixelLayout {
+    match format {
+        gltf::image::Format::R8 => texture::PixelLayout::R8,
+        gltf::image::Format::R8G8 => texture::PixelLayout::Rg8,
+        gltf::image::Format::R8G8B8 => texture::PixelLayout::Rgb8,
+        gltf::image::Format::R8G8B8A8 => texture::PixelLayout::Rgba8,
+        gltf::image::Format::B8G8R8 => texture::PixelLayout::Bgr8,
+        gltf::image::Format::B8G8R8A8 => texture::PixelLayout::Bgra8,
+        gltf::image::Format::R16 => texture::PixelLayout::R16,
+        gltf::image::Format::R16G16 => texture::PixelLayout::Rg16,
+        gltf::image::Format::R16G16B16 => texture::PixelLayout::Rgb16,
+        gltf::image::Format::R16G16B16A16 => texture::PixelLayout::Rgba16,
+    }
+}
+
+fn load_gltf_texture(
+    image_data: &gltf::image::Data,
+    texture_desc: texture::TextureDesc,
+) -> texture::TexturePointer {
+    let (pixels, format) =
+        texture::convert_pixels(&image_data.pixels, gltf_pixel_layout(image_data.format));
+    texture::Texture::deferred(
+        pixels,
         texture_desc,
         image_data.width,
         image_data.height,
@@ -359,44 +1152,149 @@ fn load_gltf_texture(image_data: &gltf::image::Data) -> texture::TexturePointer
     )
 }
 
-fn load_gltf_material(
-    gltf_material: &gltf::material::Material,
-    image_data: &Vec<gltf::image::Data>,
-) -> Material {
-    let albedo_map: Option<texture::TexturePointer> = gltf_material
-        .pbr_metallic_roughness()
-        .base_color_texture()
-        .map_or(None, |image_info| {
-            Some(load_gltf_texture(
-                &image_data[image_info.texture().source().index()],
-            ))
-        });
+// where the images of a glTF document come from, used to key the texture cache
+enum ImageOrigin<'p> {
+    File(&'p Path),
+    Memory(usize),
+}
 
-    let normal_map: Option<texture::TexturePointer> =
-        gltf_material.normal_texture().map_or(None, |image_info| {
-            Some(load_gltf_texture(
-                &image_data[image_info.texture().source().index()],
-            ))
-        });
+struct GltfTextures<'a> {
+    document: &'a gltf::Document,
+    images: &'a Vec<gltf::image::Data>,
+    origin: ImageOrigin<'a>,
+    cache: &'a texture::TextureCache,
+    anisotropy: f32,
+}
 
-    let specular_map: Option<texture::TexturePointer> = gltf_material
-        .pbr_metallic_roughness()
-        .metallic_roughness_texture()
-        .map_or(None, |image_info| {
-            Some(load_gltf_texture(
-                &image_data[image_info.texture().source().index()],
-            ))
-        });
+impl GltfTextures<'_> {
+    fn source(&self, image: &gltf::image::Image) -> texture::TextureSource {
+        match (image.source(), &self.origin) {
+            // image files can be shared between models
+            (gltf::image::Source::Uri { uri, .. }, ImageOrigin::File(path))
+                if !uri.starts_with("data:") =>
+            {
+                let directory = path.parent().unwrap_or_else(|| Path::new(""));
+                texture::TextureSource::File(directory.join(uri))
+            }
+            (_, ImageOrigin::File(path)) => {
+                texture::TextureSource::Embedded(path.to_path_buf(), image.index())
+            }
+            (_, ImageOrigin::Memory(id)) => texture::TextureSource::Memory(*id, image.index()),
+        }
+    }
 
-    let mut emissive_map: Option<texture::TexturePointer> = None;
-    if let Some(image_info) = gltf_material.emissive_texture() {
-        emissive_map = Some(load_gltf_texture(
-            &image_data[image_info.texture().source().index()],
-        ));
+    // info is the raw texture info json, it carries KHR_texture_transform
+    fn load(
+        &mut self,
+        gltf_texture: gltf::texture::Texture,
+        tex_coord: u32,
+        color_space: texture::ColorSpace,
+        info: Option<&serde_json::Value>,
+    ) -> MaterialTexture {
+        let image = gltf_texture.source();
+        let texture_desc = texture::TextureDesc {
+            color_space,
+            ..gltf_texture_desc(&gltf_texture.sampler(), self.anisotropy)
+        };
+
+        let image_data = &self.images[image.index()];
+        let texture = self
+            .cache
+            .fetch(self.source(&image), texture_desc, |texture_desc| {
+                load_gltf_texture(image_data, texture_desc)
+            });
+
+        let mut material_texture = MaterialTexture {
+            texture,
+            tex_coord,
+            transform: TextureTransform::default(),
+        };
+
+        if let Some(transform) = info
+            .and_then(|info| info.get("extensions"))
+            .and_then(|extensions| extensions.get("KHR_texture_transform"))
+        {
+            let offset = json_vec2(transform, "offset", [0.0, 0.0]);
+            let scale = json_vec2(transform, "scale", [1.0, 1.0]);
+            material_texture.transform = TextureTransform {
+                offset,
+                rotation: json_f32(transform, "rotation", 0.0),
+                scale,
+            };
+
+            // the extension may also point the texture at a different uv set
+            if let Some(tex_coord) = transform.get("texCoord").and_then(|value| value.as_u64()) {
+                material_texture.tex_coord = tex_coord as u32;
+            }
+        }
+        material_texture
+    }
+
+    // texture info objects read from the raw extension json
+    fn load_json(
+        &mut self,
+        info: Option<&serde_json::Value>,
+        color_space: texture::ColorSpace,
+    ) -> Option<MaterialTexture> {
+        let info = info?;
+        let index = info.get("index")?.as_u64()? as usize;
+        let gltf_texture = self.document.textures().nth(index)?;
+        let tex_coord = info
+            .get("texCoord")
+            .and_then(|tex_coord| tex_coord.as_u64())
+            .unwrap_or(0);
+        Some(self.load(gltf_texture, tex_coord as u32, color_space, Some(info)))
     }
+}
 
+// gltf drops the material extensions it does not support, so keep the raw json around
+fn read_gltf_json(bytes: &[u8]) -> serde_json::Value {
+    let json = if bytes.starts_with(b"glTF") {
+        match gltf::Glb::from_slice(bytes) {
+            Ok(glb) => serde_json::from_slice(&glb.json),
+            Err(_) => return serde_json::Value::Null,
+        }
+    } else {
+        serde_json::from_slice(bytes)
+    };
+    json.unwrap_or(serde_json::Value::Null)
+}
+
+fn json_f32(value: &serde_json::Value, key: &str, default: f32) -> f32 {
+    value
+        .get(key)
+        .and_then(|value| value.as_f64())
+        .map_or(default, |value| value as f32)
+}
+
+fn json_vec2(value: &serde_json::Value, key: &str, default: [f32; 2]) -> [f32; 2] {
+    let mut vector = default;
+    if let Some(array) = value.get(key).and_then(|value| value.as_array()) {
+        for (index, component) in array.iter().take(2).enumerate() {
+            vector[index] = component.as_f64().unwrap_or(default[index] as f64) as f32;
+        }
+    }
+    vector
+}
+
+fn json_vec3(value: &serde_json::Value, key: &str, default: [f32; 3]) -> iml::Vec3 {
+    let mut vector = default;
+    if let Some(array) = value.get(key).and_then(|value| value.as_array()) {
+        for (index, component) in array.iter().take(3).enumerate() {
+            vector[index] = component.as_f64().unwrap_or(default[index] as f64) as f32;
+        }
+    }
+    iml::Vec3::new(vector[0], vector[1], vector[2])
+}
+
+fn load_gltf_material(
+    gltf_material: &gltf::material::Material,
+    textures: &mut GltfTextures,
+    json: &serde_json::Value,
+) -> Material {
     let pbr_info = gltf_material.pbr_metallic_roughness();
     let base_color = pbr_info.base_color_factor();
+    let emissive = gltf_material.emissive_factor();
 
     let mut material = Material::new(
         iml::Vec3::new(base_color[0], base_color[1], base_color[2]),
@@ -405,119 +1303,562 @@ fn load_gltf_material(
         1.0,
     );
 
-    material.albedo_map = albedo_map;
-    material.normal_map = normal_map;
-    material.specular_map = specular_map;
-    material.emissive_map = emissive_map;
+    material.alpha = base_color[3];
+    material.emissive = iml::Vec3::new(emissive[0], emissive[1], emissive[2]);
+    material.double_sided = gltf_material.double_sided();
+    material.alpha_mode = match gltf_material.alpha_mode() {
+        gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+        gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+        gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+    };
+    material.alpha_cutoff = gltf_material.alpha_cutoff().unwrap_or(0.5);
+
+    // the raw texture infos, only needed for KHR_texture_transform
+    let material_json = gltf_material
+        .index()
+        .and_then(|index| json.get("materials")?.get(index));
+    let texture_json = |path: &[&str]| {
+        path.iter()
+            .try_fold(material_json?, |value, key| value.get(*key))
+    };
+
+    material.albedo_map = pbr_info.base_color_texture().map(|info| {
+        textures.load(
+            info.texture(),
+            info.tex_coord(),
+            texture::ColorSpace::Srgb,
+            texture_json(&["pbrMetallicRoughness", "baseColorTexture"]),
+        )
+    });
+
+    material.metallic_roughness_map = pbr_info.metallic_roughness_texture().map(|info| {
+        textures.load(
+            info.texture(),
+            info.tex_coord(),
+            texture::ColorSpace::Linear,
+            texture_json(&["pbrMetallicRoughness", "metallicRoughnessTexture"]),
+        )
+    });
+
+    if let Some(normal_texture) = gltf_material.normal_texture() {
+        material.normal_scale = normal_texture.scale();
+        material.normal_map = Some(textures.load(
+            normal_texture.texture(),
+            normal_texture.tex_coord(),
+            texture::ColorSpace::Linear,
+            texture_json(&["normalTexture"]),
+        ));
+    }
+
+    if let Some(occlusion_texture) = gltf_material.occlusion_texture() {
+        material.occlusion_strength = occlusion_texture.strength();
+        material.occlusion_map = Some(textures.load(
+            occlusion_texture.texture(),
+            occlusion_texture.tex_coord(),
+            texture::ColorSpace::Linear,
+            texture_json(&["occlusionTexture"]),
+        ));
+    }
+
+    material.emissive_map = gltf_material.emissive_texture().map(|info| {
+        textures.load(
+            info.texture(),
+            info.tex_coord(),
+            texture::ColorSpace::Srgb,
+            texture_json(&["emissiveTexture"]),
+        )
+    });
 
+    load_gltf_material_extensions(&mut material, gltf_material, textures, json);
     material
 }
 
-pub fn load_gltf_model(path: String) -> Result<ModelPointer, String> {
-    let (gltf, buffers, images) = gltf::import(path).expect("failed to load scene");
+fn load_gltf_material_extensions(
+    material: &mut Material,
+    gltf_material: &gltf::material::Material,
+    textures: &mut GltfTextures,
+    json: &serde_json::Value,
+) {
+    let extensions = match gltf_material
+        .index()
+        .and_then(|index| json.get("materials")?.get(index)?.get("extensions"))
+    {
+        Some(extensions) => extensions,
+        None => return,
+    };
 
-    if let Some(scene) = gltf.default_scene().or_else(|| gltf.scenes().next()) {
-        let mut model: Model = Model::default();
+    if let Some(clearcoat) = extensions.get("KHR_materials_clearcoat") {
+        material.clearcoat.factor = json_f32(clearcoat, "clearcoatFactor", 0.0);
+        material.clearcoat.roughness = json_f32(clearcoat, "clearcoatRoughnessFactor", 0.0);
+        material.clearcoat.map = textures.load_json(
+            clearcoat.get("clearcoatTexture"),
+            texture::ColorSpace::Linear,
+        );
+        material.clearcoat.roughness_map = textures.load_json(
+            clearcoat.get("clearcoatRoughnessTexture"),
+            texture::ColorSpace::Linear,
+        );
+        if let Some(normal_texture) = clearcoat.get("clearcoatNormalTexture") {
+            material.clearcoat.normal_scale = json_f32(normal_texture, "scale", 1.0);
+            material.clearcoat.normal_map =
+                textures.load_json(Some(normal_texture), texture::ColorSpace::Linear);
+        }
+    }
 
-        let mut positions: Vec<f32> = Vec::new();
-        let mut normals: Vec<f32> = Vec::new();
-        let mut tex_coords: Vec<f32> = Vec::new();
-        let mut indices: Vec<u32> = Vec::new();
+    if let Some(sheen) = extensions.get("KHR_materials_sheen") {
+        material.sheen.color = json_vec3(sheen, "sheenColorFactor", [0.0, 0.0, 0.0]);
+        material.sheen.roughness = json_f32(sheen, "sheenRoughnessFactor", 0.0);
+        material.sheen.color_map =
+            textures.load_json(sheen.get("sheenColorTexture"), texture::ColorSpace::Srgb);
+        material.sheen.roughness_map = textures.load_json(
+            sheen.get("sheenRoughnessTexture"),
+            texture::ColorSpace::Linear,
+        );
+    }
 
-        let mut process_node = |node: &gltf::scene::Node, transform: iml::Mat4| {
-            if let Some(gltf_mesh) = node.mesh() {
-                let mut mesh = Mesh::default();
-                for prim in gltf_mesh.primitives() {
-                    let reader = prim.reader(|buffer| Some(&buffers[buffer.index()]));
+    if let Some(transmission) = extensions.get("KHR_materials_transmission") {
+        material.transmission.factor = json_f32(transmission, "transmissionFactor", 0.0);
+        material.transmission.map = textures.load_json(
+            transmission.get("transmissionTexture"),
+            texture::ColorSpace::Linear,
+        );
+    }
 
-                    let material = load_gltf_material(&prim.material(), &images);
+    if let Some(specular) = extensions.get("KHR_materials_specular") {
+        material.specular.factor = json_f32(specular, "specularFactor", 1.0);
+        material.specular.color = json_vec3(specular, "specularColorFactor", [1.0, 1.0, 1.0]);
+        material.specular.map =
+            textures.load_json(specular.get("specularTexture"), texture::ColorSpace::Linear);
+        material.specular.color_map = textures.load_json(
+            specular.get("specularColorTexture"),
+            texture::ColorSpace::Srgb,
+        );
+    }
 
-                    let gltf_positions = if let Some(iterator) = reader.read_positions() {
-                        iterator.flatten().collect::<Vec<_>>()
-                    } else {
-                        return;
-                    };
+    if let Some(ior) = extensions.get("KHR_materials_ior") {
+        material.ior = json_f32(ior, "ior", 1.5);
+    }
 
-                    let gltf_normals = if let Some(iterator) = reader.read_normals() {
-                        iterator.flatten().collect::<Vec<_>>()
-                    } else {
-                        return;
-                    };
+    if let Some(emissive_strength) = extensions.get("KHR_materials_emissive_strength") {
+        material.emissive_strength = json_f32(emissive_strength, "emissiveStrength", 1.0);
+    }
+}
 
-                    let gltf_uv = if let Some(iterator) = reader.read_tex_coords(0) {
-                        iterator.into_f32().flatten().collect::<Vec<_>>()
-                    } else {
-                        panic!("Failed to get tex coords from model");
-                        //vec![0.0; gltf_positions.len()]
-                    };
+fn load_gltf_nodes(document: &gltf::Document) -> Vec<Node> {
+    let mut nodes: Vec<Node> = document
+        .nodes()
+        .map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            Node {
+                parent: None,
+                rest_pose: animation::Pose {
+                    translation,
+                    rotation,
+                    scale,
+                },
+            }
+        })
+        .collect();
 
-                    let gltf_indices = if let Some(iterator) = reader.read_indices() {
-                        iterator.into_u32().collect::<Vec<_>>()
-                    } else {
-                        panic!("Failed to get indices from model");
-                    };
+    for node in document.nodes() {
+        for child in node.children() {
+            nodes[child.index()].parent = Some(node.index());
+        }
+    }
 
-                    positions.extend_from_slice(&gltf_positions[..]);
-                    normals.extend_from_slice(&gltf_normals[..]);
-                    tex_coords.extend_from_slice(&gltf_uv[..]);
+    nodes
+}
 
-                    let sub_mesh =
-                        SubMesh::new(indices.len(), gltf_indices.len(), model.materials.len());
-                    indices.extend_from_slice(&gltf_indices[..]);
+fn load_gltf_skin(skin: &gltf::Skin, buffers: &Vec<gltf::buffer::Data>) -> animation::Skin {
+    let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+    let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+        Some(iterator) => iterator.collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
 
-                    mesh.sub_meshes.push(sub_mesh);
-                    model.materials.push(material);
-                }
-                model.meshes.push(mesh);
-            } else {
-                println!("node does have mesh");
-            }
+    animation::Skin {
+        joints: skin.joints().map(|joint| joint.index()).collect(),
+        inverse_bind_matrices,
+    }
+}
+
+fn load_gltf_animation(
+    gltf_animation: &gltf::Animation,
+    buffers: &Vec<gltf::buffer::Data>,
+) -> animation::Clip {
+    let mut clip = animation::Clip {
+        name: gltf_animation.name().unwrap_or_default().to_string(),
+        duration: 0.0,
+        channels: Vec::new(),
+    };
+
+    for channel in gltf_animation.channels() {
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let inputs = match reader.read_inputs() {
+            Some(iterator) => iterator.collect::<Vec<f32>>(),
+            None => continue,
         };
 
-        let matrix = iml::Mat4::identity();
-        for node in scene.nodes() {
-            process_gltf_node_tree(&node, matrix, &mut process_node);
-        }
+        let (property, width, outputs) = match reader.read_outputs() {
+            Some(gltf::animation::util::ReadOutputs::Translations(iterator)) => (
+                animation::Property::Translation,
+                3,
+                iterator.flatten().collect::<Vec<f32>>(),
+            ),
+            Some(gltf::animation::util::ReadOutputs::Rotations(iterator)) => (
+                animation::Property::Rotation,
+                4,
+                iterator.into_f32().flatten().collect::<Vec<f32>>(),
+            ),
+            Some(gltf::animation::util::ReadOutputs::Scales(iterator)) => (
+                animation::Property::Scale,
+                3,
+                iterator.flatten().collect::<Vec<f32>>(),
+            ),
+            Some(gltf::animation::util::ReadOutputs::MorphTargetWeights(iterator)) => {
+                let outputs = iterator.into_f32().collect::<Vec<f32>>();
+                (animation::Property::Weights, 0, outputs)
+            }
+            _ => continue,
+        };
 
-        let position_attribute: Attribute = Attribute {
-            format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-            slot: Slot::Position,
-            offset: 0,
+        let interpolation = match channel.sampler().interpolation() {
+            gltf::animation::Interpolation::Linear => animation::Interpolation::Linear,
+            gltf::animation::Interpolation::Step => animation::Interpolation::Step,
+            gltf::animation::Interpolation::CubicSpline => animation::Interpolation::CubicSpline,
         };
 
-        let normal_attribute: Attribute = Attribute {
-            format: Format::new(Dimension::VEC3, Type::FLOAT, Usage::DATA),
-            slot: Slot::Normal,
-            offset: positions.len(),
+        // weights are flattened, one value per target for every key
+        let width = match property {
+            animation::Property::Weights => {
+                let values_per_key = match interpolation {
+                    animation::Interpolation::CubicSpline => 3,
+                    _ => 1,
+                };
+                outputs.len() / (inputs.len() * values_per_key).max(1)
+            }
+            _ => width,
         };
+        if width == 0 {
+            continue;
+        }
 
-        let tex_coord_attribute = Attribute {
-            format: Format::new(Dimension::VEC2, Type::FLOAT, Usage::DATA),
-            slot: Slot::TexCoord,
-            offset: positions.len() + normals.len(),
+        if let Some(last) = inputs.last() {
+            clip.duration = clip.duration.max(*last);
+        }
+
+        clip.channels.push(animation::Channel {
+            node: channel.target().node().index(),
+            property,
+            sampler: animation::Sampler {
+                interpolation,
+                inputs,
+                outputs,
+                width,
+            },
+        });
+    }
+
+    clip
+}
+
+type Loader = fn(&Path, &LoadOptions, &texture::TextureCache) -> Result<ModelPointer, LoadError>;
+
+// importers by file extension, compared in lowercase
+const LOADERS: [(&str, Loader); 4] = [
+    ("gltf", load_gltf_file),
+    ("glb", load_gltf_file),
+    ("obj", obj::load_obj_model),
+    ("ply", ply::load_ply_model),
+];
+
+pub fn load_model(
+    path: &Path,
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match LOADERS
+        .iter()
+        .find(|(loader_extension, _)| *loader_extension == extension)
+    {
+        Some((_, loader)) => loader(path, options, texture_cache),
+        None => Err(LoadError::UnsupportedFormat(extension)),
+    }
+}
+
+fn load_gltf_file(
+    path: &Path,
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    load_gltf_model_with_options(path.to_string_lossy().into_owned(), options, texture_cache)
+}
+
+pub fn load_gltf_model(
+    path: String,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    load_gltf_model_with_options(path, &LoadOptions::default(), texture_cache)
+}
+
+pub fn load_gltf_model_with_options(
+    path: String,
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    let bytes = std::fs::read(&path)?;
+    let json = read_gltf_json(&bytes);
+    let (gltf, buffers, images) = if uses_extension(&json, "EXT_meshopt_compression") {
+        let directory = Path::new(&path).parent().unwrap_or_else(|| Path::new(""));
+        import_meshopt_gltf(&bytes, &json, Some(directory))?
+    } else {
+        gltf::import(&path)?
+    };
+    let textures = GltfTextures {
+        document: &gltf,
+        images: &images,
+        origin: ImageOrigin::File(Path::new(&path)),
+        cache: texture_cache,
+        anisotropy: options.anisotropy,
+    };
+    build_gltf_model(&gltf, &buffers, textures, &json, options)
+}
+
+pub fn load_gltf_model_from_slice(
+    bytes: &[u8],
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    let json = read_gltf_json(bytes);
+    let (gltf, buffers, images) = if uses_extension(&json, "EXT_meshopt_compression") {
+        import_meshopt_gltf(bytes, &json, None)?
+    } else {
+        gltf::import_slice(bytes)?
+    };
+    let textures = GltfTextures {
+        document: &gltf,
+        images: &images,
+        origin: ImageOrigin::Memory(texture_cache.next_memory_id()),
+        cache: texture_cache,
+        anisotropy: options.anisotropy,
+    };
+    build_gltf_model(&gltf, &buffers, textures, &json, options)
+}
+
+fn uses_extension(json: &serde_json::Value, name: &str) -> bool {
+    json.get("extensionsUsed")
+        .and_then(|extensions| extensions.as_array())
+        .map_or(false, |extensions| {
+            extensions.iter().any(|extension| extension == name)
+        })
+}
+
+type GltfImport = (
+    gltf::Document,
+    Vec<gltf::buffer::Data>,
+    Vec<gltf::image::Data>,
+);
+
+// the fallback buffers of meshopt files usually have no data, which gltf::import rejects,
+// so the buffers and images are loaded here and the compressed views decoded into them
+fn import_meshopt_gltf(
+    bytes: &[u8],
+    json: &serde_json::Value,
+    directory: Option<&Path>,
+) -> Result<GltfImport, LoadError> {
+    let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(bytes)?;
+
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+    for buffer in document.buffers() {
+        let mut data = match buffer.source() {
+            gltf::buffer::Source::Uri(uri) => read_gltf_uri(uri, directory)?,
+            // only the first buffer without a uri is the glb chunk
+            gltf::buffer::Source::Bin => blob.take().unwrap_or_default(),
+        };
+        if data.len() < buffer.length() {
+            data.resize(buffer.length(), 0);
+        }
+        buffers.push(data);
+    }
+    meshopt::decode_buffer_views(json, &mut buffers)?;
+
+    let mut images: Vec<gltf::image::Data> = Vec::new();
+    for image in document.images() {
+        let encoded = match image.source() {
+            gltf::image::Source::Uri { uri, .. } => read_gltf_uri(uri, directory)?,
+            gltf::image::Source::View { view, .. } => buffers[view.buffer().index()]
+                [view.offset()..view.offset() + view.length()]
+                .to_vec(),
         };
+        let decoded = image::load_from_memory(&encoded)
+            .map_err(|error| LoadError::Parse(format!("image {}: {}", image.index(), error)))?
+            .to_rgba();
+        images.push(gltf::image::Data {
+            width: decoded.width(),
+            height: decoded.height(),
+            format: gltf::image::Format::R8G8B8A8,
+            pixels: decoded.into_raw(),
+        });
+    }
 
-        let mut vertex_buffer = buffer::Buffer::default();
-        let vertex_buffer_data = &mut vertex_buffer.data;
+    let buffers = buffers.into_iter().map(gltf::buffer::Data).collect();
+    Ok((document, buffers, images))
+}
+
+fn read_gltf_uri(uri: &str, directory: Option<&Path>) -> Result<Vec<u8>, LoadError> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let encoded = data
+            .split(";base64,")
+            .nth(1)
+            .ok_or_else(|| LoadError::Parse(String::from("only base64 data uris are supported")))?;
+        return base64::decode(encoded).map_err(|error| LoadError::Parse(error.to_string()));
+    }
 
-        vertex_buffer_data.extend_from_slice(to_byte_slice(&positions[..]));
-        vertex_buffer_data.extend_from_slice(to_byte_slice(&normals[..]));
-        vertex_buffer_data.extend_from_slice(to_byte_slice(&tex_coords[..]));
+    match directory {
+        Some(directory) => Ok(std::fs::read(directory.join(uri))?),
+        None => Err(LoadError::Parse(format!(
+            "cannot resolve '{}' without a file path",
+            uri
+        ))),
+    }
+}
 
-        let mut index_buffer = buffer::Buffer::default();
-        index_buffer
-            .data
-            .extend_from_slice(to_byte_slice(&indices[..]));
+fn build_gltf_model(
+    gltf: &gltf::Document,
+    buffers: &Vec<gltf::buffer::Data>,
+    mut textures: GltfTextures,
+    json: &serde_json::Value,
+    options: &LoadOptions,
+) -> Result<ModelPointer, LoadError> {
+    if let Some(scene) = gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        let mut model: Model = Model::default();
 
-        model.attributes = vec![position_attribute, normal_attribute, tex_coord_attribute];
-        model.vertex_buffer = vertex_buffer;
-        model.index_buffer = index_buffer;
+        let mut streams = VertexStreams::default();
+
+        let skinned = gltf.skins().len() > 0;
+
+        let mut process_node = |node: &gltf::scene::Node, transform: iml::Mat4| {
+            if let Some(gltf_mesh) = node.mesh() {
+                let mut mesh = Mesh::default();
+                mesh.matrix = transform;
+                mesh.node = Some(node.index());
+                mesh.skin = node.skin().map(|skin| skin.index());
+                let mut morph_primitives: Vec<morph::Primitive> = Vec::new();
+                for prim in gltf_mesh.primitives() {
+                    let primitive = match read_gltf_primitive(
+                        &gltf_mesh,
+                        &prim,
+                        buffers,
+                        skinned,
+                        options,
+                        &mut model.warnings,
+                    )? {
+                        Some(primitive) => primitive,
+                        None => continue,
+                    };
+
+                    let material = load_gltf_material(&prim.material(), &mut textures, json);
+
+                    let first_vertex = streams.vertex_count();
+                    let sub_mesh = streams.push(&primitive, model.materials.len());
+                    mesh.sub_meshes.push(sub_mesh);
+                    model.materials.push(material);
+
+                    if !primitive.targets.is_empty() {
+                        // offsets are relative to each attribute section until the layout is known
+                        morph_primitives.push(morph::Primitive {
+                            positions: primitive.positions,
+                            normals: primitive.normals,
+                            tangents: primitive.tangents,
+                            targets: primitive.targets,
+                            position_offset: first_vertex * 3 * FLOAT_SIZE,
+                            normal_offset: first_vertex * 3 * FLOAT_SIZE,
+                            tangent_offset: first_vertex * 4 * FLOAT_SIZE,
+                        });
+                    }
+                }
+
+                if !morph_primitives.is_empty() {
+                    // node weights override the mesh weights
+                    let target_count = morph_primitives[0].targets.len();
+                    let default_weights = node
+                        .weights()
+                        .or_else(|| gltf_mesh.weights())
+                        .map(|weights| weights.to_vec())
+                        .unwrap_or_else(|| vec![0.0; target_count]);
+
+                    mesh.morph = Some(morph::MorphMesh {
+                        node: node.index(),
+                        default_weights,
+                        primitives: morph_primitives,
+                        applied_weights: Vec::new(),
+                    });
+                }
+                model.meshes.push(mesh);
+            }
+
+            Ok(())
+        };
+
+        let matrix = iml::Mat4::identity();
+        for node in scene.nodes() {
+            process_gltf_node_tree(&node, matrix, &mut process_node)?;
+        }
+
+        let normal_start = streams.section_start(Slot::Normal);
+        let tangent_start = streams.section_start(Slot::Tangent);
+        for morph in model
+            .meshes
+            .iter_mut()
+            .filter_map(|mesh| mesh.morph.as_mut())
+        {
+            for primitive in &mut morph.primitives {
+                primitive.normal_offset += normal_start;
+                primitive.tangent_offset += tangent_start;
+            }
+        }
+        streams.write(&mut model);
+
+        model.nodes = load_gltf_nodes(gltf);
+        model.skins = gltf
+            .skins()
+            .map(|skin| load_gltf_skin(&skin, buffers))
+            .collect();
+        model.animations = gltf
+            .animations()
+            .map(|animation| load_gltf_animation(&animation, buffers))
+            .collect();
+
+        if !model.animations.is_empty() {
+            model.animator.clip = Some(0);
+        }
+        model.update_animation(0.0);
         Ok(ModelPointer::new(model))
     } else {
-        Err(String::from("no default scene in gltf"))
+        Err(LoadError::NoScene)
     }
 }
 
+// iml matrices are column major like the animation ones
+fn to_iml_matrix(matrix: &animation::Matrix4) -> iml::Mat4 {
+    unsafe { std::ptr::read_unaligned(matrix.as_ptr() as *const iml::Mat4) }
+}
+
+// returns the bytes written
+fn write_floats(data: &mut [u8], offset: usize, values: &[f32]) -> std::ops::Range<usize> {
+    let bytes = to_byte_slice(values);
+    let range = offset..offset + bytes.len();
+    data[range.clone()].copy_from_slice(bytes);
+    range
+}
+
 fn to_byte_slice<'a, T>(floats: &'a [T]) -> &'a [u8] {
     unsafe {
         std::slice::from_raw_parts(
@@ -526,3 +1867,516 @@ fn to_byte_slice<'a, T>(floats: &'a [T]) -> &'a [u8] {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    // column major like glTF
+    fn columns(matrix: &iml::Mat4) -> Vec<f32> {
+        unsafe { std::slice::from_raw_parts(matrix.as_ptr(), 16) }.to_vec()
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Vec<f32> {
+        vec![
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, x, y, z, 1.0,
+        ]
+    }
+
+    fn float_bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    // a glTF document with its buffer embedded as a data uri
+    fn load_gltf(mut document: serde_json::Value, buffer: &[u8]) -> Result<Model, LoadError> {
+        document["asset"] = json!({ "version": "2.0" });
+        document["buffers"] = json!([{
+            "byteLength": buffer.len(),
+            "uri": format!(
+                "data:application/octet-stream;base64,{}",
+                base64::encode(buffer)
+            ),
+        }]);
+        let bytes = serde_json::to_vec(&document).unwrap();
+        let model = load_gltf_model_from_slice(
+            &bytes,
+            &LoadOptions::default(),
+            &texture::TextureCache::deferred(),
+        )?;
+        Ok(model.into_inner())
+    }
+
+    const TRIANGLE: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+    // one mesh with a single non indexed triangle at the start of the buffer
+    fn triangle_mesh() -> serde_json::Value {
+        json!({
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+            "accessors": [{
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 0.0],
+            }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        })
+    }
+
+    #[test]
+    fn node_hierarchies_give_world_matrices() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let mut document = triangle_mesh();
+        document["scenes"] = json!([{ "nodes": [0] }]);
+        document["nodes"] = json!([
+            { "mesh": 0, "translation": [1.0, 0.0, 0.0], "children": [1] },
+            {
+                "mesh": 0,
+                "rotation": [0.0, 0.0, half, half],
+                "scale": [2.0, 2.0, 2.0],
+                "children": [2],
+            },
+            {
+                "mesh": 0,
+                "matrix": [
+                    1.0, 0.0, 0.0, 0.0,
+                    0.0, 1.0, 0.0, 0.0,
+                    0.0, 0.0, 1.0, 0.0,
+                    0.0, 3.0, 0.0, 1.0,
+                ],
+            },
+        ]);
+        let model = load_gltf(document, &float_bytes(&TRIANGLE)).unwrap();
+
+        // meshes are collected depth first from the scene roots
+        assert_eq!(model.meshes.len(), 3);
+        assert_close(
+            &columns(&model.meshes[0].matrix),
+            &translation(1.0, 0.0, 0.0),
+        );
+        // rotated a quarter turn about z and doubled
+        let rotated_scaled = [
+            0.0, 2.0, 0.0, 0.0, -2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        ];
+        assert_close(&columns(&model.meshes[1].matrix), &rotated_scaled);
+        // three up in the rotated and scaled space of the parent
+        let mut grandchild = rotated_scaled;
+        grandchild[12] = -5.0;
+        assert_close(&columns(&model.meshes[2].matrix), &grandchild);
+
+        let nodes: Vec<Option<usize>> = model.meshes.iter().map(|mesh| mesh.node).collect();
+        assert_eq!(nodes, [Some(0), Some(1), Some(2)]);
+    }
+
+    const QUAD: [f32; 12] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0];
+    const QUAD_NORMALS: [f32; 12] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+
+    // a single primitive over the four corners of a quad. the buffer holds the
+    // positions, the normals and then the u16 indices
+    fn load_quad(mode: u32, indices: Option<&[u16]>, normals: bool) -> Model {
+        let mut buffer = float_bytes(&QUAD);
+        buffer.extend(float_bytes(&QUAD_NORMALS));
+        let mut accessors = vec![
+            json!({
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 4,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 0.0],
+            }),
+            json!({ "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3" }),
+        ];
+        let mut views = vec![
+            json!({ "buffer": 0, "byteLength": 48 }),
+            json!({ "buffer": 0, "byteOffset": 48, "byteLength": 48 }),
+        ];
+
+        let mut attributes = json!({ "POSITION": 0 });
+        if normals {
+            attributes["NORMAL"] = json!(1);
+        }
+        let mut primitive = json!({ "attributes": attributes, "mode": mode });
+        if let Some(indices) = indices {
+            views.push(json!({
+                "buffer": 0,
+                "byteOffset": buffer.len(),
+                "byteLength": indices.len() * 2,
+            }));
+            accessors.push(json!({
+                "bufferView": 2,
+                "componentType": 5123,
+                "count": indices.len(),
+                "type": "SCALAR",
+            }));
+            buffer.extend(indices.iter().flat_map(|index| index.to_le_bytes()));
+            primitive["indices"] = json!(2);
+        }
+        // the last view has to end on four bytes
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+
+        let document = json!({
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [primitive] }],
+            "accessors": accessors,
+            "bufferViews": views,
+        });
+        load_gltf(document, &buffer).unwrap()
+    }
+
+    fn indices(model: &Model) -> Vec<u32> {
+        model
+            .index_buffer
+            .data
+            .chunks_exact(4)
+            .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    }
+
+    fn vertex_count(model: &Model) -> usize {
+        stream::vertex_count(&model.attributes, model.vertex_buffer.data.len())
+    }
+
+    fn normals(model: &Model) -> Vec<f32> {
+        let attribute = model
+            .attributes
+            .iter()
+            .find(|attribute| attribute.slot == Slot::Normal)
+            .unwrap();
+        stream::read_floats(&model.vertex_buffer.data, attribute, vertex_count(model))
+    }
+
+    const TRIANGLES: u32 = 4;
+    const TRIANGLE_STRIP: u32 = 5;
+    const TRIANGLE_FAN: u32 = 6;
+
+    #[test]
+    fn missing_normals_are_generated_flat() {
+        let model = load_quad(TRIANGLES, Some(&[0, 1, 2, 2, 1, 3]), false);
+        // flat normals give every corner its own vertex
+        assert_eq!(vertex_count(&model), 6);
+        assert_eq!(indices(&model), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(normals(&model), [0.0, 0.0, 1.0].repeat(6));
+    }
+
+    #[test]
+    fn given_normals_keep_the_vertices_shared() {
+        let model = load_quad(TRIANGLES, Some(&[0, 1, 2, 2, 1, 3]), true);
+        assert_eq!(vertex_count(&model), 4);
+        assert_eq!(indices(&model), [0, 1, 2, 2, 1, 3]);
+        assert_eq!(normals(&model), QUAD_NORMALS);
+    }
+
+    #[test]
+    fn strips_become_triangle_lists() {
+        let model = load_quad(TRIANGLE_STRIP, Some(&[0, 1, 2, 3]), true);
+        assert_eq!(vertex_count(&model), 4);
+        // every other triangle is flipped to keep the winding
+        assert_eq!(indices(&model), [0, 1, 2, 1, 3, 2]);
+        assert_eq!(model.meshes[0].sub_meshes[0].num_indices, 6);
+    }
+
+    #[test]
+    fn fans_become_triangle_lists() {
+        let model = load_quad(TRIANGLE_FAN, Some(&[0, 1, 3, 2]), true);
+        assert_eq!(vertex_count(&model), 4);
+        assert_eq!(indices(&model), [1, 3, 0, 3, 2, 0]);
+    }
+
+    #[test]
+    fn primitives_without_indices_draw_their_vertices_in_order() {
+        let model = load_quad(TRIANGLE_STRIP, None, true);
+        assert_eq!(vertex_count(&model), 4);
+        assert_eq!(indices(&model), [0, 1, 2, 1, 3, 2]);
+
+        let mut document = triangle_mesh();
+        document["scenes"] = json!([{ "nodes": [0] }]);
+        document["nodes"] = json!([{ "mesh": 0 }]);
+        let model = load_gltf(document, &float_bytes(&TRIANGLE)).unwrap();
+        assert_eq!(vertex_count(&model), 3);
+        assert_eq!(indices(&model), [0, 1, 2]);
+        assert_eq!(normals(&model), [0.0, 0.0, 1.0].repeat(3));
+    }
+
+    #[test]
+    fn unsupported_modes_are_skipped() {
+        const LINES: u32 = 1;
+        let model = load_quad(LINES, Some(&[0, 1, 2, 3]), true);
+        assert_eq!(model.meshes.len(), 1);
+        assert!(model.meshes[0].sub_meshes.is_empty());
+        assert_eq!(
+            model.warnings,
+            ["mesh 0 primitive 0: skipping unsupported mode Lines"]
+        );
+    }
+
+    #[test]
+    fn primitives_without_positions_fail_to_load() {
+        let mut document = triangle_mesh();
+        document["scenes"] = json!([{ "nodes": [0] }]);
+        document["nodes"] = json!([{ "mesh": 0 }]);
+        document["meshes"] = json!([{ "primitives": [{ "attributes": { "NORMAL": 0 } }] }]);
+        // the gltf crate already rejects the document while validating it
+        assert!(matches!(
+            load_gltf(document, &float_bytes(&TRIANGLE)),
+            Err(LoadError::Import(_))
+        ));
+    }
+
+    #[test]
+    fn animated_nodes_move_rigid_meshes() {
+        let mut model = Model {
+            nodes: vec![
+                Node {
+                    parent: None,
+                    rest_pose: animation::Pose::default(),
+                },
+                Node {
+                    parent: Some(0),
+                    rest_pose: animation::Pose {
+                        translation: [0.0, 1.0, 0.0],
+                        ..animation::Pose::default()
+                    },
+                },
+            ],
+            meshes: vec![Mesh {
+                node: Some(1),
+                ..Mesh::default()
+            }],
+            animations: vec![animation::Clip {
+                name: String::from("slide"),
+                duration: 1.0,
+                channels: vec![animation::Channel {
+                    node: 0,
+                    property: animation::Property::Translation,
+                    sampler: animation::Sampler {
+                        interpolation: animation::Interpolation::Linear,
+                        inputs: vec![0.0, 1.0],
+                        outputs: vec![0.0, 0.0, 0.0, 4.0, 0.0, 0.0],
+                        width: 3,
+                    },
+                }],
+            }],
+            animator: animation::Animator {
+                clip: Some(0),
+                looping: false,
+                ..animation::Animator::default()
+            },
+            ..Model::default()
+        };
+
+        model.update_animation(0.0);
+        assert_close(
+            &columns(&model.meshes[0].matrix),
+            &translation(0.0, 1.0, 0.0),
+        );
+
+        model.update_animation(0.25);
+        assert_close(
+            &columns(&model.meshes[0].matrix),
+            &translation(1.0, 1.0, 0.0),
+        );
+    }
+
+    // a quad facing +z with the second uv set turned a quarter
+    fn uv_quad(u: [f32; 4]) -> PrimitiveData {
+        let positions = vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        let tex_coords: Vec<f32> = (0..4)
+            .flat_map(|vertex| [u[vertex], positions[vertex * 3 + 1]])
+            .collect();
+        let tex_coords1: Vec<f32> = positions
+            .chunks_exact(3)
+            .flat_map(|position| [position[1], -position[0]])
+            .collect();
+        PrimitiveData {
+            normals: [0.0, 0.0, 1.0].repeat(4),
+            tex_coords,
+            tex_coords1,
+            tangents: Vec::new(),
+            joints: (0..16).map(|value| value as f32).collect(),
+            weights: Vec::new(),
+            indices: vec![0, 1, 2, 0, 2, 3],
+            targets: vec![morph::Target {
+                positions: positions.clone(),
+                normals: Vec::new(),
+                tangents: Vec::new(),
+            }],
+            packed: Vec::new(),
+            positions,
+        }
+    }
+
+    #[test]
+    fn tangents_follow_the_normal_map_uv_set() {
+        let mut primitive = uv_quad([0.0, 1.0, 1.0, 0.0]);
+        primitive.generate_missing_attributes(&LoadOptions::default(), 0);
+        assert_close(&primitive.tangents, &[1.0, 0.0, 0.0, 1.0].repeat(4));
+
+        let mut primitive = uv_quad([0.0, 1.0, 1.0, 0.0]);
+        primitive.generate_missing_attributes(&LoadOptions::default(), 1);
+        assert_close(&primitive.tangents, &[0.0, 1.0, 0.0, 1.0].repeat(4));
+    }
+
+    #[test]
+    fn mirrored_tangents_split_shared_vertices() {
+        // the second triangle runs its u the other way
+        let mut primitive = uv_quad([0.0, 1.0, 0.0, 1.0]);
+        primitive.generate_missing_attributes(&LoadOptions::default(), 0);
+
+        // vertices 0 and 2 are copied for the mirrored triangle
+        assert_eq!(primitive.indices, vec![0, 1, 2, 4, 5, 3]);
+        assert_close(
+            &primitive.tangents,
+            &[
+                [1.0, 0.0, 0.0, 1.0].repeat(3),
+                [-1.0, 0.0, 0.0, -1.0].repeat(3),
+            ]
+            .concat(),
+        );
+        let copies = [0, 2];
+        let copied = |values: &[f32], width: usize| -> Vec<f32> {
+            copies
+                .iter()
+                .flat_map(|vertex| values[vertex * width..(vertex + 1) * width].to_vec())
+                .collect()
+        };
+        assert_close(&primitive.positions[12..], &copied(&primitive.positions, 3));
+        assert_close(
+            &primitive.tex_coords1[8..],
+            &copied(&primitive.tex_coords1, 2),
+        );
+        assert_close(&primitive.joints[16..], &copied(&primitive.joints, 4));
+        assert_close(
+            &primitive.targets[0].positions[12..],
+            &copied(&primitive.targets[0].positions, 3),
+        );
+        assert!(primitive.weights.is_empty());
+    }
+
+    #[test]
+    fn quantized_streams_keep_their_format() {
+        // u16 positions padded to 8 bytes and normalized i8 normals padded to 4
+        let mut buffer = Vec::new();
+        for position in [[0u16, 0, 0], [2, 0, 0], [0, 2, 0]] {
+            buffer.extend(position.iter().flat_map(|value| value.to_le_bytes()));
+            buffer.extend([0, 0]);
+        }
+        for _ in 0..3 {
+            buffer.extend([0, 0, 127, 0]);
+        }
+        let document = json!({
+            "extensionsUsed": ["KHR_mesh_quantization"],
+            "extensionsRequired": ["KHR_mesh_quantization"],
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "NORMAL": 1 } }] }],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5123,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0, 0, 0],
+                    "max": [2, 2, 0],
+                },
+                {
+                    "bufferView": 1,
+                    "componentType": 5120,
+                    "normalized": true,
+                    "count": 3,
+                    "type": "VEC3",
+                },
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 24, "byteStride": 8 },
+                { "buffer": 0, "byteOffset": 24, "byteLength": 12, "byteStride": 4 },
+            ],
+        });
+        let model = load_gltf(document, &buffer).unwrap();
+        let attribute = |slot: Slot| {
+            *model
+                .attributes
+                .iter()
+                .find(|attribute| attribute.slot == slot)
+                .unwrap()
+        };
+
+        let position = attribute(Slot::Position);
+        assert!(position.format == Format::new(Dimension::VEC3, Type::UINT16, Usage::DATA));
+        assert_eq!(position.stride, 8);
+        assert!(position.interpretation == stream::Interpretation::Float);
+        assert_eq!(
+            stream::read_floats(&model.vertex_buffer.data, &position, 3),
+            [0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0]
+        );
+
+        let normal = attribute(Slot::Normal);
+        assert!(normal.format == Format::new(Dimension::VEC3, Type::INT8, Usage::DATA));
+        assert_eq!(normal.stride, 4);
+        assert!(normal.interpretation == stream::Interpretation::Normalized);
+        assert_eq!(normals(&model), [0.0, 0.0, 1.0].repeat(3));
+
+        // generated tangents are not quantized
+        let tangent = attribute(Slot::Tangent);
+        assert!(tangent.format == Format::new(Dimension::VEC4, Type::FLOAT, Usage::DATA));
+    }
+
+    #[test]
+    fn morphing_marks_only_the_blended_bytes_dirty() {
+        // one vertex, the position, normal and tangent sections follow each other
+        let base = [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0];
+        let mut model = Model::default();
+        model.vertex_buffer = buffer::Buffer::new(float_bytes(&base));
+        model.vertex_buffer.dirty = false;
+        let mut mesh = Mesh::default();
+        mesh.morph = Some(morph::MorphMesh {
+            node: 0,
+            default_weights: vec![0.0],
+            primitives: vec![morph::Primitive {
+                positions: base[..3].to_vec(),
+                normals: base[3..6].to_vec(),
+                tangents: base[6..].to_vec(),
+                targets: vec![morph::Target {
+                    positions: vec![1.0, 0.0, 0.0],
+                    normals: vec![0.0; 3],
+                    tangents: vec![0.0; 3],
+                }],
+                position_offset: 0,
+                normal_offset: 12,
+                tangent_offset: 24,
+            }],
+            applied_weights: Vec::new(),
+        });
+        model.meshes.push(mesh);
+
+        model.apply_morph_weights(&[vec![0.5]]);
+        assert!(!model.vertex_buffer.dirty);
+        assert_eq!(model.vertex_buffer.dirty_ranges, [0..12, 12..24, 24..40]);
+        let mut morphed = base;
+        morphed[0] = 0.5;
+        assert_eq!(model.vertex_buffer.data, float_bytes(&morphed));
+
+        // unchanged weights are not written again
+        model.vertex_buffer.dirty_ranges.clear();
+        model.apply_morph_weights(&[vec![0.5]]);
+        assert!(model.vertex_buffer.dirty_ranges.is_empty());
+    }
+}
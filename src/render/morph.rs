@@ -0,0 +1,158 @@
+// morph.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+// displacements of one glTF morph target, empty when the target leaves the attribute alone
+pub struct Target {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    // xyz only, the handedness in w never morphs
+    pub tangents: Vec<f32>,
+}
+
+// a morphed primitive, keeps the base attributes so every frame blends from the rest shape
+pub struct Primitive {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub tangents: Vec<f32>,
+    pub targets: Vec<Target>,
+    // byte offsets of the primitive inside the planar model vertex buffer
+    pub position_offset: usize,
+    pub normal_offset: usize,
+    pub tangent_offset: usize,
+}
+
+impl Primitive {
+    pub fn blend(&self, weights: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let position_deltas: Vec<&[f32]> = self
+            .targets
+            .iter()
+            .map(|target| &target.positions[..])
+            .collect();
+        let normal_deltas: Vec<&[f32]> = self
+            .targets
+            .iter()
+            .map(|target| &target.normals[..])
+            .collect();
+        let tangent_deltas: Vec<&[f32]> = self
+            .targets
+            .iter()
+            .map(|target| &target.tangents[..])
+            .collect();
+
+        (
+            blend(&self.positions, 3, &position_deltas, weights),
+            blend(&self.normals, 3, &normal_deltas, weights),
+            blend(&self.tangents, 4, &tangent_deltas, weights),
+        )
+    }
+}
+
+pub struct MorphMesh {
+    // the node instancing the mesh, animation channels target nodes
+    pub node: usize,
+    pub default_weights: Vec<f32>,
+    pub primitives: Vec<Primitive>,
+    // weights the vertex buffer currently holds
+    pub applied_weights: Vec<f32>,
+}
+
+// base + sum(weight * delta), deltas are always vec3 and only offset the first three
+// components of each element so vec4 tangents keep their w
+pub fn blend(base: &[f32], width: usize, deltas: &[&[f32]], weights: &[f32]) -> Vec<f32> {
+    let mut result = base.to_vec();
+    let count = base.len() / width;
+
+    for (delta, weight) in deltas.iter().zip(weights) {
+        if *weight == 0.0 || delta.len() < count * 3 {
+            continue;
+        }
+
+        for element in 0..count {
+            for axis in 0..3 {
+                result[element * width + axis] += delta[element * 3 + axis] * weight;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    // two vertices and two targets, the second target only moves positions
+    fn primitive() -> Primitive {
+        Primitive {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            tangents: vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, -1.0],
+            targets: vec![
+                Target {
+                    positions: vec![0.0, 1.0, 0.0, 0.0, 2.0, 0.0],
+                    normals: vec![0.0, 1.0, 0.0, 0.0, -1.0, 0.0],
+                    tangents: vec![0.0, 0.0, 1.0, 0.0, 0.0, 2.0],
+                },
+                Target {
+                    positions: vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                    normals: Vec::new(),
+                    tangents: Vec::new(),
+                },
+            ],
+            position_offset: 0,
+            normal_offset: 0,
+            tangent_offset: 0,
+        }
+    }
+
+    #[test]
+    fn deltas_are_added_by_weight() {
+        let (positions, normals, tangents) = primitive().blend(&[0.5, 2.0]);
+        assert_close(&positions, &[2.0, 0.5, 0.0, 3.0, 1.0, 0.0]);
+        assert_close(&normals, &[0.0, 0.5, 1.0, 0.0, -0.5, 1.0]);
+        // w keeps the handedness
+        assert_close(&tangents, &[1.0, 0.0, 0.5, 1.0, 1.0, 0.0, 1.0, -1.0]);
+    }
+
+    #[test]
+    fn zero_weights_give_the_base_shape() {
+        let primitive = primitive();
+        let (positions, normals, tangents) = primitive.blend(&[0.0, 0.0]);
+        assert_close(&positions, &primitive.positions);
+        assert_close(&normals, &primitive.normals);
+        assert_close(&tangents, &primitive.tangents);
+
+        let (positions, _, _) = primitive.blend(&[]);
+        assert_close(&positions, &primitive.positions);
+    }
+
+    #[test]
+    fn targets_without_a_weight_are_left_out() {
+        let (positions, normals, _) = primitive().blend(&[1.0]);
+        assert_close(&positions, &[0.0, 1.0, 0.0, 1.0, 2.0, 0.0]);
+        assert_close(&normals, &[0.0, 1.0, 1.0, 0.0, -1.0, 1.0]);
+    }
+
+    #[test]
+    fn negative_weights_subtract() {
+        let base = [1.0, 1.0, 1.0];
+        let delta: &[f32] = &[1.0, 2.0, 3.0];
+        assert_close(&blend(&base, 3, &[delta], &[-1.0]), &[0.0, -1.0, -2.0]);
+    }
+}
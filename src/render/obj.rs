@@ -0,0 +1,717 @@
+// obj.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{
+    geometry,
+    material::{AlphaMode, Material, MaterialTexture, TextureTransform},
+    model::{LoadError, LoadOptions, Mesh, Model, ModelPointer, PrimitiveData, VertexStreams},
+    texture,
+};
+
+use iml;
+
+pub fn load_obj_model(
+    path: &Path,
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    let source = std::fs::read_to_string(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let obj = parse_obj(&source)?;
+
+    let mut model = Model::default();
+    let mut libraries: HashMap<String, MtlMaterial> = HashMap::new();
+    for library in &obj.material_libraries {
+        match std::fs::read_to_string(directory.join(library)) {
+            Ok(source) => {
+                for material in parse_mtl(&source, &mut model.warnings)? {
+                    libraries.insert(material.name.clone(), material);
+                }
+            }
+            Err(error) => model.warnings.push(format!(
+                "failed to read material library {}: {}",
+                library, error
+            )),
+        }
+    }
+
+    let mut mesh = Mesh::default();
+    let mut streams = VertexStreams::default();
+
+    for group in obj.groups {
+        if group.indices.is_empty() {
+            continue;
+        }
+
+        let material = match group.material.as_ref().and_then(|name| libraries.get(name)) {
+            Some(mtl) => {
+                load_mtl_material(mtl, directory, options, texture_cache, &mut model.warnings)
+            }
+            None => Material::default(),
+        };
+
+        let mut primitive = group.into_primitive();
+        primitive.generate_missing_attributes(options, 0);
+
+        mesh.sub_meshes
+            .push(streams.push(&primitive, model.materials.len()));
+        model.materials.push(material);
+    }
+
+    streams.write(&mut model);
+    model.meshes.push(mesh);
+    Ok(ModelPointer::new(model))
+}
+
+struct ObjFile {
+    material_libraries: Vec<String>,
+    groups: Vec<ObjGroup>,
+}
+
+// faces sharing a material, each becomes a sub mesh
+struct ObjGroup {
+    material: Option<String>,
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    tex_coords: Vec<f32>,
+    indices: Vec<u32>,
+    // false once a face corner has no normal, they are generated instead
+    has_normals: bool,
+    // position, tex coord and normal index of every vertex already added
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+impl ObjGroup {
+    fn new(material: Option<String>) -> ObjGroup {
+        ObjGroup {
+            material,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            tex_coords: Vec::new(),
+            indices: Vec::new(),
+            has_normals: true,
+            vertices: HashMap::new(),
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        key: (usize, Option<usize>, Option<usize>),
+        attributes: &ObjAttributes,
+    ) -> u32 {
+        if let Some(index) = self.vertices.get(&key) {
+            return *index;
+        }
+
+        let (position, tex_coord, normal) = key;
+        self.positions
+            .extend_from_slice(&attributes.positions[position]);
+        self.tex_coords
+            .extend_from_slice(&tex_coord.map_or([0.0; 2], |index| attributes.tex_coords[index]));
+        match normal {
+            Some(index) => self.normals.extend_from_slice(&attributes.normals[index]),
+            None => {
+                self.normals.extend_from_slice(&[0.0; 3]);
+                self.has_normals = false;
+            }
+        }
+
+        let index = self.vertices.len() as u32;
+        self.vertices.insert(key, index);
+        index
+    }
+
+    fn into_primitive(self) -> PrimitiveData {
+        PrimitiveData {
+            positions: self.positions,
+            normals: if self.has_normals {
+                self.normals
+            } else {
+                Vec::new()
+            },
+            tex_coords1: self.tex_coords.clone(),
+            tex_coords: self.tex_coords,
+            tangents: Vec::new(),
+            joints: Vec::new(),
+            weights: Vec::new(),
+            indices: self.indices,
+            targets: Vec::new(),
+            packed: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ObjAttributes {
+    positions: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+}
+
+fn parse_obj(source: &str) -> Result<ObjFile, LoadError> {
+    let mut attributes = ObjAttributes::default();
+    let mut material_libraries: Vec<String> = Vec::new();
+    let mut groups: Vec<ObjGroup> = Vec::new();
+    let mut group_by_material: HashMap<Option<String>, usize> = HashMap::new();
+    let mut material: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => attributes
+                .positions
+                .push(parse_floats(&mut tokens, line_number)?),
+            "vt" => {
+                // obj puts v = 0 at the bottom, the images are uploaded top row first
+                let [u, v]: [f32; 2] = parse_floats(&mut tokens, line_number)?;
+                attributes.tex_coords.push([u, 1.0 - v]);
+            }
+            "vn" => attributes
+                .normals
+                .push(parse_floats(&mut tokens, line_number)?),
+            "f" => {
+                let group_index = *group_by_material
+                    .entry(material.clone())
+                    .or_insert_with(|| {
+                        groups.push(ObjGroup::new(material.clone()));
+                        groups.len() - 1
+                    });
+                let group = &mut groups[group_index];
+
+                let mut corners: Vec<u32> = Vec::new();
+                for corner in tokens {
+                    let key = parse_corner(corner, &attributes, line_number)?;
+                    corners.push(group.vertex(key, &attributes));
+                }
+                if corners.len() < 3 {
+                    return Err(parse_error(line_number, "face with less than 3 vertices"));
+                }
+
+                // polygons are convex in practice, a fan keeps their winding
+                group.indices.extend(geometry::triangulate(
+                    geometry::Topology::TriangleFan,
+                    &corners,
+                ));
+            }
+            "usemtl" => material = Some(rest_of_line(tokens)),
+            "mtllib" => material_libraries.push(rest_of_line(tokens)),
+            // objects, groups, smoothing groups and lines do not change how it is drawn
+            _ => {}
+        }
+    }
+
+    Ok(ObjFile {
+        material_libraries,
+        groups,
+    })
+}
+
+// v, v/vt, v//vn or v/vt/vn, indices start at one and negative ones count from the end
+fn parse_corner(
+    corner: &str,
+    attributes: &ObjAttributes,
+    line_number: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), LoadError> {
+    let mut indices = corner.split('/');
+    let position = match indices.next() {
+        Some(index) => resolve_index(index, attributes.positions.len(), line_number)?,
+        None => return Err(parse_error(line_number, "face corner without a position")),
+    };
+
+    let mut optional_index = |count: usize| match indices.next() {
+        Some(index) if !index.is_empty() => resolve_index(index, count, line_number).map(Some),
+        _ => Ok(None),
+    };
+    let tex_coord = optional_index(attributes.tex_coords.len())?;
+    let normal = optional_index(attributes.normals.len())?;
+
+    Ok((position, tex_coord, normal))
+}
+
+fn resolve_index(token: &str, count: usize, line_number: usize) -> Result<usize, LoadError> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| parse_error(line_number, &format!("invalid index '{}'", token)))?;
+
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(parse_error(
+            line_number,
+            &format!("index {} is out of range", index),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+// values after the first N are ignored, like the w of a position
+fn parse_floats<'a, const N: usize>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<[f32; N], LoadError> {
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        *value = tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| parse_error(line_number, &format!("expected {} numbers", N)))?;
+    }
+    Ok(values)
+}
+
+fn parse_float<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<f32, LoadError> {
+    parse_floats::<1>(tokens, line_number).map(|[value]| value)
+}
+
+fn rest_of_line<'a>(tokens: impl Iterator<Item = &'a str>) -> String {
+    tokens.collect::<Vec<_>>().join(" ")
+}
+
+fn parse_error(line_number: usize, message: &str) -> LoadError {
+    LoadError::Parse(format!("line {}: {}", line_number, message))
+}
+
+// a texture statement, options come before the file name
+struct MtlMap {
+    file: String,
+    // -bm, only meaningful for bump and normal maps
+    bump_multiplier: f32,
+    // -type normal, marks a bump statement as a tangent space normal map
+    normal: bool,
+}
+
+struct MtlMaterial {
+    name: String,
+    diffuse: [f32; 3],
+    dissolve: f32,
+    emissive: Option<[f32; 3]>,
+    shininess: Option<f32>,
+    // PBR extension, Pr Pm Pc Pcr
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    ior: f32,
+    diffuse_map: Option<MtlMap>,
+    emissive_map: Option<MtlMap>,
+    roughness_map: Option<MtlMap>,
+    metallic_map: Option<MtlMap>,
+    normal_map: Option<MtlMap>,
+}
+
+impl MtlMaterial {
+    fn new(name: String) -> MtlMaterial {
+        MtlMaterial {
+            name,
+            diffuse: [1.0; 3],
+            dissolve: 1.0,
+            emissive: None,
+            shininess: None,
+            roughness: None,
+            metallic: None,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            ior: 1.5,
+            diffuse_map: None,
+            emissive_map: None,
+            roughness_map: None,
+            metallic_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+fn parse_mtl(source: &str, warnings: &mut Vec<String>) -> Result<Vec<MtlMaterial>, LoadError> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial::new(rest_of_line(tokens)));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => continue,
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_floats(&mut tokens, line_number)?,
+            "Ke" => material.emissive = Some(parse_floats(&mut tokens, line_number)?),
+            "d" => material.dissolve = parse_float(&mut tokens, line_number)?,
+            "Tr" => material.dissolve = 1.0 - parse_float(&mut tokens, line_number)?,
+            "Ns" => material.shininess = Some(parse_float(&mut tokens, line_number)?),
+            "Ni" => material.ior = parse_float(&mut tokens, line_number)?,
+            "Pr" => material.roughness = Some(parse_float(&mut tokens, line_number)?),
+            "Pm" => material.metallic = Some(parse_float(&mut tokens, line_number)?),
+            "Pc" => material.clearcoat = parse_float(&mut tokens, line_number)?,
+            "Pcr" => material.clearcoat_roughness = parse_float(&mut tokens, line_number)?,
+            "map_Kd" => material.diffuse_map = parse_map(tokens),
+            "map_Ke" => material.emissive_map = parse_map(tokens),
+            "map_Pr" => material.roughness_map = parse_map(tokens),
+            "map_Pm" => material.metallic_map = parse_map(tokens),
+            "norm" => material.normal_map = parse_map(tokens),
+            // bump maps are height maps unless they are flagged as normal maps
+            "map_Bump" | "map_bump" | "bump" => match parse_map(tokens) {
+                Some(map) if map.normal => material.normal_map = Some(map),
+                Some(map) => warnings.push(format!(
+                    "line {}: height map {} is not supported, normal maps need -type normal",
+                    line_number, map.file
+                )),
+                None => {}
+            },
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+// the file name is the last token, paths with spaces are not supported
+fn parse_map<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<MtlMap> {
+    let tokens: Vec<&str> = tokens.collect();
+    let file = tokens.last()?.to_string();
+
+    let option = |name: &str| {
+        tokens
+            .iter()
+            .position(|token| *token == name)
+            .and_then(|index| tokens.get(index + 1))
+    };
+    let bump_multiplier = option("-bm")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(1.0);
+    let normal = option("-type") == Some(&"normal");
+
+    Some(MtlMap {
+        file,
+        bump_multiplier,
+        normal,
+    })
+}
+
+fn load_mtl_material(
+    mtl: &MtlMaterial,
+    directory: &Path,
+    options: &LoadOptions,
+    texture_cache: &texture::TextureCache,
+    warnings: &mut Vec<String>,
+) -> Material {
+    // a map without its factor is used as is
+    let roughness = match (mtl.roughness, mtl.shininess, &mtl.roughness_map) {
+        (Some(roughness), _, _) => roughness,
+        (None, _, Some(_)) => 1.0,
+        // Blinn-Phong exponent to GGX roughness
+        (None, Some(shininess), None) => (2.0 / (shininess.max(0.0) + 2.0)).sqrt(),
+        (None, None, None) => Material::default().roughness,
+    };
+    let metallic = match (mtl.metallic, &mtl.metallic_map) {
+        (Some(metallic), _) => metallic,
+        (None, Some(_)) => 1.0,
+        (None, None) => 0.0,
+    };
+
+    let color = iml::Vec3::new(mtl.diffuse[0], mtl.diffuse[1], mtl.diffuse[2]);
+    let mut material = Material::new(color, roughness, metallic, 1.0);
+
+    material.alpha = mtl.dissolve;
+    if mtl.dissolve < 1.0 {
+        material.alpha_mode = AlphaMode::Blend;
+    }
+
+    let emissive = match (mtl.emissive, &mtl.emissive_map) {
+        (Some(emissive), _) => emissive,
+        (None, Some(_)) => [1.0; 3],
+        (None, None) => [0.0; 3],
+    };
+    material.emissive = iml::Vec3::new(emissive[0], emissive[1], emissive[2]);
+    material.ior = mtl.ior;
+    material.clearcoat.factor = mtl.clearcoat;
+    material.clearcoat.roughness = mtl.clearcoat_roughness;
+
+    let texture_desc = texture::TextureDesc {
+        wrap_s: texture::WrapMode::REPEAT,
+        wrap_t: texture::WrapMode::REPEAT,
+        min_filter: texture::Filter::LINEAR_MIP_LINEAR,
+        mag_filter: texture::Filter::LINEAR,
+        anisotropy: options.anisotropy,
+        color_space: texture::ColorSpace::Linear,
+    };
+    let srgb_desc = texture::TextureDesc {
+        color_space: texture::ColorSpace::Srgb,
+        ..texture_desc
+    };
+
+    let mut load = |map: &Option<MtlMap>, texture_desc: texture::TextureDesc| {
+        let path = directory.join(&map.as_ref()?.file);
+        let fallback = texture_cache.white_texture.clone();
+        load_texture_file(texture_cache, &path, texture_desc, fallback, warnings)
+    };
+
+    material.albedo_map = load(&mtl.diffuse_map, srgb_desc);
+    material.emissive_map = load(&mtl.emissive_map, srgb_desc);
+
+    if let Some(normal_map) = &mtl.normal_map {
+        let path = directory.join(&normal_map.file);
+        let fallback = texture_cache.blue_texture.clone();
+        material.normal_map =
+            load_texture_file(texture_cache, &path, texture_desc, fallback, warnings);
+        material.normal_scale = normal_map.bump_multiplier;
+    }
+
+    if mtl.roughness_map.is_some() || mtl.metallic_map.is_some() {
+        let roughness = mtl
+            .roughness_map
+            .as_ref()
+            .map(|map| directory.join(&map.file));
+        let metallic = mtl
+            .metallic_map
+            .as_ref()
+            .map(|map| directory.join(&map.file));
+
+        let source = texture::TextureSource::MetallicRoughness(roughness.clone(), metallic.clone());
+        let fallback = texture_cache.white_texture.clone();
+        let texture = texture_cache.fetch(source, texture_desc, |texture_desc| {
+            texture::load_metallic_roughness_files(
+                roughness.as_deref(),
+                metallic.as_deref(),
+                texture_desc,
+            )
+            .unwrap_or_else(|error| {
+                warnings.push(format!(
+                    "failed to load roughness and metallic maps: {}",
+                    error
+                ));
+                fallback
+            })
+        });
+        material.metallic_roughness_map = Some(material_texture(texture));
+    }
+
+    material
+}
+
+// the fallback stands in for a file that exists but does not decode
+fn load_texture_file(
+    texture_cache: &texture::TextureCache,
+    path: &Path,
+    texture_desc: texture::TextureDesc,
+    fallback: texture::TexturePointer,
+    warnings: &mut Vec<String>,
+) -> Option<MaterialTexture> {
+    if !path.is_file() {
+        warnings.push(format!("missing texture {}", path.display()));
+        return None;
+    }
+
+    let source = texture::TextureSource::File(path.to_path_buf());
+    let texture = texture_cache.fetch(source, texture_desc, |texture_desc| {
+        texture::load_texture_file(path, texture_desc).unwrap_or_else(|error| {
+            warnings.push(format!(
+                "failed to load texture {}: {}",
+                path.display(),
+                error
+            ));
+            fallback
+        })
+    });
+    Some(material_texture(texture))
+}
+
+fn material_texture(texture: texture::TexturePointer) -> MaterialTexture {
+    MaterialTexture {
+        texture,
+        tex_coord: 0,
+        transform: TextureTransform::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_parse_error<T>(result: Result<T, LoadError>) -> bool {
+        matches!(result, Err(LoadError::Parse(_)))
+    }
+
+    fn triangle_attributes() -> ObjAttributes {
+        ObjAttributes {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            tex_coords: vec![[0.0, 1.0], [1.0, 1.0]],
+            normals: vec![[0.0, 0.0, 1.0]],
+        }
+    }
+
+    #[test]
+    fn corners_are_parsed_in_every_form() {
+        let attributes = triangle_attributes();
+        let corner = |corner: &str| parse_corner(corner, &attributes, 1).unwrap();
+        assert_eq!(corner("3"), (2, None, None));
+        assert_eq!(corner("3/2"), (2, Some(1), None));
+        assert_eq!(corner("3//1"), (2, None, Some(0)));
+        assert_eq!(corner("3/2/1"), (2, Some(1), Some(0)));
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        assert_eq!(resolve_index("-1", 3, 1).unwrap(), 2);
+        assert_eq!(resolve_index("-3", 3, 1).unwrap(), 0);
+
+        let obj =
+            parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 1 1 0\nf -3 -2 -1\n").unwrap();
+        let group = &obj.groups[0];
+        assert_eq!(group.indices, [1, 2, 0, 2, 3, 1]);
+        assert_eq!(
+            group.positions,
+            [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn out_of_range_indices_fail_to_parse() {
+        for token in ["0", "4", "-4", "one"] {
+            assert!(is_parse_error(resolve_index(token, 3, 1)), "{}", token);
+        }
+        let attributes = triangle_attributes();
+        assert!(is_parse_error(parse_corner("1/3", &attributes, 1)));
+        assert!(is_parse_error(parse_corner("1//2", &attributes, 1)));
+
+        match parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n") {
+            Err(LoadError::Parse(message)) => {
+                assert!(message.starts_with("line 4:"), "{}", message)
+            }
+            _ => panic!("the face should not parse"),
+        }
+    }
+
+    #[test]
+    fn faces_need_three_corners() {
+        assert!(is_parse_error(parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n")));
+    }
+
+    #[test]
+    fn quads_are_fanned_into_triangles() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+            vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+            f 1/1/1 2/2/1 3/3/1 4/4/1\n";
+        let obj = parse_obj(source).unwrap();
+        let group = &obj.groups[0];
+        // fanned around the first corner, the winding is kept
+        assert_eq!(group.indices, [1, 2, 0, 2, 3, 0]);
+        assert!(group.has_normals);
+        assert_eq!(group.normals, [0.0, 0.0, 1.0].repeat(4));
+        // v is flipped so the top row of the image is v = 0
+        assert_eq!(group.tex_coords, [0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn corners_without_normals_leave_them_to_be_generated() {
+        let obj = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3\n").unwrap();
+        let primitive = obj.groups.into_iter().next().unwrap().into_primitive();
+        assert!(primitive.normals.is_empty());
+        assert_eq!(primitive.tex_coords, [0.0; 6]);
+    }
+
+    #[test]
+    fn faces_are_grouped_by_material() {
+        let source = "mtllib scene.mtl\n\
+            v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+            f 1 2 3\n\
+            usemtl red\nf 1 2 3\n\
+            usemtl blue\nf 2 3 4\n\
+            usemtl red\nf 1 3 4\n";
+        let obj = parse_obj(source).unwrap();
+        assert_eq!(obj.material_libraries, ["scene.mtl"]);
+
+        let materials: Vec<Option<&str>> = obj
+            .groups
+            .iter()
+            .map(|group| group.material.as_deref())
+            .collect();
+        assert_eq!(materials, [None, Some("red"), Some("blue")]);
+        // every group has its own vertices
+        let red = &obj.groups[1];
+        assert_eq!(red.indices, [1, 2, 0, 2, 3, 0]);
+        assert_eq!(red.positions.len(), 4 * 3);
+        assert_eq!(obj.groups[2].indices, [1, 2, 0]);
+    }
+
+    fn normal_map(source: &str) -> (Option<(String, f32)>, Vec<String>) {
+        let mut warnings = Vec::new();
+        let materials = parse_mtl(source, &mut warnings).unwrap();
+        let normal_map = materials[0]
+            .normal_map
+            .as_ref()
+            .map(|map| (map.file.clone(), map.bump_multiplier));
+        (normal_map, warnings)
+    }
+
+    #[test]
+    fn norm_is_a_normal_map() {
+        let (map, warnings) = normal_map("newmtl a\nnorm -bm 0.5 normal.png\n");
+        assert_eq!(map, Some((String::from("normal.png"), 0.5)));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn bump_maps_are_normal_maps_only_when_flagged() {
+        let (map, warnings) = normal_map("newmtl a\nmap_Bump -type normal normal.png\n");
+        assert_eq!(map, Some((String::from("normal.png"), 1.0)));
+        assert!(warnings.is_empty());
+
+        for keyword in ["map_Bump", "map_bump", "bump"] {
+            let source = format!("newmtl a\nnorm normal.png\n{} -bm 2 height.png\n", keyword);
+            let (map, warnings) = normal_map(&source);
+            assert_eq!(map, Some((String::from("normal.png"), 1.0)));
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].starts_with("line 3:"), "{}", warnings[0]);
+        }
+    }
+
+    #[test]
+    fn missing_textures_are_warnings() {
+        let texture_cache = texture::TextureCache::deferred();
+        let path = Path::new("missing/albedo.png");
+        let mut warnings = Vec::new();
+        let texture = load_texture_file(
+            &texture_cache,
+            path,
+            texture::TextureDesc::default(),
+            texture_cache.white_texture.clone(),
+            &mut warnings,
+        );
+        assert!(texture.is_none());
+        assert_eq!(
+            warnings,
+            vec![format!("missing texture {}", path.display())]
+        );
+    }
+}
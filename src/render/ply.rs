@@ -0,0 +1,468 @@
+// ply.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::path::Path;
+
+use super::{
+    geometry,
+    material::Material,
+    model::{LoadError, LoadOptions, Mesh, Model, ModelPointer, PrimitiveData, VertexStreams},
+    texture,
+};
+
+// scans usually carry vertex colors, there is no color attribute to put them in so
+// they are skipped like every other property the renderer does not use
+pub fn load_ply_model(
+    path: &Path,
+    options: &LoadOptions,
+    _texture_cache: &texture::TextureCache,
+) -> Result<ModelPointer, LoadError> {
+    let bytes = std::fs::read(path)?;
+    let mut primitive = parse_ply(&bytes)?;
+    primitive.generate_missing_attributes(options, 0);
+
+    let mut model = Model::default();
+    let mut mesh = Mesh::default();
+    let mut streams = VertexStreams::default();
+
+    mesh.sub_meshes.push(streams.push(&primitive, 0));
+    model.materials.push(Material::default());
+
+    streams.write(&mut model);
+    model.meshes.push(mesh);
+    Ok(ModelPointer::new(model))
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone)]
+enum Scalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Scalar, LoadError> {
+        match name {
+            "char" | "int8" => Ok(Scalar::Int8),
+            "uchar" | "uint8" => Ok(Scalar::UInt8),
+            "short" | "int16" => Ok(Scalar::Int16),
+            "ushort" | "uint16" => Ok(Scalar::UInt16),
+            "int" | "int32" => Ok(Scalar::Int32),
+            "uint" | "uint32" => Ok(Scalar::UInt32),
+            "float" | "float32" => Ok(Scalar::Float32),
+            "double" | "float64" => Ok(Scalar::Float64),
+            _ => Err(LoadError::Parse(format!("unknown ply type '{}'", name))),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Scalar::Int8 | Scalar::UInt8 => 1,
+            Scalar::Int16 | Scalar::UInt16 => 2,
+            Scalar::Int32 | Scalar::UInt32 | Scalar::Float32 => 4,
+            Scalar::Float64 => 8,
+        }
+    }
+}
+
+enum PropertyType {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+struct Property {
+    name: String,
+    property_type: PropertyType,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+
+struct Header {
+    encoding: Encoding,
+    elements: Vec<Element>,
+    // where the element data starts
+    body: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, LoadError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| LoadError::Parse(String::from("ply header has no end_header")))?;
+
+    // the body starts after the line break, which may be \r\n
+    let mut body = end + END.len();
+    while body < bytes.len() && bytes[body] != b'\n' {
+        body += 1;
+    }
+    body += 1;
+
+    let text = String::from_utf8_lossy(&bytes[..end]);
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(LoadError::Parse(String::from("not a ply file")));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", format, ..] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    _ => return Err(LoadError::Parse(format!("unknown ply format '{}'", format))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| LoadError::Parse(format!("invalid element count '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    property_type: PropertyType::List {
+                        count: Scalar::parse(count)?,
+                        item: Scalar::parse(item)?,
+                    },
+                };
+                add_property(&mut elements, property)?;
+            }
+            ["property", scalar, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    property_type: PropertyType::Scalar(Scalar::parse(scalar)?),
+                };
+                add_property(&mut elements, property)?;
+            }
+            // comments and obj_info
+            _ => {}
+        }
+    }
+
+    Ok(Header {
+        encoding: encoding
+            .ok_or_else(|| LoadError::Parse(String::from("ply header has no format")))?,
+        elements,
+        body,
+    })
+}
+
+fn add_property(elements: &mut [Element], property: Property) -> Result<(), LoadError> {
+    match elements.last_mut() {
+        Some(element) => {
+            element.properties.push(property);
+            Ok(())
+        }
+        None => Err(LoadError::Parse(format!(
+            "property '{}' before any element",
+            property.name
+        ))),
+    }
+}
+
+// reads the values of the body one at a time, whitespace separated in ascii files
+struct BodyReader<'b> {
+    encoding: Encoding,
+    bytes: &'b [u8],
+    position: usize,
+}
+
+impl BodyReader<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, LoadError> {
+        if self.encoding == Encoding::Ascii {
+            return self.read_token();
+        }
+
+        let size = scalar.size();
+        let end = self.position + size;
+        if end > self.bytes.len() {
+            return Err(LoadError::Parse(String::from("ply body is truncated")));
+        }
+
+        let mut raw = [0u8; 8];
+        raw[..size].copy_from_slice(&self.bytes[self.position..end]);
+        if self.encoding == Encoding::BinaryBigEndian {
+            raw[..size].reverse();
+        }
+        self.position = end;
+
+        let value = match scalar {
+            Scalar::Int8 => raw[0] as i8 as f64,
+            Scalar::UInt8 => raw[0] as f64,
+            Scalar::Int16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Scalar::UInt16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Scalar::Int32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Scalar::UInt32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Scalar::Float32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Scalar::Float64 => f64::from_le_bytes(raw),
+        };
+        Ok(value)
+    }
+
+    fn read_token(&mut self) -> Result<f64, LoadError> {
+        while self.position < self.bytes.len() && self.bytes[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+
+        let start = self.position;
+        while self.position < self.bytes.len() && !self.bytes[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+
+        let token = String::from_utf8_lossy(&self.bytes[start..self.position]);
+        token
+            .parse()
+            .map_err(|_| LoadError::Parse(format!("invalid ply value '{}'", token)))
+    }
+}
+
+fn parse_ply(bytes: &[u8]) -> Result<PrimitiveData, LoadError> {
+    let header = parse_header(bytes)?;
+    let mut reader = BodyReader {
+        encoding: header.encoding,
+        bytes: &bytes[header.body..],
+        position: 0,
+    };
+
+    let mut positions: Vec<f32> = Vec::new();
+    let mut normals: Vec<f32> = Vec::new();
+    let mut tex_coords: Vec<f32> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for element in &header.elements {
+        let position = [
+            element.property(&["x"]),
+            element.property(&["y"]),
+            element.property(&["z"]),
+        ];
+        let normal = [
+            element.property(&["nx"]),
+            element.property(&["ny"]),
+            element.property(&["nz"]),
+        ];
+        let tex_coord = [
+            element.property(&["s", "u", "texture_u", "texture_s"]),
+            element.property(&["t", "v", "texture_v", "texture_t"]),
+        ];
+        let face = element.property(&["vertex_indices", "vertex_index"]);
+
+        let mut values: Vec<f64> = vec![0.0; element.properties.len()];
+        let mut list: Vec<u32> = Vec::new();
+
+        for _ in 0..element.count {
+            for (index, property) in element.properties.iter().enumerate() {
+                match property.property_type {
+                    PropertyType::Scalar(scalar) => values[index] = reader.read(scalar)?,
+                    PropertyType::List { count, item } => {
+                        let count = reader.read(count)? as usize;
+                        list.clear();
+                        for _ in 0..count {
+                            list.push(reader.read(item)? as u32);
+                        }
+
+                        if element.name == "face" && face == Some(index) && count >= 3 {
+                            indices.extend(geometry::triangulate(
+                                geometry::Topology::TriangleFan,
+                                &list,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if element.name != "vertex" {
+                continue;
+            }
+
+            let value =
+                |property: Option<usize>| property.map_or(0.0, |index| values[index] as f32);
+            positions.extend(position.iter().map(|property| value(*property)));
+            if normal.iter().all(Option::is_some) {
+                normals.extend(normal.iter().map(|property| value(*property)));
+            }
+            if tex_coord.iter().all(Option::is_some) {
+                // v = 0 is the bottom of the image like obj
+                tex_coords.push(value(tex_coord[0]));
+                tex_coords.push(1.0 - value(tex_coord[1]));
+            }
+        }
+    }
+
+    let vertex_count = positions.len() / 3;
+    if indices.is_empty() {
+        return Err(LoadError::Parse(String::from(
+            "ply has no faces, point clouds are not supported",
+        )));
+    }
+    if let Some(index) = indices
+        .iter()
+        .find(|index| **index as usize >= vertex_count)
+    {
+        return Err(LoadError::Parse(format!(
+            "face index {} is out of range",
+            index
+        )));
+    }
+
+    if tex_coords.is_empty() {
+        tex_coords = vec![0.0; vertex_count * 2];
+    }
+
+    Ok(PrimitiveData {
+        positions,
+        normals,
+        tex_coords1: tex_coords.clone(),
+        tex_coords,
+        tangents: Vec::new(),
+        joints: Vec::new(),
+        weights: Vec::new(),
+        indices,
+        targets: Vec::new(),
+        packed: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+
+    fn binary_square(format: &str, to_bytes: fn(f32) -> [u8; 4], face: &[u8]) -> Vec<u8> {
+        let header = format!(
+            "ply\nformat {} 1.0\ncomment made by hand\n\
+             element vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+             property uchar red\n\
+             element face 1\nproperty list uchar ushort vertex_indices\nend_header\n",
+            format
+        );
+        let mut bytes = header.into_bytes();
+        for position in SQUARE {
+            for value in position {
+                bytes.extend(to_bytes(value));
+            }
+            // the color is read and skipped
+            bytes.push(255);
+        }
+        bytes.extend(face);
+        bytes
+    }
+
+    fn is_parse_error<T>(result: Result<T, LoadError>) -> bool {
+        matches!(result, Err(LoadError::Parse(_)))
+    }
+
+    #[test]
+    fn ascii_bodies() {
+        let source = "ply\r\nformat ascii 1.0\r\n\
+            element vertex 4\r\nproperty float x\r\nproperty float y\r\nproperty float z\r\n\
+            property float nx\r\nproperty float ny\r\nproperty float nz\r\n\
+            property float u\r\nproperty float v\r\n\
+            element face 1\r\nproperty list uchar int vertex_indices\r\nend_header\r\n\
+            0 0 0 0 0 1 0 0\r\n1 0 0 0 0 1 1 0\r\n1 1 0 0 0 1 1 1\r\n0 1 0 0 0 1 0 1\r\n\
+            4 0 1 2 3\r\n";
+        let primitive = parse_ply(source.as_bytes()).unwrap();
+        assert_eq!(primitive.positions, SQUARE.concat());
+        assert_eq!(primitive.normals, [0.0, 0.0, 1.0].repeat(4));
+        // v is flipped like obj
+        assert_eq!(
+            primitive.tex_coords,
+            [0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]
+        );
+        // the quad is fanned into two triangles
+        assert_eq!(primitive.indices, [1, 2, 0, 2, 3, 0]);
+    }
+
+    #[test]
+    fn binary_little_endian_bodies() {
+        let bytes = binary_square(
+            "binary_little_endian",
+            f32::to_le_bytes,
+            &[4, 0, 0, 1, 0, 2, 0, 3, 0],
+        );
+        let primitive = parse_ply(&bytes).unwrap();
+        assert_eq!(primitive.positions, SQUARE.concat());
+        assert!(primitive.normals.is_empty());
+        assert_eq!(primitive.tex_coords, [0.0; 8]);
+        assert_eq!(primitive.indices, [1, 2, 0, 2, 3, 0]);
+    }
+
+    #[test]
+    fn binary_big_endian_bodies() {
+        let mut bytes = binary_square(
+            "binary_big_endian",
+            f32::to_be_bytes,
+            &[4, 0, 0, 0, 1, 0, 2, 0, 3],
+        );
+        let primitive = parse_ply(&bytes).unwrap();
+        assert_eq!(primitive.positions, SQUARE.concat());
+        assert_eq!(primitive.indices, [1, 2, 0, 2, 3, 0]);
+
+        // the last index is cut in half
+        bytes.pop();
+        assert!(is_parse_error(parse_ply(&bytes)));
+    }
+
+    #[test]
+    fn face_indices_out_of_range_fail_to_parse() {
+        let bytes = binary_square(
+            "binary_little_endian",
+            f32::to_le_bytes,
+            &[3, 0, 0, 1, 0, 4, 0],
+        );
+        match parse_ply(&bytes) {
+            Err(LoadError::Parse(message)) => assert_eq!(message, "face index 4 is out of range"),
+            _ => panic!("the face should not parse"),
+        }
+    }
+
+    #[test]
+    fn point_clouds_and_unknown_formats_fail_to_parse() {
+        let points = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n";
+        assert!(is_parse_error(parse_ply(points.as_bytes())));
+        let unknown = "ply\nformat binary_middle_endian 1.0\nend_header\n";
+        assert!(is_parse_error(parse_ply(unknown.as_bytes())));
+        assert!(is_parse_error(parse_ply(b"ply\nformat ascii 1.0\n")));
+    }
+}
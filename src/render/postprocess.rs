@@ -0,0 +1,421 @@
+// postprocess.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{
+    backend::Backend, bloom, framebuffer::FrameBuffer, lut, model, shader, texture, tonemap,
+};
+
+const EFFECT_VERTEX_SHADER: &str = "resources/shaders/fullscreen.vs";
+const COLOR_GRADING_SHADER: &str = "resources/shaders/postprocess/color_grading.fs";
+// every built in effect after color grading, they start disabled
+const BUILT_IN_EFFECTS: [&str; 3] = [
+    "resources/shaders/postprocess/chromatic_aberration.fs",
+    "resources/shaders/postprocess/vignette.fs",
+    "resources/shaders/postprocess/film_grain.fs",
+];
+// the color grading table until a .cube file is loaded
+const IDENTITY_LUT_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum EffectError {
+    Io(PathBuf, io::Error),
+    Shader(PathBuf, String),
+    Lut(PathBuf, lut::CubeError),
+}
+
+impl fmt::Display for EffectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EffectError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            EffectError::Shader(path, error) => {
+                write!(f, "{} failed to compile: {}", path.display(), error)
+            }
+            EffectError::Lut(path, error) => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for EffectError {}
+
+pub enum Pass {
+    Bloom(bloom::Bloom),
+}
+
+pub struct Stage {
+    pub enabled: bool,
+    pub pass: Pass,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match &self.pass {
+            Pass::Bloom(_) => "Bloom",
+        }
+    }
+}
+
+// a float uniform of an effect shader, declared as
+//     uniform float strength; // min max default
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+pub fn parse_parameters(source: &str) -> Vec<Parameter> {
+    let mut parameters = Vec::new();
+    for line in source.lines() {
+        let (declaration, comment) = match line.split_once("//") {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let words: Vec<&str> = declaration.split_whitespace().collect();
+        let name = match words.as_slice() {
+            ["uniform", "float", name] => name.trim_end_matches(';'),
+            ["uniform", "float", name, ";"] => name,
+            _ => continue,
+        };
+        let numbers = comment
+            .split_whitespace()
+            .map(|word| word.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>();
+        if let Ok(numbers) = numbers {
+            if let [min, max, default] = numbers[..] {
+                if !name.is_empty() && min <= max {
+                    parameters.push(Parameter {
+                        name: name.to_string(),
+                        value: default.clamp(min, max),
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+    }
+    parameters
+}
+
+// a fullscreen fragment shader run on the tone mapped image
+pub struct Effect {
+    pub name: String,
+    pub enabled: bool,
+    pub parameters: Vec<Parameter>,
+    pipeline: shader::Pipeline,
+    // the uniform location of each parameter, looked up once
+    parameter_locations: Vec<i32>,
+    lut: Option<lut::Lut3D>,
+}
+
+impl Effect {
+    // named after the file, film_grain.fs is shown as "film grain"
+    pub fn from_file(path: &Path) -> Result<Effect, EffectError> {
+        let source =
+            fs::read_to_string(path).map_err(|error| EffectError::Io(path.to_path_buf(), error))?;
+        let pipeline = shader::Pipeline::new(EFFECT_VERTEX_SHADER, &path.to_string_lossy())
+            .map_err(|error| EffectError::Shader(path.to_path_buf(), error))?;
+        let name = path.file_stem().map_or(String::from("effect"), |stem| {
+            stem.to_string_lossy().replace('_', " ")
+        });
+
+        let parameters = parse_parameters(&source);
+        let parameter_locations = parameters
+            .iter()
+            .map(|parameter| pipeline.uniform_location(&format!("{}\0", parameter.name)))
+            .collect();
+
+        Ok(Effect {
+            name,
+            enabled: false,
+            parameters,
+            pipeline,
+            parameter_locations,
+            lut: None,
+        })
+    }
+
+    fn render(&self, source: &texture::Texture, time: f32, model_cache: &model::ModelCache) {
+        let pipeline = &self.pipeline;
+        unsafe {
+            gl::UseProgram(pipeline.id);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, source.id());
+        }
+        pipeline.set_uniform_1i("source\0", 0);
+        pipeline.set_uniform_vec2(
+            "texelSize\0",
+            &iml::Vec2::new(1.0 / source.width as f32, 1.0 / source.height as f32),
+        );
+        pipeline.set_uniform_1f("time\0", time);
+        for (parameter, location) in self.parameters.iter().zip(&self.parameter_locations) {
+            unsafe {
+                gl::Uniform1f(*location, parameter.value);
+            }
+        }
+
+        if let Some(lut) = &self.lut {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE1);
+                gl::BindTexture(gl::TEXTURE_3D, lut.id);
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+            pipeline.set_uniform_1i("lut\0", 1);
+            pipeline.set_uniform_1f("lutSize\0", lut.size as f32);
+            pipeline.set_uniform_vec3("lutDomainMin\0", &iml::Vec3::from(lut.domain_min));
+            pipeline.set_uniform_vec3("lutDomainMax\0", &iml::Vec3::from(lut.domain_max));
+        }
+
+        draw_quad(model_cache);
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+// effects read the last written target and write the other one
+struct PingPong {
+    targets: [FrameBuffer; 2],
+    written: usize,
+}
+
+impl PingPong {
+    fn new() -> PingPong {
+        let target = || {
+            // sized on the first frame
+            let mut framebuffer = FrameBuffer::new(1, 1);
+            framebuffer.add_color_texture(0, gl::RGBA16F);
+            framebuffer
+        };
+        PingPong {
+            targets: [target(), target()],
+            written: 1,
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        for target in &mut self.targets {
+            target.resize(width, height);
+        }
+    }
+
+    fn next(&self) -> &FrameBuffer {
+        &self.targets[1 - self.written]
+    }
+
+    fn last_written(&self) -> &texture::TexturePointer {
+        self.targets[self.written].color_texture(0).unwrap()
+    }
+
+    fn swap(&mut self) {
+        self.written = 1 - self.written;
+    }
+}
+
+// the linear stages run on the scene target before tone mapping, the effects on the
+// display encoded image after it, each in order
+pub struct Chain {
+    pub stages: Vec<Stage>,
+    pub effects: Vec<Effect>,
+    tone_map_pass: tonemap::ToneMapPass,
+    ping_pong: PingPong,
+}
+
+impl Chain {
+    pub fn new() -> Result<Chain, EffectError> {
+        let bloom = bloom::Bloom::new()
+            .map_err(|error| EffectError::Shader(PathBuf::from("bloom"), error))?;
+        let tone_map_pass = tonemap::ToneMapPass::new()
+            .map_err(|error| EffectError::Shader(PathBuf::from("tonemap"), error))?;
+
+        let mut color_grading = Effect::from_file(Path::new(COLOR_GRADING_SHADER))?;
+        color_grading.lut = Some(lut::Lut3D::new(&lut::Cube::identity(IDENTITY_LUT_SIZE)));
+        let mut effects = vec![color_grading];
+        for path in BUILT_IN_EFFECTS {
+            effects.push(Effect::from_file(Path::new(path))?);
+        }
+
+        Ok(Chain {
+            stages: vec![Stage {
+                enabled: true,
+                pass: Pass::Bloom(bloom),
+            }],
+            effects,
+            tone_map_pass,
+            ping_pong: PingPong::new(),
+        })
+    }
+
+    // user effects are appended enabled
+    pub fn add_effect(&mut self, path: &Path) -> Result<(), EffectError> {
+        let mut effect = Effect::from_file(path)?;
+        effect.enabled = true;
+        self.effects.push(effect);
+        Ok(())
+    }
+
+    // replaces the table of the color grading effect and enables it
+    pub fn set_lut(&mut self, path: &Path) -> Result<(), EffectError> {
+        let cube =
+            lut::Cube::load(path).map_err(|error| EffectError::Lut(path.to_path_buf(), error))?;
+        if let Some(effect) = self.effects.iter_mut().find(|effect| effect.lut.is_some()) {
+            effect.lut = Some(lut::Lut3D::new(&cube));
+            effect.enabled = true;
+        }
+        Ok(())
+    }
+
+    pub fn render_hdr(&mut self, scene_target: &FrameBuffer, model_cache: &model::ModelCache) {
+        for stage in self.stages.iter_mut().filter(|stage| stage.enabled) {
+            match &mut stage.pass {
+                Pass::Bloom(bloom) => bloom.render(scene_target, model_cache),
+            }
+        }
+    }
+
+    // tone maps the scene and runs the effects, the last pass writes to output or the
+    // window when there is none
+    pub fn render_display(
+        &mut self,
+        scene_color: &texture::Texture,
+        tone_map: &tonemap::ToneMapSettings,
+        output: Option<&FrameBuffer>,
+        time: f32,
+        model_cache: &model::ModelCache,
+    ) {
+        let (width, height) = (scene_color.width, scene_color.height);
+        let effects: Vec<&Effect> = self
+            .effects
+            .iter()
+            .filter(|effect| effect.enabled)
+            .collect();
+        if effects.is_empty() {
+            bind_output(output, width, height);
+            self.tone_map_pass
+                .render(scene_color, tone_map, model_cache);
+            return;
+        }
+
+        self.ping_pong.resize(width, height);
+        self.ping_pong.next().bind();
+        self.tone_map_pass
+            .render(scene_color, tone_map, model_cache);
+        self.ping_pong.swap();
+
+        for (index, effect) in effects.iter().enumerate() {
+            let last = index + 1 == effects.len();
+            if last {
+                bind_output(output, width, height);
+            } else {
+                self.ping_pong.next().bind();
+            }
+            effect.render(self.ping_pong.last_written(), time, model_cache);
+            if !last {
+                self.ping_pong.swap();
+            }
+        }
+    }
+}
+
+fn bind_output(output: Option<&FrameBuffer>, width: u32, height: u32) {
+    match output {
+        Some(framebuffer) => framebuffer.bind(),
+        None => {
+            FrameBuffer::unbind();
+            unsafe {
+                gl::Viewport(0, 0, width as i32, height as i32);
+            }
+        }
+    }
+}
+
+// a quad covering the viewport, the pipeline and its inputs have to be bound
+pub fn draw_quad(model_cache: &model::ModelCache) {
+    let mut quad_model = model_cache.shape(&model::Shape::Quad).borrow_mut();
+    Backend::set_vertex_buffer(&mut quad_model.vertex_buffer);
+    Backend::set_attributes(&quad_model.attributes);
+    Backend::set_index_buffer(&mut quad_model.index_buffer);
+
+    let sub_mesh = &quad_model.meshes[0].sub_meshes[0];
+    let start_index = sub_mesh.start_index * std::mem::size_of::<u32>();
+    unsafe {
+        gl::DrawElements(
+            gl::TRIANGLES,
+            sub_mesh.num_indices as i32,
+            gl::UNSIGNED_INT,
+            start_index as *const _,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameter(name: &str, value: f32, min: f32, max: f32) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            value,
+            min,
+            max,
+        }
+    }
+
+    #[test]
+    fn uniforms_with_a_range_comment_are_parameters() {
+        let source = "
+            uniform sampler2D source;
+            uniform float strength; // 0.0 2.0 0.5
+            uniform float radius ; // -1 1 0.25
+            uniform float time;
+            uniform float speed; // how fast the grain moves
+            uniform vec3 tint; // 0 1 1
+        ";
+        assert_eq!(
+            parse_parameters(source),
+            [
+                parameter("strength", 0.5, 0.0, 2.0),
+                parameter("radius", 0.25, -1.0, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_need_three_numbers_in_order() {
+        let source = "
+            uniform float reversed; // 1 0 0.5
+            uniform float short; // 0 1
+            uniform float long; // 0 1 0.5 2
+        ";
+        assert!(parse_parameters(source).is_empty());
+    }
+
+    #[test]
+    fn defaults_are_clamped_to_the_range() {
+        let source = "
+            uniform float high; // 0 1 4
+            uniform float low; // 0.5 1 -2
+        ";
+        assert_eq!(
+            parse_parameters(source),
+            [
+                parameter("high", 1.0, 0.0, 1.0),
+                parameter("low", 0.5, 0.5, 1.0),
+            ]
+        );
+    }
+}
@@ -27,6 +27,11 @@ impl Drop for Pipeline {
 }
 
 impl Pipeline {
+    // -1 when the uniform is not used by the program, setting it is then a no op
+    pub fn uniform_location(&self, name: &str) -> i32 {
+        unsafe { gl::GetUniformLocation(self.id, name.as_ptr() as *const _) }
+    }
+
     pub fn set_uniform_mat4(&self, name: &str, matrix: &iml::Mat4) {
         unsafe {
             gl::UniformMatrix4fv(
@@ -86,8 +91,9 @@ impl Pipeline {
         }
     }
 }
-fn parse_shader_file(shader_file: &str) -> String {
-    let file = fs::read_to_string(shader_file).unwrap();
+fn parse_shader_file(shader_file: &str) -> Result<String, String> {
+    let file = fs::read_to_string(shader_file)
+        .map_err(|error| format!("failed to read {}: {}", shader_file, error))?;
 
     let mut result: String = String::new();
     for line in file.lines() {
@@ -95,14 +101,14 @@ fn parse_shader_file(shader_file: &str) -> String {
         if buf.contains("#include") {
             buf.replace_range(0..8, SHADER_BASE_PATH);
             buf.retain(|c| !c.is_whitespace());
-            let include_source = parse_shader_file(&buf);
+            let include_source = parse_shader_file(&buf)?;
             result.push_str(&include_source);
         } else {
             result.push_str(&buf);
             result.push_str("\n");
         }
     }
-    result
+    Ok(result)
 }
 
 fn compile_shader(shader_type: gl::types::GLenum, source: &String) -> Result<u32, String> {
@@ -170,14 +176,11 @@ fn build_program(shaders: Vec<u32>) -> Result<u32, String> {
 }
 
 impl Pipeline {
-    pub fn new(
-        vertex_shader: &'static str,
-        fragment_shader: &'static str,
-    ) -> Result<Pipeline, String> {
-        let vs_source: String = parse_shader_file(vertex_shader);
+    pub fn new(vertex_shader: &str, fragment_shader: &str) -> Result<Pipeline, String> {
+        let vs_source: String = parse_shader_file(vertex_shader)?;
         let vs_shader = compile_shader(gl::VERTEX_SHADER, &vs_source)?;
 
-        let fs_source: String = parse_shader_file(fragment_shader);
+        let fs_source: String = parse_shader_file(fragment_shader)?;
         let fs_shader = compile_shader(gl::FRAGMENT_SHADER, &fs_source)?;
 
         let program_result = build_program(vec![vs_shader, fs_shader]);
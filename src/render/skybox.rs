@@ -5,7 +5,11 @@
 //
 // Distributed under the MIT Lisense
 // https://mit-license.org/
-use super::{backend::*, model, shader, stream, texture};
+use super::{
+    backend::*,
+    framebuffer::{DepthFormat, FrameBuffer, Target},
+    model, shader, stream, texture,
+};
 use crate::iml;
 
 fn get_capture_views() -> Vec<iml::Mat4> {
@@ -51,19 +55,24 @@ pub struct Skybox {
 }
 
 impl Skybox {
-    pub fn new(image_path: &'static str, model_cache: &mut model::ModelCache) -> Skybox {
-        let data = texture::load_hdr_texture(image_path);
-
+    // pixels are the decoded RGB float image from texture::load_hdr_texture, the maps
+    // are rendered here so this has to run on the main thread
+    pub fn from_hdr(
+        pixels: &Vec<u8>,
+        width: u32,
+        height: u32,
+        model_cache: &mut model::ModelCache,
+    ) -> Skybox {
         let texture_format = stream::Format::new(
             stream::Dimension::VEC3,
             stream::Type::FLOAT,
             stream::Usage::RGB,
         );
         let hdr_texture = texture::Texture::new(
-            &data.0,
+            pixels,
             texture::TextureDesc::default(),
-            data.1,
-            data.2,
+            width,
+            height,
             texture_format,
             texture::Type::Tex2D,
         );
@@ -79,21 +88,80 @@ impl Skybox {
             brdf: brdf_texture,
         }
     }
+
+    // black environment maps, used while the environment is still loading
+    pub fn placeholder(model_cache: &mut model::ModelCache) -> Skybox {
+        Skybox {
+            skybox: generate_black_cube_texture(),
+            irradiance: generate_black_cube_texture(),
+            prefilter: generate_black_cube_texture(),
+            brdf: generte_brdf_texture(model_cache),
+        }
+    }
+}
+
+fn generate_black_cube_texture() -> texture::TexturePointer {
+    let black: [f32; 3] = [0.0, 0.0, 0.0];
+    let mut cube_id: u32 = 0;
+    unsafe {
+        gl::GenTextures(1, &mut cube_id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, cube_id);
+        for index in 0..6 {
+            gl::TexImage2D(
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + index,
+                0,
+                gl::RGB32F as i32,
+                1,
+                1,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                black.as_ptr().cast(),
+            );
+        }
+        gl::TexParameteri(
+            gl::TEXTURE_CUBE_MAP,
+            gl::TEXTURE_MIN_FILTER,
+            gl::LINEAR as i32,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_CUBE_MAP,
+            gl::TEXTURE_MAG_FILTER,
+            gl::LINEAR as i32,
+        );
+    }
+
+    texture::Texture::from_id(
+        cube_id,
+        texture::TextureDesc::default(),
+        1,
+        1,
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        ),
+        texture::Type::TexCUBE,
+    )
 }
 
 static SKYBOX_RESOLUTION: i32 = 1080;
+static IRRADIANCE_RESOLUTION: i32 = 32;
+static PREFILTER_RESOLUTION: i32 = 128;
+static BRDF_RESOLUTION: i32 = 512;
+
+fn check_framebuffer(framebuffer: &FrameBuffer, name: &str) {
+    if let Err(error) = framebuffer.check() {
+        println!("{} framebuffer is incomplete: {}", name, error);
+    }
+}
+
 fn generate_skybox_texture(
     hdr_texture: &texture::Texture,
     model_cache: &mut model::ModelCache,
 ) -> texture::TexturePointer {
     let mut skybox_id: u32 = 0;
     unsafe {
-        let mut frame_buffer: u32 = 0;
-
-        gl::GenFramebuffers(1, &mut frame_buffer);
-        gl::BindFramebuffer(gl::FRAMEBUFFER, frame_buffer);
-        gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, 0);
-
         gl::GenTextures(1, &mut skybox_id);
         gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_id);
 
@@ -137,7 +205,27 @@ fn generate_skybox_texture(
                 gl::LINEAR as i32,
             );
         }
+    }
+
+    let skybox_texture = texture::Texture::from_id(
+        skybox_id,
+        texture::TextureDesc::default(),
+        SKYBOX_RESOLUTION as u32,
+        SKYBOX_RESOLUTION as u32,
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        ),
+        texture::Type::TexCUBE,
+    );
 
+    // nothing is depth tested, the cube is only seen from the inside
+    let mut framebuffer = FrameBuffer::new(SKYBOX_RESOLUTION as u32, SKYBOX_RESOLUTION as u32);
+    framebuffer.attach_color(0, &skybox_texture, Target::CubeFace(0), 0);
+    check_framebuffer(&framebuffer, "skybox");
+
+    unsafe {
         let angle: f32 = 90.0;
         let capture_projection: iml::Mat4 =
             iml::shared::perspective(angle.to_radians(), 1.0, 0.1, 10.0);
@@ -155,22 +243,14 @@ fn generate_skybox_texture(
         pipeline.set_uniform_mat4("projection\0", &capture_projection);
         pipeline.set_uniform_1i("hdrTexture\0", 0);
         gl::ActiveTexture(gl::TEXTURE0);
-        gl::BindTexture(gl::TEXTURE_2D, hdr_texture.id);
+        gl::BindTexture(gl::TEXTURE_2D, hdr_texture.id());
 
-        gl::Viewport(0, 0, SKYBOX_RESOLUTION, SKYBOX_RESOLUTION);
-        gl::BindFramebuffer(gl::FRAMEBUFFER, frame_buffer);
+        framebuffer.bind();
 
         for index in 0..6 {
             pipeline.set_uniform_mat4("view\0", &capture_views[index]);
 
-            let texture_target = gl::TEXTURE_CUBE_MAP_POSITIVE_X + index as u32;
-            gl::FramebufferTexture2D(
-                gl::FRAMEBUFFER,
-                gl::COLOR_ATTACHMENT0,
-                texture_target,
-                skybox_id,
-                0,
-            );
+            framebuffer.attach_color(0, &skybox_texture, Target::CubeFace(index as u32), 0);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
             let model = &mut model_cache.shape(&model::Shape::Cube).borrow_mut();
@@ -189,25 +269,10 @@ fn generate_skybox_texture(
                 start_index as *const _,
             );
         }
-
-        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-        gl::DeleteFramebuffers(1, &frame_buffer);
     }
+    FrameBuffer::unbind();
 
-    let skybox_texture = texture::Texture {
-        id: skybox_id,
-        format: stream::Format::new(
-            stream::Dimension::VEC3,
-            stream::Type::FLOAT,
-            stream::Usage::RGB,
-        ),
-        width: SKYBOX_RESOLUTION as u32,
-        height: SKYBOX_RESOLUTION as u32,
-        texture_desc: texture::TextureDesc::default(),
-        _type: texture::Type::TexCUBE,
-    };
-
-    Box::new(skybox_texture)
+    skybox_texture
 }
 
 fn generate_irradiance_map(
@@ -217,16 +282,7 @@ fn generate_irradiance_map(
     let capture_views = get_capture_views();
     let mut irradiance_id: u32 = 0;
     unsafe {
-        let mut capture_rbo: u32 = 0;
-        let mut capture_fbo: u32 = 0;
-
-        gl::GenRenderbuffers(1, &mut capture_rbo);
-        gl::GenFramebuffers(1, &mut capture_fbo);
-        let angle: f32 = 90.0;
-        let capture_projection: iml::Mat4 =
-            iml::shared::perspective(angle.to_radians(), 1.0, 0.1, 10.0);
-
-        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id());
         gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
 
         gl::GenTextures(1, &mut irradiance_id);
@@ -237,8 +293,8 @@ fn generate_irradiance_map(
                 texture_target,
                 0,
                 gl::RGB16F as i32,
-                32,
-                32,
+                IRRADIANCE_RESOLUTION,
+                IRRADIANCE_RESOLUTION,
                 0,
                 gl::RGB,
                 gl::FLOAT,
@@ -271,10 +327,31 @@ fn generate_irradiance_map(
             gl::TEXTURE_MAG_FILTER,
             gl::LINEAR as i32,
         );
+    }
 
-        gl::BindFramebuffer(gl::FRAMEBUFFER, capture_fbo);
-        gl::BindRenderbuffer(gl::RENDERBUFFER, capture_rbo);
-        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, 32, 32);
+    let irradiance_texture = texture::Texture::from_id(
+        irradiance_id,
+        texture::TextureDesc::default(),
+        IRRADIANCE_RESOLUTION as u32,
+        IRRADIANCE_RESOLUTION as u32,
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        ),
+        texture::Type::TexCUBE,
+    );
+
+    let mut framebuffer =
+        FrameBuffer::new(IRRADIANCE_RESOLUTION as u32, IRRADIANCE_RESOLUTION as u32);
+    framebuffer.add_depth_renderbuffer(DepthFormat::Depth24);
+    framebuffer.attach_color(0, &irradiance_texture, Target::CubeFace(0), 0);
+    check_framebuffer(&framebuffer, "irradiance");
+
+    unsafe {
+        let angle: f32 = 90.0;
+        let capture_projection: iml::Mat4 =
+            iml::shared::perspective(angle.to_radians(), 1.0, 0.1, 10.0);
 
         let irrandiance_pipeline = shader::Pipeline::new(
             "resources/shaders/skybox.vs",
@@ -288,10 +365,9 @@ fn generate_irradiance_map(
         irrandiance_pipeline.set_uniform_mat4("projection\0", &capture_projection);
 
         gl::ActiveTexture(gl::TEXTURE0);
-        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id());
 
-        gl::Viewport(0, 0, 32, 32);
-        gl::BindFramebuffer(gl::FRAMEBUFFER, capture_fbo);
+        framebuffer.bind();
 
         let cube_model = &mut model_cache.shape(&model::Shape::Cube).borrow_mut();
 
@@ -303,15 +379,7 @@ fn generate_irradiance_map(
         let sub_mesh: &model::SubMesh = &mesh.sub_meshes[0];
         for index in 0..6 {
             irrandiance_pipeline.set_uniform_mat4("view\0", &capture_views[index]);
-            let texture_target = gl::TEXTURE_CUBE_MAP_POSITIVE_X + index as u32;
-
-            gl::FramebufferTexture2D(
-                gl::FRAMEBUFFER,
-                gl::COLOR_ATTACHMENT0,
-                texture_target,
-                irradiance_id,
-                0,
-            );
+            framebuffer.attach_color(0, &irradiance_texture, Target::CubeFace(index as u32), 0);
 
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             let start_index = sub_mesh.start_index * std::mem::size_of::<u32>();
@@ -322,26 +390,10 @@ fn generate_irradiance_map(
                 start_index as *const _,
             );
         }
-
-        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-        gl::DeleteFramebuffers(1, &capture_fbo);
-        gl::DeleteRenderbuffers(1, &capture_rbo);
     }
+    FrameBuffer::unbind();
 
-    let irradiance_texture = texture::Texture {
-        id: irradiance_id,
-        format: stream::Format::new(
-            stream::Dimension::VEC3,
-            stream::Type::FLOAT,
-            stream::Usage::RGB,
-        ),
-        width: SKYBOX_RESOLUTION as u32,
-        height: SKYBOX_RESOLUTION as u32,
-        texture_desc: texture::TextureDesc::default(),
-        _type: texture::Type::TexCUBE,
-    };
-
-    Box::new(irradiance_texture)
+    irradiance_texture
 }
 
 fn generate_prefilter_texture(
@@ -351,12 +403,6 @@ fn generate_prefilter_texture(
     let mut prefilter_id = 0;
     let capture_views = get_capture_views();
     unsafe {
-        let mut capture_rbo: u32 = 0;
-        let mut capture_fbo: u32 = 0;
-
-        gl::GenRenderbuffers(1, &mut capture_rbo);
-        gl::GenFramebuffers(1, &mut capture_fbo);
-
         gl::GenTextures(1, &mut prefilter_id);
         gl::BindTexture(gl::TEXTURE_CUBE_MAP, prefilter_id);
 
@@ -366,8 +412,8 @@ fn generate_prefilter_texture(
                 texture_target,
                 0,
                 gl::RGB16F as i32,
-                128,
-                128,
+                PREFILTER_RESOLUTION,
+                PREFILTER_RESOLUTION,
                 0,
                 gl::RGB,
                 gl::FLOAT,
@@ -402,7 +448,28 @@ fn generate_prefilter_texture(
         );
 
         gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+    }
+
+    let prefilter_texture = texture::Texture::from_id(
+        prefilter_id,
+        texture::TextureDesc::default(),
+        PREFILTER_RESOLUTION as u32,
+        PREFILTER_RESOLUTION as u32,
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        ),
+        texture::Type::TexCUBE,
+    );
 
+    let mut framebuffer =
+        FrameBuffer::new(PREFILTER_RESOLUTION as u32, PREFILTER_RESOLUTION as u32);
+    framebuffer.add_depth_renderbuffer(DepthFormat::Depth24);
+    framebuffer.attach_color(0, &prefilter_texture, Target::CubeFace(0), 0);
+    check_framebuffer(&framebuffer, "prefilter");
+
+    unsafe {
         let prefiler_pipeline = shader::Pipeline::new(
             "resources/shaders/skybox.vs",
             "resources/shaders/prefilterMap.fs",
@@ -416,8 +483,7 @@ fn generate_prefilter_texture(
         prefiler_pipeline.set_uniform_mat4("projection\0", &capture_projection);
 
         gl::ActiveTexture(gl::TEXTURE0);
-        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id);
-        gl::BindFramebuffer(gl::FRAMEBUFFER, capture_fbo);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox_texture.id());
 
         let max_mip_levels = 5;
 
@@ -430,30 +496,22 @@ fn generate_prefilter_texture(
         let sub_mesh = &mesh.sub_meshes[0];
         for mip in 0..max_mip_levels {
             let pow = 0.5f64.powf(mip as f64);
-            let mip_width: u32 = (128.0 * pow) as u32;
-            let mip_height: u32 = (128.0 * pow) as u32;
-
-            gl::BindRenderbuffer(gl::RENDERBUFFER, capture_rbo);
-            gl::RenderbufferStorage(
-                gl::RENDERBUFFER,
-                gl::DEPTH_COMPONENT24,
-                mip_width as i32,
-                mip_height as i32,
-            );
+            let mip_width: u32 = (PREFILTER_RESOLUTION as f64 * pow) as u32;
+            let mip_height: u32 = (PREFILTER_RESOLUTION as f64 * pow) as u32;
 
-            gl::Viewport(0, 0, mip_width as i32, mip_height as i32);
+            // the depth renderbuffer follows the size of the mip level
+            framebuffer.resize(mip_width, mip_height);
+            framebuffer.bind();
 
             let roughness = mip as f32 / (max_mip_levels - 1) as f32;
             prefiler_pipeline.set_uniform_1f("roughness\0", roughness);
 
             for index in 0..6 {
                 prefiler_pipeline.set_uniform_mat4("view\0", &capture_views[index]);
-                let texture_target = gl::TEXTURE_CUBE_MAP_POSITIVE_X + index as u32;
-                gl::FramebufferTexture2D(
-                    gl::FRAMEBUFFER,
-                    gl::COLOR_ATTACHMENT0,
-                    texture_target,
-                    prefilter_id,
+                framebuffer.attach_color(
+                    0,
+                    &prefilter_texture,
+                    Target::CubeFace(index as u32),
                     mip,
                 );
 
@@ -468,37 +526,15 @@ fn generate_prefilter_texture(
                 );
             }
         }
-
-        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-        gl::DeleteFramebuffers(1, &capture_fbo);
-        gl::DeleteRenderbuffers(1, &capture_rbo);
     }
+    FrameBuffer::unbind();
 
-    let prefilter_texture = texture::Texture {
-        id: prefilter_id,
-        format: stream::Format::new(
-            stream::Dimension::VEC3,
-            stream::Type::FLOAT,
-            stream::Usage::RGB,
-        ),
-        width: SKYBOX_RESOLUTION as u32,
-        height: SKYBOX_RESOLUTION as u32,
-        texture_desc: texture::TextureDesc::default(),
-        _type: texture::Type::TexCUBE,
-    };
-
-    Box::new(prefilter_texture)
+    prefilter_texture
 }
 
 fn generte_brdf_texture(model_cache: &mut model::ModelCache) -> texture::TexturePointer {
     let mut brdf_id: u32 = 0;
     unsafe {
-        let mut capture_rbo: u32 = 0;
-        let mut capture_fbo: u32 = 0;
-
-        gl::GenRenderbuffers(1, &mut capture_rbo);
-        gl::GenFramebuffers(1, &mut capture_fbo);
-
         gl::GenTextures(1, &mut brdf_id);
         gl::BindTexture(gl::TEXTURE_2D, brdf_id);
 
@@ -506,8 +542,8 @@ fn generte_brdf_texture(model_cache: &mut model::ModelCache) -> texture::Texture
             gl::TEXTURE_2D,
             0,
             gl::RGB16F as i32,
-            1080,
-            1080,
+            BRDF_RESOLUTION,
+            BRDF_RESOLUTION,
             0,
             gl::RG,
             gl::FLOAT,
@@ -518,19 +554,28 @@ fn generte_brdf_texture(model_cache: &mut model::ModelCache) -> texture::Texture
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    }
 
-        gl::BindFramebuffer(gl::FRAMEBUFFER, capture_fbo);
-        gl::BindRenderbuffer(gl::RENDERBUFFER, capture_rbo);
+    let brdf_texture = texture::Texture::from_id(
+        brdf_id,
+        texture::TextureDesc::default(),
+        BRDF_RESOLUTION as u32,
+        BRDF_RESOLUTION as u32,
+        stream::Format::new(
+            stream::Dimension::VEC2,
+            stream::Type::FLOAT,
+            stream::Usage::RG,
+        ),
+        texture::Type::Tex2D,
+    );
 
-        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, 512, 512);
-        gl::FramebufferTexture2D(
-            gl::FRAMEBUFFER,
-            gl::COLOR_ATTACHMENT0,
-            gl::TEXTURE_2D,
-            brdf_id,
-            0,
-        );
-        gl::Viewport(0, 0, 1080, 1080);
+    let mut framebuffer = FrameBuffer::new(BRDF_RESOLUTION as u32, BRDF_RESOLUTION as u32);
+    framebuffer.add_depth_renderbuffer(DepthFormat::Depth24);
+    framebuffer.attach_color(0, &brdf_texture, Target::Texture2D, 0);
+    check_framebuffer(&framebuffer, "brdf");
+
+    unsafe {
+        framebuffer.bind();
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
         let brdf_pipeline =
@@ -559,24 +604,8 @@ fn generte_brdf_texture(model_cache: &mut model::ModelCache) -> texture::Texture
             gl::UNSIGNED_INT,
             start_index as *const _,
         );
-
-        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-        gl::DeleteFramebuffers(1, &capture_fbo);
-        gl::DeleteRenderbuffers(1, &capture_rbo);
     }
+    FrameBuffer::unbind();
 
-    let brdf_texture = texture::Texture {
-        id: brdf_id,
-        format: stream::Format::new(
-            stream::Dimension::VEC3,
-            stream::Type::FLOAT,
-            stream::Usage::RGB,
-        ),
-        width: SKYBOX_RESOLUTION as u32,
-        height: SKYBOX_RESOLUTION as u32,
-        texture_desc: texture::TextureDesc::default(),
-        _type: texture::Type::TexCUBE,
-    };
-
-    Box::new(brdf_texture)
+    brdf_texture
 }
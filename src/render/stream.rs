@@ -6,10 +6,10 @@
 // Distributed under the MIT Lisense
 // https://mit-license.org/
 
-const TYPE_SIZE: [usize; Type::NUM as usize] = [4, 2, 4, 1, 4, 2, 1];
+const TYPE_SIZE: [usize; Type::NUM as usize] = [4, 1, 2, 4, 1, 2, 4];
 const DIMENSION_SIZE: [usize; Dimension::NUM as usize] = [1, 2, 3, 4];
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq)]
 pub enum Type {
     FLOAT = 0,
     INT8,
@@ -21,7 +21,7 @@ pub enum Type {
     NUM,
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq)]
 pub enum Usage {
     DATA = 0,
     RED,
@@ -30,7 +30,7 @@ pub enum Usage {
     RGBA,
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq)]
 pub enum Dimension {
     SCALAR = 0,
     VEC2,
@@ -39,7 +39,7 @@ pub enum Dimension {
     NUM,
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq)]
 pub struct Format {
     pub dimension: Dimension,
     pub _type: Type,
@@ -69,24 +69,347 @@ impl Format {
 }
 
 #[repr(u8)]
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq)]
 pub enum Slot {
     Position = 0,
     Normal,
     TexCoord,
+    Tangent,
+    Joints,
+    Weights,
+    TexCoord1,
+    NUM,
+}
+
+// how the shader sees the components of an attribute
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub enum Interpretation {
+    // converted to float as is, 255u8 becomes 255.0
+    Float,
+    // unsigned types map to [0, 1] and signed ones to [-1, 1], for colors and quantized data
+    Normalized,
+    // kept as integers, the shader input has to be an int or uint vector
+    Integer,
 }
 
 #[derive(Copy, Debug, Clone)]
 pub struct Attribute {
     pub format: Format,
     pub slot: Slot,
+    // in bytes from the start of the vertex buffer
     pub offset: usize,
+    // bytes from one element to the next, 0 when the elements are tightly packed
+    pub stride: usize,
+    pub interpretation: Interpretation,
 }
 
 impl Attribute {
-    pub fn get_total_offset(self) -> usize {
-        return self.offset * self.format.type_size();
+    // a tightly packed array of one attribute, like the planar buffers of the loaders
+    pub fn new(format: Format, slot: Slot, offset: usize) -> Attribute {
+        Attribute {
+            format,
+            slot,
+            offset,
+            stride: 0,
+            interpretation: Interpretation::Float,
+        }
+    }
+
+    pub fn normalized(self) -> Attribute {
+        Attribute {
+            interpretation: Interpretation::Normalized,
+            ..self
+        }
+    }
+
+    pub fn integer(self) -> Attribute {
+        Attribute {
+            interpretation: Interpretation::Integer,
+            ..self
+        }
+    }
+
+    pub fn element_stride(&self) -> usize {
+        match self.stride {
+            0 => self.format.stride(),
+            stride => stride,
+        }
+    }
+
+    pub fn element_offset(&self, index: usize) -> usize {
+        self.offset + index * self.element_stride()
+    }
+
+    // number of whole elements the buffer holds for this attribute
+    pub fn element_count(&self, buffer_size: usize) -> usize {
+        let element_size = self.format.stride();
+        if buffer_size < self.offset + element_size {
+            return 0;
+        }
+        (buffer_size - self.offset - element_size) / self.element_stride() + 1
     }
 }
 
 pub type Attributes = Vec<Attribute>;
+
+// places the attributes one after the other in a single vertex, each aligned to four
+// bytes like GL recommends. returns the attributes and the size of a vertex.
+pub fn interleaved_layout(elements: &[(Slot, Format, Interpretation)]) -> (Attributes, usize) {
+    let mut offset = 0;
+    let mut attributes: Attributes = Vec::with_capacity(elements.len());
+    for (slot, format, interpretation) in elements {
+        attributes.push(Attribute {
+            format: *format,
+            slot: *slot,
+            offset,
+            stride: 0,
+            interpretation: *interpretation,
+        });
+        offset = align(offset + format.stride(), 4);
+    }
+
+    let vertex_size = offset;
+    for attribute in &mut attributes {
+        attribute.stride = vertex_size;
+    }
+    (attributes, vertex_size)
+}
+
+// the planar buffers hold every attribute for every vertex, interleaved ones hold whole
+// vertices, in both cases the attribute with the fewest elements bounds the vertex count
+pub fn vertex_count(attributes: &Attributes, buffer_size: usize) -> usize {
+    attributes
+        .iter()
+        .map(|attribute| attribute.element_count(buffer_size))
+        .min()
+        .unwrap_or(0)
+}
+
+// decodes one element to floats the way GL would feed a float shader input
+pub fn read_element(data: &[u8], attribute: &Attribute, index: usize) -> [f32; 4] {
+    let format = &attribute.format;
+    let type_size = format.type_size();
+    let start = attribute.element_offset(index);
+    let normalized = attribute.interpretation == Interpretation::Normalized;
+
+    let mut element = [0.0; 4];
+    for (component, value) in element.iter_mut().enumerate().take(format.dimension_size()) {
+        let bytes = &data[start + component * type_size..start + (component + 1) * type_size];
+        *value = match format._type {
+            Type::FLOAT => f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Type::INT8 => normalize_signed(bytes[0] as i8 as f32, i8::MAX as f32, normalized),
+            Type::INT16 => normalize_signed(
+                i16::from_ne_bytes([bytes[0], bytes[1]]) as f32,
+                i16::MAX as f32,
+                normalized,
+            ),
+            Type::INT32 => normalize_signed(
+                i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+                i32::MAX as f32,
+                normalized,
+            ),
+            Type::UINT8 => normalize_unsigned(bytes[0] as f32, u8::MAX as f32, normalized),
+            Type::UINT16 => normalize_unsigned(
+                u16::from_ne_bytes([bytes[0], bytes[1]]) as f32,
+                u16::MAX as f32,
+                normalized,
+            ),
+            Type::UINT32 | Type::NUM => normalize_unsigned(
+                u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+                u32::MAX as f32,
+                normalized,
+            ),
+        };
+    }
+    element
+}
+
+// the first count elements of an attribute as tightly packed floats
+pub fn read_floats(data: &[u8], attribute: &Attribute, count: usize) -> Vec<f32> {
+    let dimension = attribute.format.dimension_size();
+    (0..count)
+        .flat_map(|index| {
+            let element = read_element(data, attribute, index);
+            element.into_iter().take(dimension)
+        })
+        .collect()
+}
+
+fn normalize_signed(value: f32, max: f32, normalized: bool) -> f32 {
+    // GL 4.2 rule, the most negative value clamps to -1
+    if normalized {
+        (value / max).max(-1.0)
+    } else {
+        value
+    }
+}
+
+fn normalize_unsigned(value: f32, max: f32, normalized: bool) -> f32 {
+    if normalized {
+        value / max
+    } else {
+        value
+    }
+}
+
+pub fn align(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(dimension: Dimension, _type: Type) -> Format {
+        Format::new(dimension, _type, Usage::DATA)
+    }
+
+    // position, normal, tex coord and joints like a quantized skinned mesh
+    fn quantized_layout() -> (Attributes, usize) {
+        interleaved_layout(&[
+            (
+                Slot::Position,
+                format(Dimension::VEC3, Type::FLOAT),
+                Interpretation::Float,
+            ),
+            (
+                Slot::Normal,
+                format(Dimension::VEC3, Type::INT8),
+                Interpretation::Normalized,
+            ),
+            (
+                Slot::TexCoord,
+                format(Dimension::VEC2, Type::UINT16),
+                Interpretation::Normalized,
+            ),
+            (
+                Slot::Joints,
+                format(Dimension::VEC4, Type::UINT8),
+                Interpretation::Integer,
+            ),
+        ])
+    }
+
+    fn quantized_vertex(
+        position: [f32; 3],
+        normal: [i8; 3],
+        uv: [u16; 2],
+        joints: [u8; 4],
+    ) -> Vec<u8> {
+        let mut vertex: Vec<u8> = position
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        vertex.extend(normal.iter().map(|value| *value as u8));
+        vertex.push(0);
+        vertex.extend(uv.iter().flat_map(|value| value.to_ne_bytes()));
+        vertex.extend_from_slice(&joints);
+        vertex
+    }
+
+    #[test]
+    fn interleaved_attributes_are_aligned_to_four_bytes() {
+        let (attributes, vertex_size) = quantized_layout();
+        let offsets: Vec<usize> = attributes
+            .iter()
+            .map(|attribute| attribute.offset)
+            .collect();
+        assert_eq!(offsets, vec![0, 12, 16, 20]);
+        assert_eq!(vertex_size, 24);
+        for attribute in &attributes {
+            assert_eq!(attribute.element_stride(), 24);
+        }
+        assert_eq!(attributes[1].element_offset(2), 12 + 2 * 24);
+    }
+
+    #[test]
+    fn planar_attributes_are_tightly_packed() {
+        let position = Attribute::new(format(Dimension::VEC3, Type::FLOAT), Slot::Position, 0);
+        let tex_coord = Attribute::new(format(Dimension::VEC2, Type::FLOAT), Slot::TexCoord, 36);
+        assert_eq!(position.element_stride(), 12);
+        assert_eq!(tex_coord.element_offset(1), 44);
+
+        // three positions then three tex coords, the positions alone would run into
+        // the tex coords so the smallest count wins
+        assert_eq!(position.element_count(60), 5);
+        assert_eq!(tex_coord.element_count(60), 3);
+        assert_eq!(tex_coord.element_count(40), 0);
+        assert_eq!(vertex_count(&vec![position, tex_coord], 60), 3);
+        assert_eq!(vertex_count(&Vec::new(), 60), 0);
+    }
+
+    #[test]
+    fn interleaved_vertices_count_whole_elements() {
+        let (attributes, vertex_size) = quantized_layout();
+        assert_eq!(vertex_count(&attributes, vertex_size * 3), 3);
+        // the joints of the last vertex are cut off
+        assert_eq!(vertex_count(&attributes, vertex_size * 3 - 1), 2);
+    }
+
+    #[test]
+    fn elements_decode_by_interpretation() {
+        let (attributes, _) = quantized_layout();
+        let mut data = quantized_vertex([1.0, 2.0, 3.0], [127, 0, -64], [0, 65535], [0, 1, 2, 3]);
+        data.extend(quantized_vertex(
+            [-1.0, 0.5, 0.0],
+            [-128, -127, 1],
+            [32768, 1],
+            [4, 250, 0, 255],
+        ));
+
+        assert_eq!(
+            read_element(&data, &attributes[0], 1),
+            [-1.0, 0.5, 0.0, 0.0]
+        );
+
+        let normal = read_element(&data, &attributes[1], 0);
+        assert_eq!(&normal[..3], &[1.0, 0.0, -64.0 / 127.0]);
+        // the most negative value clamps to -1
+        let normal = read_element(&data, &attributes[1], 1);
+        assert_eq!(&normal[..3], &[-1.0, -1.0, 1.0 / 127.0]);
+
+        let tex_coord = read_element(&data, &attributes[2], 1);
+        assert_eq!(&tex_coord[..2], &[32768.0 / 65535.0, 1.0 / 65535.0]);
+
+        assert_eq!(
+            read_element(&data, &attributes[3], 1),
+            [4.0, 250.0, 0.0, 255.0]
+        );
+    }
+
+    #[test]
+    fn floats_are_read_tightly_packed() {
+        let (attributes, _) = quantized_layout();
+        let mut data = quantized_vertex([1.0, 2.0, 3.0], [0; 3], [0, 65535], [0; 4]);
+        data.extend(quantized_vertex(
+            [4.0, 5.0, 6.0],
+            [0; 3],
+            [65535, 0],
+            [0; 4],
+        ));
+
+        assert_eq!(
+            read_floats(&data, &attributes[0], 2),
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+        assert_eq!(
+            read_floats(&data, &attributes[2], 2),
+            vec![0.0, 1.0, 1.0, 0.0]
+        );
+
+        // float interpretation keeps the raw values
+        let raw = Attribute {
+            interpretation: Interpretation::Float,
+            ..attributes[2]
+        };
+        assert_eq!(read_floats(&data, &raw, 1), vec![0.0, 65535.0]);
+    }
+
+    #[test]
+    fn offsets_round_up_to_the_alignment() {
+        assert_eq!(align(0, 4), 0);
+        assert_eq!(align(3, 4), 4);
+        assert_eq!(align(8, 4), 8);
+        assert_eq!(align(9, 8), 16);
+    }
+}
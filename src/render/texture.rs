@@ -11,22 +11,29 @@ use super::stream;
 use gl::types::GLenum;
 use image;
 
-use std::boxed::Box;
-//use std::collections::HashMap;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 const WHITE_COLOR: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
 const BLUE_COLOR: [u8; 4] = [0x80, 0x80, 0xFF, 0xFF];
 const GRAY_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 0xFF];
 const BLACK_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
 
+// EXT_texture_filter_anisotropic, only core since 4.6 so the bindings do not have it
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
 pub enum Type {
     Tex2D,
     TexCUBE,
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum WrapMode {
     REPEAT,
     MIRROR,
@@ -36,7 +43,7 @@ pub enum WrapMode {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Filter {
     NEAREST,
     LINEAR,
@@ -46,11 +53,70 @@ pub enum Filter {
     LINEAR_MIP_LINEAR,
 }
 
-pub fn load_hdr_texture(path: &'static str) -> (Vec<u8>, u32, u32) {
-    let bytes = fs::read(path).unwrap();
-    let decoder = image::hdr::HDRDecoder::new(bytes.as_slice()).unwrap();
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Linear,
+    Srgb,
+}
+
+// channel layouts decoded images come in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PixelLayout {
+    R8,
+    // two channel images are luminance and alpha
+    Rg8,
+    Rgb8,
+    Rgba8,
+    Bgr8,
+    Bgra8,
+    R16,
+    Rg16,
+    Rgb16,
+    Rgba16,
+    Rgb32F,
+}
+
+impl PixelLayout {
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelLayout::R8 | PixelLayout::R16 => 1,
+            PixelLayout::Rg8 | PixelLayout::Rg16 => 2,
+            PixelLayout::Rgb8 | PixelLayout::Bgr8 | PixelLayout::Rgb16 | PixelLayout::Rgb32F => 3,
+            PixelLayout::Rgba8 | PixelLayout::Bgra8 | PixelLayout::Rgba16 => 4,
+        }
+    }
+
+    pub fn bytes_per_channel(&self) -> usize {
+        match self {
+            PixelLayout::R16 | PixelLayout::Rg16 | PixelLayout::Rgb16 | PixelLayout::Rgba16 => 2,
+            PixelLayout::Rgb32F => 4,
+            _ => 1,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.channels() % 2 == 0
+    }
+}
+
+impl Filter {
+    pub fn uses_mipmaps(&self) -> bool {
+        !matches!(self, Filter::NEAREST | Filter::LINEAR)
+    }
+}
+
+pub fn load_hdr_texture(path: &Path) -> image::ImageResult<(Vec<u8>, u32, u32)> {
+    let (mut rgb, width, height) = read_hdr_pixels(path)?;
+    vertical_flip(&mut rgb, width as usize, height as usize, 3 * 4);
+    Ok((rgb, width, height))
+}
+
+// RGB floats, top row first
+fn read_hdr_pixels(path: &Path) -> image::ImageResult<(Vec<u8>, u32, u32)> {
+    let bytes = fs::read(path)?;
+    let decoder = image::hdr::HDRDecoder::new(bytes.as_slice())?;
     let info = decoder.metadata();
-    let rgb_data = decoder.read_image_hdr().unwrap();
+    let rgb_data = decoder.read_image_hdr()?;
 
     let mut rgba: Vec<u8> = Vec::with_capacity(rgb_data.len() * (3 * 4));
     for rgb in rgb_data {
@@ -59,16 +125,19 @@ pub fn load_hdr_texture(path: &'static str) -> (Vec<u8>, u32, u32) {
         rgba.extend_from_slice(&rgb.data[2].to_ne_bytes());
     }
 
-    vertical_flip(&mut rgba, info.width as usize, info.height as usize, 3 * 4);
-
-    (rgba, info.width, info.height)
+    Ok((rgba, info.width, info.height))
 }
 
+#[derive(Copy, Clone, Debug)]
 pub struct TextureDesc {
     pub wrap_s: WrapMode,
     pub wrap_t: WrapMode,
     pub min_filter: Filter,
     pub mag_filter: Filter,
+    // 1.0 disables anisotropic filtering, clamped to what the driver supports
+    pub anisotropy: f32,
+    // color maps are sampled as sRGB so the shader sees linear values
+    pub color_space: ColorSpace,
 }
 
 impl Default for TextureDesc {
@@ -78,12 +147,43 @@ impl Default for TextureDesc {
             wrap_t: WrapMode::CLAMP,
             min_filter: Filter::LINEAR,
             mag_filter: Filter::LINEAR,
+            anisotropy: 1.0,
+            color_space: ColorSpace::Linear,
         }
     }
 }
 
+// anisotropy is compared by its bits so descs can be used as cache keys
+impl PartialEq for TextureDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.wrap_s == other.wrap_s
+            && self.wrap_t == other.wrap_t
+            && self.min_filter == other.min_filter
+            && self.mag_filter == other.mag_filter
+            && self.anisotropy.to_bits() == other.anisotropy.to_bits()
+            && self.color_space == other.color_space
+    }
+}
+
+impl Eq for TextureDesc {}
+
+impl Hash for TextureDesc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.wrap_s.hash(state);
+        self.wrap_t.hash(state);
+        self.min_filter.hash(state);
+        self.mag_filter.hash(state);
+        self.anisotropy.to_bits().hash(state);
+        self.color_space.hash(state);
+    }
+}
+
+// textures can be created on the loader threads, they keep their pixels until
+// upload is called on the thread that owns the GL context
 pub struct Texture {
-    pub id: u32,
+    // 0 until the texture is uploaded
+    id: AtomicU32,
+    pixels: Mutex<Option<Vec<u8>>>,
     pub format: stream::Format,
     pub _type: Type,
     pub width: u32,
@@ -91,8 +191,9 @@ pub struct Texture {
     pub texture_desc: TextureDesc,
 }
 
-pub type TexturePointer = Box<Texture>;
+pub type TexturePointer = Arc<Texture>;
 impl Texture {
+    // creates the GL texture right away, only call it on the main thread
     pub fn new(
         pixels: &Vec<u8>,
         texture_desc: TextureDesc,
@@ -100,7 +201,70 @@ impl Texture {
         height: u32,
         format: stream::Format,
         _type: Type,
-    ) -> Box<Texture> {
+    ) -> TexturePointer {
+        let texture = Texture::deferred(pixels.clone(), texture_desc, width, height, format, _type);
+        texture.upload();
+        texture
+    }
+
+    // keeps the pixels around until upload, safe to call from any thread
+    pub fn deferred(
+        pixels: Vec<u8>,
+        texture_desc: TextureDesc,
+        width: u32,
+        height: u32,
+        format: stream::Format,
+        _type: Type,
+    ) -> TexturePointer {
+        Arc::new(Texture {
+            id: AtomicU32::new(0),
+            pixels: Mutex::new(Some(pixels)),
+            format,
+            _type,
+            width,
+            height,
+            texture_desc,
+        })
+    }
+
+    // wraps a texture the caller already created, like the render targets of the skybox
+    pub fn from_id(
+        id: u32,
+        texture_desc: TextureDesc,
+        width: u32,
+        height: u32,
+        format: stream::Format,
+        _type: Type,
+    ) -> TexturePointer {
+        Arc::new(Texture {
+            id: AtomicU32::new(id),
+            pixels: Mutex::new(None),
+            format,
+            _type,
+            width,
+            height,
+            texture_desc,
+        })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id.load(Ordering::Acquire)
+    }
+
+    pub fn is_uploaded(&self) -> bool {
+        self.id() != 0
+    }
+
+    // creates the GL texture from the pending pixels, returns false when there was
+    // nothing left to upload
+    pub fn upload(&self) -> bool {
+        let pixels = match self.pixels.lock().unwrap().take() {
+            Some(pixels) => pixels,
+            None => return false,
+        };
+
+        let texture_desc = &self.texture_desc;
+        let format = self.format;
         let mut texture_id: u32 = 0;
         unsafe {
             gl::GenTextures(1, &mut texture_id);
@@ -120,80 +284,358 @@ impl Texture {
 
             let data_format = GLenum::from(format.usage);
             let data_type = GLenum::from(format._type);
+            let internal_format = internal_format(texture_desc.color_space, format);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                data_format as i32,
-                width as i32,
-                height as i32,
+                internal_format as i32,
+                self.width as i32,
+                self.height as i32,
                 0,
                 data_format,
                 data_type,
                 pixels.as_ptr().cast(),
             );
+
+            if texture_desc.min_filter.uses_mipmaps() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+
+            if texture_desc.anisotropy > 1.0 {
+                let mut max_anisotropy: f32 = 1.0;
+                gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max_anisotropy);
+                gl::TexParameterf(
+                    gl::TEXTURE_2D,
+                    TEXTURE_MAX_ANISOTROPY,
+                    texture_desc.anisotropy.min(max_anisotropy),
+                );
+            }
         }
 
-        let texture = Texture {
-            id: texture_id,
-            format,
-            _type,
-            width,
-            height,
-            texture_desc,
-        };
-        Box::new(texture)
+        self.id.store(texture_id, Ordering::Release);
+        true
+    }
+
+    // approximate GPU memory, a full mip chain adds a third
+    pub fn byte_size(&self) -> usize {
+        let size = self.width as usize * self.height as usize * self.format.stride();
+        if self.texture_desc.min_filter.uses_mipmaps() {
+            size * 4 / 3
+        } else {
+            size
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        let id = self.id();
+        if id != 0 {
+            unsafe {
+                gl::DeleteTextures(1, &id);
+            }
+        }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TextureSource {
+    // an image embedded in a model file, by model path and image index
+    Embedded(PathBuf, usize),
+    // an image embedded in a model loaded from memory, the id comes from the cache
+    Memory(usize, usize),
+    // an image file on disk, shared by every model that references it
+    File(PathBuf),
+    // separate roughness and metallic files packed into one texture
+    MetallicRoughness(Option<PathBuf>, Option<PathBuf>),
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TextureCacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub textures: usize,
+    pub bytes_resident: usize,
+}
+
+// textures are held weakly, they are freed once no material references them.
+// the cache is shared with the loader threads so every method takes &self.
 pub struct TextureCache {
-    pub blue_texture: Box<Texture>,
-    pub white_texture: Box<Texture>,
-    pub gray_texture: Box<Texture>,
-    pub black_texture: Box<Texture>,
+    pub blue_texture: TexturePointer,
+    pub white_texture: TexturePointer,
+    pub gray_texture: TexturePointer,
+    pub black_texture: TexturePointer,
+    textures: Mutex<HashMap<(TextureSource, TextureDesc), Weak<Texture>>>,
+    next_memory_id: AtomicUsize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
 }
 
 impl TextureCache {
     pub fn new() -> TextureCache {
-        let format = stream::Format::new(
-            stream::Dimension::VEC3,
-            stream::Type::UINT8,
-            stream::Usage::RGBA,
-        );
+        let cache = TextureCache::deferred();
+        for texture in [
+            &cache.blue_texture,
+            &cache.white_texture,
+            &cache.gray_texture,
+            &cache.black_texture,
+        ] {
+            texture.upload();
+        }
+        cache
+    }
 
-        TextureCache {
-            blue_texture: Texture::new(
-                &Vec::from(BLUE_COLOR),
-                TextureDesc::default(),
-                1,
-                1,
-                format.clone(),
-                Type::Tex2D,
-            ),
-            white_texture: Texture::new(
-                &Vec::from(WHITE_COLOR),
-                TextureDesc::default(),
-                1,
-                1,
-                format.clone(),
-                Type::Tex2D,
-            ),
-            gray_texture: Texture::new(
-                &Vec::from(GRAY_COLOR),
-                TextureDesc::default(),
-                1,
-                1,
-                format.clone(),
-                Type::Tex2D,
-            ),
-            black_texture: Texture::new(
-                &Vec::from(BLACK_COLOR),
+    // the fallback textures wait for upload, models can be loaded without a GL context
+    pub fn deferred() -> TextureCache {
+        let fallback = |color: &[u8]| {
+            Texture::deferred(
+                color.to_vec(),
                 TextureDesc::default(),
                 1,
                 1,
-                format.clone(),
+                stream::Format::new(
+                    stream::Dimension::VEC4,
+                    stream::Type::UINT8,
+                    stream::Usage::RGBA,
+                ),
                 Type::Tex2D,
-            ),
+            )
+        };
+
+        TextureCache {
+            blue_texture: fallback(&BLUE_COLOR),
+            white_texture: fallback(&WHITE_COLOR),
+            gray_texture: fallback(&GRAY_COLOR),
+            black_texture: fallback(&BLACK_COLOR),
+            textures: Mutex::new(HashMap::new()),
+            next_memory_id: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    // returns the cached texture or creates it with the given desc. the lock is not
+    // held while decoding, two threads missing on the same image both decode it and
+    // the first one to finish wins.
+    pub fn fetch<F>(
+        &self,
+        source: TextureSource,
+        texture_desc: TextureDesc,
+        create: F,
+    ) -> TexturePointer
+    where
+        F: FnOnce(TextureDesc) -> TexturePointer,
+    {
+        let key = (source, texture_desc);
+        if let Some(texture) = self.lookup(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return texture;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let texture = create(texture_desc);
+
+        let mut textures = self.textures.lock().unwrap();
+        textures.retain(|_, texture| texture.strong_count() > 0);
+        if let Some(existing) = textures.get(&key).and_then(|texture| texture.upgrade()) {
+            return existing;
+        }
+        textures.insert(key, Arc::downgrade(&texture));
+        texture
+    }
+
+    fn lookup(&self, key: &(TextureSource, TextureDesc)) -> Option<TexturePointer> {
+        self.textures
+            .lock()
+            .unwrap()
+            .get(key)
+            .and_then(|texture| texture.upgrade())
+    }
+
+    // models loaded from memory have no path to key their images with
+    pub fn next_memory_id(&self) -> usize {
+        self.next_memory_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn stats(&self) -> TextureCacheStats {
+        let mut stats = TextureCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..TextureCacheStats::default()
+        };
+
+        let textures = self.textures.lock().unwrap();
+        for texture in textures.values().filter_map(|texture| texture.upgrade()) {
+            stats.textures += 1;
+            stats.bytes_resident += texture.byte_size();
         }
+        stats
+    }
+}
+
+// .hdr files keep their float pixels, anything else is read as 8 bit RGBA
+pub fn load_texture_file(
+    path: &Path,
+    texture_desc: TextureDesc,
+) -> image::ImageResult<TexturePointer> {
+    let is_hdr = path
+        .extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("hdr"));
+    let (pixels, format, width, height) = if is_hdr {
+        let (pixels, width, height) = read_hdr_pixels(path)?;
+        let (pixels, format) = convert_pixels(&pixels, PixelLayout::Rgb32F);
+        (pixels, format, width, height)
+    } else {
+        let image = image::open(path)?.to_rgba();
+        let (width, height) = image.dimensions();
+        let (pixels, format) = convert_pixels(&image.into_raw(), PixelLayout::Rgba8);
+        (pixels, format, width, height)
+    };
+    Ok(Texture::deferred(
+        pixels,
+        texture_desc,
+        width,
+        height,
+        format,
+        Type::Tex2D,
+    ))
+}
+
+// packs grayscale roughness and metallic files into the green and blue channels
+// like a glTF metallic roughness map. a missing file leaves its channel at one so
+// the material factor is used as is.
+pub fn load_metallic_roughness_files(
+    roughness: Option<&Path>,
+    metallic: Option<&Path>,
+    texture_desc: TextureDesc,
+) -> image::ImageResult<TexturePointer> {
+    let roughness = match roughness {
+        Some(path) => Some(image::open(path)?.to_luma()),
+        None => None,
+    };
+    let metallic = match metallic {
+        Some(path) => Some(image::open(path)?.to_luma()),
+        None => None,
+    };
+
+    let (width, height) = roughness
+        .as_ref()
+        .or(metallic.as_ref())
+        .map_or((1, 1), |image| image.dimensions());
+
+    // the metallic map is resampled when the sizes do not match
+    let metallic = metallic.map(|image| {
+        if image.dimensions() == (width, height) {
+            image
+        } else {
+            image::imageops::resize(&image, width, height, image::FilterType::Triangle)
+        }
+    });
+
+    let mut pixels: Vec<u8> = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let roughness = roughness
+                .as_ref()
+                .map_or(0xFF, |image| image.get_pixel(x, y).data[0]);
+            let metallic = metallic
+                .as_ref()
+                .map_or(0xFF, |image| image.get_pixel(x, y).data[0]);
+            pixels.extend_from_slice(&[0xFF, roughness, metallic, 0xFF]);
+        }
+    }
+
+    let format = stream::Format::new(
+        stream::Dimension::VEC4,
+        stream::Type::UINT8,
+        stream::Usage::RGBA,
+    );
+    Ok(Texture::deferred(
+        pixels,
+        texture_desc,
+        width,
+        height,
+        format,
+        Type::Tex2D,
+    ))
+}
+
+// repacks decoded pixels as 8 bit RGB or RGBA, the only layouts the sRGB
+// internal formats accept. grayscale is copied into every color channel and
+// 16 bit channels are rounded to 8 bits. float pixels are kept as they are since
+// they can go past 1.
+pub fn convert_pixels(pixels: &[u8], layout: PixelLayout) -> (Vec<u8>, stream::Format) {
+    if layout == PixelLayout::Rgb32F {
+        let format = stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::FLOAT,
+            stream::Usage::RGB,
+        );
+        return (pixels.to_vec(), format);
+    }
+
+    let channels = layout.channels();
+    let bytes_per_channel = layout.bytes_per_channel();
+    let bytes_per_pixel = channels * bytes_per_channel;
+
+    let channel = |pixel: &[u8], index: usize| -> u8 {
+        match bytes_per_channel {
+            1 => pixel[index],
+            _ => {
+                let start = index * 2;
+                let value = u16::from_ne_bytes([pixel[start], pixel[start + 1]]) as u32;
+                ((value * 255 + 32767) / 65535) as u8
+            }
+        }
+    };
+
+    let output_channels = if layout.has_alpha() { 4 } else { 3 };
+    let mut converted: Vec<u8> =
+        Vec::with_capacity(pixels.len() / bytes_per_pixel * output_channels);
+    for pixel in pixels.chunks_exact(bytes_per_pixel) {
+        let rgb = match layout {
+            PixelLayout::R8 | PixelLayout::Rg8 | PixelLayout::R16 | PixelLayout::Rg16 => {
+                let luminance = channel(pixel, 0);
+                [luminance, luminance, luminance]
+            }
+            PixelLayout::Bgr8 | PixelLayout::Bgra8 => {
+                [channel(pixel, 2), channel(pixel, 1), channel(pixel, 0)]
+            }
+            _ => [channel(pixel, 0), channel(pixel, 1), channel(pixel, 2)],
+        };
+
+        converted.extend_from_slice(&rgb);
+        if layout.has_alpha() {
+            converted.push(channel(pixel, channels - 1));
+        }
+    }
+
+    let format = if layout.has_alpha() {
+        stream::Format::new(
+            stream::Dimension::VEC4,
+            stream::Type::UINT8,
+            stream::Usage::RGBA,
+        )
+    } else {
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::UINT8,
+            stream::Usage::RGB,
+        )
+    };
+    (converted, format)
+}
+
+// float data is always linear, 8 bit color is decoded by the sampler when it is sRGB
+fn internal_format(color_space: ColorSpace, format: stream::Format) -> GLenum {
+    match (color_space, format._type, format.usage) {
+        (_, stream::Type::FLOAT, stream::Usage::RGB) => gl::RGB32F,
+        (_, stream::Type::FLOAT, stream::Usage::RGBA) => gl::RGBA32F,
+        (ColorSpace::Srgb, _, stream::Usage::RGB) => gl::SRGB8,
+        (ColorSpace::Srgb, _, stream::Usage::RGBA) => gl::SRGB8_ALPHA8,
+        (ColorSpace::Linear, _, stream::Usage::RGB) => gl::RGB8,
+        (ColorSpace::Linear, _, stream::Usage::RGBA) => gl::RGBA8,
+        _ => GLenum::from(format.usage),
     }
 }
 
@@ -208,3 +650,107 @@ fn vertical_flip(pixels: &mut Vec<u8>, width: usize, height: usize, bytes_per_pi
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb8() -> stream::Format {
+        stream::Format::new(
+            stream::Dimension::VEC3,
+            stream::Type::UINT8,
+            stream::Usage::RGB,
+        )
+    }
+
+    fn rgba8() -> stream::Format {
+        stream::Format::new(
+            stream::Dimension::VEC4,
+            stream::Type::UINT8,
+            stream::Usage::RGBA,
+        )
+    }
+
+    fn u16_bytes(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+
+    fn float_bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn grayscale_is_copied_to_every_channel() {
+        let (pixels, format) = convert_pixels(&[10, 200], PixelLayout::R8);
+        assert_eq!(pixels, vec![10, 10, 10, 200, 200, 200]);
+        assert_eq!(format, rgb8());
+
+        // the second channel is alpha
+        let (pixels, format) = convert_pixels(&[10, 20, 200, 255], PixelLayout::Rg8);
+        assert_eq!(pixels, vec![10, 10, 10, 20, 200, 200, 200, 255]);
+        assert_eq!(format, rgba8());
+    }
+
+    #[test]
+    fn color_pixels_keep_their_channels() {
+        let (pixels, format) = convert_pixels(&[1, 2, 3, 4, 5, 6], PixelLayout::Rgb8);
+        assert_eq!(pixels, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(format, rgb8());
+
+        let (pixels, format) = convert_pixels(&[1, 2, 3, 4], PixelLayout::Rgba8);
+        assert_eq!(pixels, vec![1, 2, 3, 4]);
+        assert_eq!(format, rgba8());
+
+        let (pixels, format) = convert_pixels(&[1, 2, 3, 4], PixelLayout::Bgra8);
+        assert_eq!(pixels, vec![3, 2, 1, 4]);
+        assert_eq!(format, rgba8());
+    }
+
+    #[test]
+    fn sixteen_bit_channels_are_rounded_to_eight_bits() {
+        let (pixels, format) = convert_pixels(
+            &u16_bytes(&[0, 128, 129, 257 * 200, 0xFFFF, 0xFFFF]),
+            PixelLayout::Rgb16,
+        );
+        assert_eq!(pixels, vec![0, 0, 1, 200, 255, 255]);
+        assert_eq!(format, rgb8());
+
+        let (pixels, format) = convert_pixels(&u16_bytes(&[257 * 7, 0x8000]), PixelLayout::Rg16);
+        assert_eq!(pixels, vec![7, 7, 7, 128]);
+        assert_eq!(format, rgba8());
+    }
+
+    #[test]
+    fn float_pixels_are_kept() {
+        let values = [0.5, 2.0, 16.0];
+        let (pixels, format) = convert_pixels(&float_bytes(&values), PixelLayout::Rgb32F);
+        assert_eq!(pixels, float_bytes(&values));
+        assert_eq!(
+            format,
+            stream::Format::new(
+                stream::Dimension::VEC3,
+                stream::Type::FLOAT,
+                stream::Usage::RGB,
+            )
+        );
+    }
+
+    #[test]
+    fn internal_formats_follow_the_color_space() {
+        assert_eq!(internal_format(ColorSpace::Srgb, rgb8()), gl::SRGB8);
+        assert_eq!(internal_format(ColorSpace::Srgb, rgba8()), gl::SRGB8_ALPHA8);
+        assert_eq!(internal_format(ColorSpace::Linear, rgb8()), gl::RGB8);
+        assert_eq!(internal_format(ColorSpace::Linear, rgba8()), gl::RGBA8);
+
+        // floats are never sRGB encoded
+        let (_, format) = convert_pixels(&float_bytes(&[1.0; 3]), PixelLayout::Rgb32F);
+        assert_eq!(internal_format(ColorSpace::Srgb, format), gl::RGB32F);
+        assert_eq!(internal_format(ColorSpace::Linear, format), gl::RGB32F);
+    }
+}
@@ -0,0 +1,109 @@
+// tonemap.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use super::{model, postprocess, shader, texture};
+
+// the order matches the defines in tonemap.fs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Operator {
+    Reinhard,
+    AcesFilmic,
+    AgX,
+    Uncharted2,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 4] = [
+        Operator::Reinhard,
+        Operator::AcesFilmic,
+        Operator::AgX,
+        Operator::Uncharted2,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operator::Reinhard => "Reinhard",
+            Operator::AcesFilmic => "ACES filmic",
+            Operator::AgX => "AgX",
+            Operator::Uncharted2 => "Uncharted 2",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ToneMapSettings {
+    pub operator: Operator,
+    // in stops, 0 leaves the radiance as rendered
+    pub exposure: f32,
+}
+
+impl Default for ToneMapSettings {
+    fn default() -> Self {
+        Self {
+            operator: Operator::AcesFilmic,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl ToneMapSettings {
+    // what the radiance is multiplied with before the curve
+    pub fn exposure_scale(&self) -> f32 {
+        exposure_scale(self.exposure)
+    }
+}
+
+pub fn exposure_scale(ev: f32) -> f32 {
+    2.0f32.powf(ev)
+}
+
+// maps the float scene target to display values and encodes them as sRGB, so it has
+// to draw into a target without GL_FRAMEBUFFER_SRGB
+pub struct ToneMapPass {
+    pipeline: shader::Pipeline,
+}
+
+impl ToneMapPass {
+    pub fn new() -> Result<ToneMapPass, String> {
+        let pipeline = shader::Pipeline::new(
+            "resources/shaders/fullscreen.vs",
+            "resources/shaders/tonemap.fs",
+        )?;
+        Ok(ToneMapPass { pipeline })
+    }
+
+    // draws into whatever framebuffer is bound
+    pub fn render(
+        &self,
+        hdr_texture: &texture::Texture,
+        settings: &ToneMapSettings,
+        model_cache: &model::ModelCache,
+    ) {
+        unsafe {
+            gl::UseProgram(self.pipeline.id);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+        }
+        self.pipeline.set_uniform_1i("hdrTexture\0", 0);
+        self.pipeline
+            .set_uniform_1f("exposure\0", settings.exposure_scale());
+        self.pipeline
+            .set_uniform_1i("toneMapOperator\0", settings.operator as i32);
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, hdr_texture.id());
+        }
+        postprocess::draw_quad(model_cache);
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
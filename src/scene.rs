@@ -0,0 +1,225 @@
+// scene.rs
+//
+// Created on 2026/10/18 by Dante Ruiz
+// Copyright 2026 Dante Ruiz
+//
+// Distributed under the MIT Lisense
+// https://mit-license.org/
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::model::{Model, Shape};
+
+// everything App::run sets up, read from and written back to a json file. every
+// field is optional in the file, missing ones use the defaults below.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Scene {
+    pub window: WindowDesc,
+    pub camera: CameraDesc,
+    // equirectangular hdr image, the environment stays black without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<PathBuf>,
+    pub lights: Vec<LightDesc>,
+    pub entities: Vec<EntityDesc>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct WindowDesc {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for WindowDesc {
+    fn default() -> Self {
+        Self {
+            width: 1080,
+            height: 1080,
+        }
+    }
+}
+
+// angles are in degrees like the camera controls
+#[derive(Serialize, Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct CameraDesc {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+}
+
+impl Default for CameraDesc {
+    fn default() -> Self {
+        Self {
+            position: [-2.0, 5.0, -2.0],
+            yaw: 40.0,
+            pitch: 50.0,
+            fov: 90.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct LightDesc {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl Default for LightDesc {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0, 0.0],
+            color: [1.0, 1.0, 1.0],
+            intensity: 300.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelSource {
+    // any format render::model::load_model has an importer for
+    Path(PathBuf),
+    Shape(Shape),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EntityDesc {
+    pub model: ModelSource,
+    #[serde(default)]
+    pub transform: TransformDesc,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<MaterialOverride>,
+}
+
+// rotation is a quaternion in x, y, z, w order like glTF
+#[derive(Serialize, Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct TransformDesc {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl Default for TransformDesc {
+    fn default() -> Self {
+        Self {
+            translation: [0.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl TransformDesc {
+    pub fn transform(&self) -> iml::Transform {
+        let [x, y, z] = self.translation;
+        let [rotation_x, rotation_y, rotation_z, rotation_w] = self.rotation;
+
+        let mut transform = iml::Transform::default();
+        transform.translation = iml::Point3::new(x, y, z);
+        transform.rotation = iml::Quat::new(rotation_w, rotation_x, rotation_y, rotation_z);
+        transform.scale = iml::Vec3::from(self.scale);
+        transform
+    }
+
+    pub fn from_transform(transform: &iml::Transform) -> TransformDesc {
+        let translation = &transform.translation;
+        let rotation = &transform.rotation;
+        let scale = &transform.scale;
+        TransformDesc {
+            translation: [translation.x, translation.y, translation.z],
+            rotation: [rotation.x, rotation.y, rotation.z, rotation.w],
+            scale: [scale.x, scale.y, scale.z],
+        }
+    }
+}
+
+// replaces the factors of every material of the model, maps are left alone
+#[derive(Serialize, Deserialize, Copy, Clone, Default)]
+#[serde(default)]
+pub struct MaterialOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roughness: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metallic: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emissive: Option<[f32; 3]>,
+}
+
+impl MaterialOverride {
+    pub fn apply(&self, model: &mut Model) {
+        for material in &mut model.materials {
+            if let Some(color) = self.color {
+                material.color = iml::Vec3::from(color);
+            }
+            if let Some(alpha) = self.alpha {
+                material.alpha = alpha;
+            }
+            if let Some(roughness) = self.roughness {
+                material.roughness = roughness;
+            }
+            if let Some(metallic) = self.metallic {
+                material.metallic = metallic;
+            }
+            if let Some(emissive) = self.emissive {
+                material.emissive = iml::Vec3::from(emissive);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "failed to access scene file: {}", error),
+            SceneError::Json(error) => write!(f, "invalid scene file: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(error: serde_json::Error) -> Self {
+        SceneError::Json(error)
+    }
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Scene, SceneError> {
+        let text = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SceneError> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
@@ -11,6 +11,17 @@ use egui;
 use crate::app::*;
 use crate::iml;
 use crate::render::egui_painter::EguiPainter;
+use crate::render::exposure::AutoExposure;
+use crate::render::postprocess::{Chain, Pass};
+use crate::render::texture::TextureCacheStats;
+use crate::render::tonemap::{Operator, ToneMapSettings};
+
+// what the user asked for this frame, handled by the app
+#[derive(Default)]
+pub struct UiActions {
+    pub export_scene: bool,
+    pub save_scene: bool,
+}
 
 pub struct Ui {
     egui_context: egui::Context,
@@ -29,7 +40,16 @@ impl Ui {
         }
     }
 
-    pub fn update(&mut self, raw_input: egui::RawInput, lights: &mut Vec<Light>) {
+    pub fn update(
+        &mut self,
+        raw_input: egui::RawInput,
+        lights: &mut Vec<Light>,
+        tone_map: &mut ToneMapSettings,
+        auto_exposure: &mut AutoExposure,
+        post_process: &mut Chain,
+        texture_stats: &TextureCacheStats,
+    ) -> UiActions {
+        let mut actions = UiActions::default();
         self.egui_context.begin_frame(raw_input);
         egui::Window::new("test").show(&self.egui_context, |ui| {
             ui.label("Lights");
@@ -56,6 +76,98 @@ impl Ui {
                 ui.separator();
                 count += 1;
             }
+            ui.label("Tone mapping");
+            ui.separator();
+            egui::ComboBox::from_label("operator")
+                .selected_text(tone_map.operator.name())
+                .show_ui(ui, |ui| {
+                    for operator in Operator::ALL {
+                        ui.selectable_value(&mut tone_map.operator, operator, operator.name());
+                    }
+                });
+            ui.add(egui::Slider::new(&mut tone_map.exposure, -10.0..=10.0).text("exposure (EV)"));
+            ui.separator();
+
+            ui.label("Auto exposure");
+            ui.separator();
+            let settings = &mut auto_exposure.settings;
+            ui.checkbox(&mut settings.enabled, "enabled");
+            ui.add(egui::Slider::new(&mut settings.speed, 0.1..=10.0).text("speed"));
+            ui.add(egui::Slider::new(&mut settings.min_ev, -16.0..=0.0).text("min EV"));
+            ui.add(egui::Slider::new(&mut settings.max_ev, 0.0..=16.0).text("max EV"));
+            if settings.enabled {
+                ui.label(format!("adapted {:+.2} EV", auto_exposure.exposure()));
+                histogram_plot(ui, auto_exposure);
+            }
+            ui.separator();
+
+            ui.label("Post processing");
+            ui.separator();
+            for stage in &mut post_process.stages {
+                let name = stage.name();
+                ui.checkbox(&mut stage.enabled, name);
+                match &mut stage.pass {
+                    Pass::Bloom(bloom) => {
+                        let settings = &mut bloom.settings;
+                        ui.add(
+                            egui::Slider::new(&mut settings.intensity, 0.0..=0.3).text("intensity"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut settings.radius, 0.001..=0.02).text("radius"),
+                        );
+                    }
+                }
+            }
+            // effects run top to bottom, moving one down swaps it with the next
+            let mut swap = None;
+            let effect_count = post_process.effects.len();
+            for (index, effect) in post_process.effects.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut effect.enabled, effect.name.as_str());
+                    if index > 0 && ui.small_button("up").clicked() {
+                        swap = Some(index - 1);
+                    }
+                    if index + 1 < effect_count && ui.small_button("down").clicked() {
+                        swap = Some(index);
+                    }
+                });
+                if effect.enabled {
+                    for parameter in &mut effect.parameters {
+                        ui.add(
+                            egui::Slider::new(&mut parameter.value, parameter.min..=parameter.max)
+                                .text(parameter.name.as_str()),
+                        );
+                    }
+                }
+            }
+            if let Some(index) = swap {
+                post_process.effects.swap(index, index + 1);
+            }
+            ui.separator();
+
+            ui.label("Textures");
+            ui.separator();
+            ui.label(format!(
+                "{} resident, {:.1} MB",
+                texture_stats.textures,
+                texture_stats.bytes_resident as f32 / (1024.0 * 1024.0)
+            ));
+            ui.label(format!(
+                "{} hits, {} misses",
+                texture_stats.hits, texture_stats.misses
+            ));
+            ui.separator();
+
+            ui.label("Scene");
+            ui.separator();
+            if ui.button("Export scene.glb").clicked() {
+                actions.export_scene = true;
+            }
+            if ui.button("Save scene").clicked() {
+                actions.save_scene = true;
+            }
+            ui.separator();
+
             ui.label("Material");
             ui.separator();
 
@@ -71,6 +183,7 @@ impl Ui {
             //     material.color = iml::Vec3::from(color);
             // }
         });
+        actions
     }
 
     pub fn render(&mut self, width: f32, height: f32) {
@@ -83,3 +196,29 @@ impl Ui {
         )
     }
 }
+
+// log2 luminance on x, pixels on y. the black bin is left out.
+fn histogram_plot(ui: &mut egui::Ui, auto_exposure: &AutoExposure) {
+    let histogram = auto_exposure.histogram();
+    let range = &auto_exposure.range;
+    let width = range.bin_width(histogram.len()) as f64;
+    let bars: Vec<egui::plot::Bar> = histogram
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(bin, count)| {
+            egui::plot::Bar::new(range.bin_center(bin, histogram.len()) as f64, *count as f64)
+                .width(width)
+        })
+        .collect();
+
+    egui::plot::Plot::new("luminance histogram")
+        .height(80.0)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .show_y(false)
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(egui::plot::BarChart::new(bars))
+        });
+}